@@ -65,6 +65,14 @@ pub enum TaskRunStatus {
     Cancelled,
     #[cfg_attr(feature = "orm", sea_orm(string_value = "failed"))]
     Failed,
+    // Never started, because a run it was waiting on failed, was cancelled or
+    // was blocked itself. `blocked_by` on the tree node says which.
+    #[cfg_attr(feature = "orm", sea_orm(string_value = "blocked"))]
+    Blocked,
+    // Was queued or running when the server stopped, so its process, if it
+    // had one, is gone and it will never finish.
+    #[cfg_attr(feature = "orm", sea_orm(string_value = "interrupted"))]
+    Interrupted,
 }
 
 impl TaskRunStatus {
@@ -75,6 +83,8 @@ impl TaskRunStatus {
             TaskRunStatus::Success => "Success",
             TaskRunStatus::Cancelled => "Cancelled",
             TaskRunStatus::Failed => "Failed",
+            TaskRunStatus::Blocked => "Blocked",
+            TaskRunStatus::Interrupted => "Interrupted",
         }
     }
 
@@ -87,7 +97,11 @@ impl TaskRunStatus {
     pub fn is_terminal(self) -> bool {
         matches!(
             self,
-            TaskRunStatus::Success | TaskRunStatus::Failed | TaskRunStatus::Cancelled
+            TaskRunStatus::Success
+                | TaskRunStatus::Failed
+                | TaskRunStatus::Cancelled
+                | TaskRunStatus::Blocked
+                | TaskRunStatus::Interrupted
         )
     }
 
@@ -114,18 +128,170 @@ wire_type! {
         pub title: Option<String>,
         /// The color used for client-side log rendering for this task.
         pub color: Option<String>,
+        /// The directory to run the task in, relative to `task.config.json`. Subtasks inherit it unless they set their own.
+        pub cwd: Option<String>,
         /// The command that the task will run.
         pub command: Option<String>,
+        /// Environment variables to set for the task. Values may reference other variables as `${VAR}`. Subtasks inherit them and can override individual keys.
+        pub env: Option<IndexMap<String, String>>,
+        /// One or more `.env` files to load, relative to `task.config.json`. Values set in `env` take precedence over them.
+        pub env_file: Option<EnvFile>,
         /// Any other task names that this task depends on.
-        pub depends_on: Option<Vec<String>>,
+        pub depends_on: Option<Vec<Dependency>>,
+        /// Whether to start any task in `dependsOn` that has not succeeded and is not already running, rather than waiting for it. Defaults to true. Subtasks inherit it unless they set their own.
+        pub auto_start_dependencies: Option<bool>,
         /// Whether the task is optional. If true, the task will only run if started manually.
         pub optional: Option<bool>,
+        /// How to tell the task is up while it is still running, so that tasks depending on it can start without waiting for it to exit.
+        pub ready: Option<ReadyProbe>,
+        /// The signal sent to the task's process group to stop it, such as `SIGINT`. Defaults to `SIGTERM`.
+        pub stop_signal: Option<String>,
+        /// How long the task has to exit after `stopSignal` before it is sent `SIGKILL`. Defaults to 5000.
+        pub stop_timeout_ms: Option<u64>,
+        /// Whether to run the command under a pseudo-terminal, so that it sees an interactive terminal rather than pipes. Its stdout and stderr then arrive as one stream. Only supported on Linux. Defaults to false.
+        pub pty: Option<bool>,
+        /// Whether clients can send input to the command's stdin while it runs. Otherwise stdin is empty, so a command that reads it sees end-of-file rather than waiting. A task with `pty: true` always takes input. Defaults to false.
+        pub stdin: Option<bool>,
+        /// How long the command may run, in milliseconds, before it is stopped the way cancelling it would and its run fails. Each restart gets the full time again. Defaults to no limit.
+        pub timeout_ms: Option<u64>,
+        /// Whether to start the task's command again when it exits on its own, for long-running tasks such as dev servers.
+        pub restart: Option<RestartPolicy>,
         /// Subtasks of this task. Keys must be unique task names.
         pub tasks: Option<IndexMap<String, Task>>,
         pub depends_on_tasks: Option<IndexMap<String, Task>>,
     }
 }
 
+wire_type! {
+    // `envFile` takes either a single path or a list of them, loaded in order.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(untagged)]
+    pub enum EnvFile {
+        Path(String),
+        Paths(Vec<String>),
+    }
+}
+
+impl EnvFile {
+    pub fn paths(&self) -> &[String] {
+        match self {
+            EnvFile::Path(path) => std::slice::from_ref(path),
+            EnvFile::Paths(paths) => paths,
+        }
+    }
+}
+
+wire_type! {
+    // Every probe that is set has to pass before the run counts as ready. A run
+    // whose probe does not pass within the timeout is stopped and fails.
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ReadyProbe {
+        /// A regular expression matched against each line the task prints.
+        pub log: Option<String>,
+        /// A port on localhost that accepts TCP connections once the task is up.
+        pub port: Option<u16>,
+        /// An `http://` URL that answers with a 2xx status once the task is up.
+        pub http: Option<String>,
+        /// How long to wait for the probes to pass, in milliseconds. Defaults to 60000.
+        pub timeout_ms: Option<u64>,
+    }
+}
+
+wire_type! {
+    // Each restart starts a new attempt of the same run. Once a task runs out
+    // of restarts, its run fails the way it would have without a policy.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RestartPolicy {
+        /// Which exits to restart after.
+        pub on: RestartOn,
+        /// How many times in a row to restart before giving up. Defaults to no limit.
+        pub max_restarts: Option<u32>,
+        /// How long to wait before restarting, in milliseconds, doubled for each restart in a row up to a minute. Defaults to 1000.
+        pub backoff_ms: Option<u64>,
+        /// How long the command has to stay up for it to no longer count as restarting in a row, in milliseconds. Defaults to 60000.
+        pub reset_after_ms: Option<u64>,
+    }
+}
+
+wire_type! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub enum RestartOn {
+        /// Only after the command fails.
+        Failure,
+        /// After every exit, including a successful one.
+        Always,
+    }
+}
+
+wire_type! {
+    // An entry in `dependsOn`: the key of the task depended on, or an object
+    // that also says which of that task's runs count.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(untagged)]
+    pub enum Dependency {
+        Key(String),
+        Detailed(DetailedDependency),
+    }
+}
+
+impl Dependency {
+    pub fn task(&self) -> &str {
+        match self {
+            Dependency::Key(task) => task,
+            Dependency::Detailed(dependency) => &dependency.task,
+        }
+    }
+
+    pub fn satisfied_by(&self) -> SatisfactionPolicy {
+        match self {
+            Dependency::Key(_) => SatisfactionPolicy::default(),
+            Dependency::Detailed(dependency) => dependency.satisfied_by.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<&str> for Dependency {
+    fn from(task: &str) -> Self {
+        Dependency::Key(task.to_string())
+    }
+}
+
+wire_type! {
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct DetailedDependency {
+        /// The key of the task depended on.
+        pub task: String,
+        /// Which of the task's runs count as having satisfied it. Defaults to `always`.
+        pub satisfied_by: Option<SatisfactionPolicy>,
+    }
+}
+
+wire_type! {
+    // Which run of a dependency is good enough. A run counts once it succeeds,
+    // or while it is running once its `ready` probe has passed.
+    //
+    // On the wire: `"always"`, `"sinceServerStart"`, `"sameRootRun"`, or
+    // `{ "withinSeconds": 300 }`.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub enum SatisfactionPolicy {
+        /// Any run, however old.
+        #[default]
+        Always,
+        /// A run since the server last started.
+        SinceServerStart,
+        /// A run in the last this many seconds.
+        WithinSeconds(u64),
+        /// A run started by the same invocation, such as a sibling subtask or a
+        /// dependency started for this run.
+        SameRootRun,
+    }
+}
+
 // Tasks keyed by their fully qualified key (`dev`, `dev:api`, …), in the order
 // they appear in `task.config.json`. Clients use this; the API boundary spells
 // the map out so utoipa does not emit a `$ref` to an unregistered schema.
@@ -139,10 +305,36 @@ wire_type! {
         pub id: String,
         pub task: String,
         pub cwd: String,
+        /// The directory the task's command runs in, once its `cwd` is resolved.
+        pub working_dir: Option<String>,
         pub parent_run_id: Option<String>,
+        /// The run that started this one because it was waiting on this task.
+        pub started_by_run_id: Option<String>,
         pub status: TaskRunStatus,
+        /// Whether the task's `ready` probe has passed since the run last started.
+        pub ready: bool,
         pub updated_at: i64,
         pub waiting_on: Option<String>,
+        /// For a `Blocked` run, the task it was waiting on when that task's run failed, was cancelled or was blocked itself.
+        pub blocked_by: Option<String>,
+        /// The exit code of the run's process, once it has exited. One killed by a signal reports `128 + n`, like a shell.
+        pub exit_code: Option<i32>,
+        /// The signal that ended the run's process, such as `SIGKILL`.
+        pub signal: Option<String>,
+        /// The process ID of the run's latest process, which is also its process group ID.
+        pub pid: Option<u32>,
+        /// When the run's latest process started, in milliseconds since the epoch.
+        pub started_at: Option<i64>,
+        /// When the run's latest process exited, in milliseconds since the epoch.
+        pub finished_at: Option<i64>,
+        /// The optional subtasks enabled for the invocation this run belongs to.
+        pub include_tasks: Vec<String>,
+        /// Starts at 1 and goes up each time the run is restarted. Earlier attempts' logs are kept.
+        pub attempt: u32,
+        /// How many times the task's `restart` policy has restarted the run since it was last started by hand.
+        pub restarts: u32,
+        /// For a run stopped for taking too long, the limit it ran into in milliseconds: its task's `timeoutMs`, or the one its tree was started with.
+        pub timed_out_after_ms: Option<u64>,
         pub children: Vec<TaskRunTreeNode>,
     }
 }
@@ -158,6 +350,50 @@ wire_type! {
         pub is_stderr: bool,
         pub timestamp: i64,
         pub sequence: u64,
+        /// The run's attempt the line was written in.
+        pub attempt: u32,
+    }
+}
+
+wire_type! {
+    // A log line that matched a search.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct LogSearchMatch {
+        pub run_id: String,
+        pub task: String,
+        pub sequence: u64,
+        pub attempt: u32,
+        pub timestamp: i64,
+        pub is_stderr: bool,
+        /// The line, cut down around the match, with each match wrapped in `**`.
+        pub snippet: String,
+    }
+}
+
+// ------------------------------------------------------------------- config
+
+wire_type! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub enum DiagnosticSeverity {
+        Error,
+        Warning,
+    }
+}
+
+wire_type! {
+    // One problem found in `task.config.json`. Lines and columns are 1-based and
+    // point at the offending key or value.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ConfigDiagnostic {
+        pub severity: DiagnosticSeverity,
+        /// Where in the document the problem is, such as `$.tasks.dev.command`.
+        pub path: String,
+        pub line: usize,
+        pub column: usize,
+        pub message: String,
     }
 }
 
@@ -188,11 +424,51 @@ wire_type! {
     }
 }
 
+wire_type! {
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ValidateConfigRequest {
+        #[cfg_attr(feature = "schema", schema(example = "/Users/johndoe/documents/github/example-project"))]
+        pub cwd: String,
+    }
+}
+
+wire_type! {
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SearchLogsRequest {
+        #[cfg_attr(feature = "schema", schema(example = "/Users/johndoe/documents/github/example-project"))]
+        pub cwd: String,
+        /// Text to find anywhere in a line, ignoring case. At least three characters.
+        pub q: String,
+        /// Only lines written by this task.
+        pub task: Option<String>,
+        /// Only lines written at or after this time, in milliseconds since the epoch.
+        pub since: Option<i64>,
+        /// At most this many matches, newest first. Defaults to 100.
+        pub limit: Option<u64>,
+    }
+}
+
 wire_type! {
     #[derive(Debug, Clone, Default, Serialize, Deserialize)]
     #[serde(rename_all = "camelCase")]
     pub struct GetTaskRunLogsRequest {
         pub include_children: Option<bool>,
+        /// Which of the run's attempts to return. Defaults to the latest.
+        pub attempt: Option<u32>,
+        /// Only lines with a greater sequence.
+        pub after_sequence: Option<u64>,
+        /// Only lines with a smaller sequence.
+        pub before_sequence: Option<u64>,
+        /// At most this many lines, starting from the oldest that match.
+        pub limit: Option<u64>,
+        /// At most this many lines, ending with the newest that match. Cannot
+        /// be combined with `limit`.
+        pub tail: Option<u64>,
+        /// Websocket only: the last line a reconnecting client already has.
+        /// The lines after it are sent as log messages instead of a snapshot.
+        pub from_sequence: Option<u64>,
     }
 }
 
@@ -203,6 +479,10 @@ wire_type! {
         pub task: String,
         pub cwd: String,
         pub include_tasks: Option<Vec<String>>,
+        /// Environment variables for this run only, applied on top of each task's own.
+        pub env: Option<IndexMap<String, String>>,
+        /// How long the run and its subtasks may take altogether, in milliseconds. Whatever is still queued or running then is stopped and fails. Ignored when the task is already running, as that run was started by someone else.
+        pub timeout_ms: Option<u64>,
     }
 }
 
@@ -222,6 +502,53 @@ wire_type! {
     }
 }
 
+wire_type! {
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct IncludeTaskRequest {
+        /// The full key of one of the run's optional subtasks, such as `dev:storybook`.
+        pub task: String,
+    }
+}
+
+wire_type! {
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ExcludeTaskRequest {
+        /// The full key of one of the run's optional subtasks, such as `dev:storybook`.
+        pub task: String,
+    }
+}
+
+wire_type! {
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ResizeTaskRunRequest {
+        /// The terminal's width, in columns.
+        pub cols: u16,
+        /// The terminal's height, in rows.
+        pub rows: u16,
+    }
+}
+
+wire_type! {
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SignalTaskRunRequest {
+        /// The signal to send the run's process group, such as `SIGHUP`. The `SIG` prefix and case are optional.
+        pub signal: String,
+    }
+}
+
+wire_type! {
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SendTaskRunInputRequest {
+        /// Written to the task's stdin, or typed at its terminal with `pty: true`, as is. End it with `\n` to submit a line, or `\r` for a terminal.
+        pub data: String,
+    }
+}
+
 // ---------------------------------------------------------- HTTP: responses
 
 wire_type! {
@@ -256,6 +583,10 @@ wire_type! {
     pub struct GetTaskRunLogsResponseBody {
         pub run_id: String,
         pub logs: Vec<TaskRunLogLine>,
+        /// Whether `limit` or `tail` left out lines that match: newer ones for
+        /// `limit`, older ones for `tail`.
+        #[serde(default)]
+        pub has_more: bool,
     }
 }
 
@@ -283,11 +614,89 @@ wire_type! {
     }
 }
 
+wire_type! {
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct IncludeTaskResponseBody {
+        /// The subtask's run, if it was started or queued. Unset while the run has not reached its subtasks, which then start with it.
+        pub run_id: Option<String>,
+    }
+}
+
+wire_type! {
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ExcludeTaskResponseBody {
+        pub cancelled_run_ids: Vec<String>,
+    }
+}
+
+wire_type! {
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SignalTaskRunResponseBody {
+        /// The full name of the signal that was sent, such as `SIGHUP`.
+        pub signal: String,
+    }
+}
+
+wire_type! {
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SendTaskRunInputResponseBody {
+        /// How many bytes were written.
+        pub bytes_written: usize,
+    }
+}
+
+wire_type! {
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ResizeTaskRunResponseBody {
+        /// The terminal's width, in columns.
+        pub cols: u16,
+        /// The terminal's height, in rows.
+        pub rows: u16,
+    }
+}
+
+wire_type! {
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct DeleteTaskRunResponseBody {
+        /// The run and every run under it, which were deleted with their logs.
+        pub deleted_run_ids: Vec<String>,
+    }
+}
+
+wire_type! {
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SearchLogsResponseBody {
+        /// Newest first.
+        pub matches: Vec<LogSearchMatch>,
+        /// Whether `limit` left out older matches.
+        pub has_more: bool,
+    }
+}
+
+wire_type! {
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ValidateConfigResponseBody {
+        /// Every problem found, in document order. Empty when the config is valid.
+        pub diagnostics: Vec<ConfigDiagnostic>,
+    }
+}
+
 /// Every endpoint answers with either its success body or an [`ErrorResponse`],
 /// untagged, so the shape is what distinguishes them.
 macro_rules! response_enum {
     ($name:ident, $body:ident) => {
         wire_type! {
+            // Built once and serialized (or read) straight away, so a large
+            // success body is not worth boxing.
+            #[allow(clippy::large_enum_variant)]
             #[derive(Debug, Clone, Serialize, Deserialize)]
             #[serde(untagged)]
             pub enum $name {
@@ -305,6 +714,14 @@ response_enum!(GetTaskRunLogsResponse, GetTaskRunLogsResponseBody);
 response_enum!(StartTaskResponse, StartTaskResponseBody);
 response_enum!(CancelTaskResponse, CancelTaskResponseBody);
 response_enum!(RestartTaskResponse, RestartTaskResponseBody);
+response_enum!(IncludeTaskResponse, IncludeTaskResponseBody);
+response_enum!(ExcludeTaskResponse, ExcludeTaskResponseBody);
+response_enum!(ResizeTaskRunResponse, ResizeTaskRunResponseBody);
+response_enum!(SendTaskRunInputResponse, SendTaskRunInputResponseBody);
+response_enum!(SignalTaskRunResponse, SignalTaskRunResponseBody);
+response_enum!(DeleteTaskRunResponse, DeleteTaskRunResponseBody);
+response_enum!(SearchLogsResponse, SearchLogsResponseBody);
+response_enum!(ValidateConfigResponse, ValidateConfigResponseBody);
 
 // --------------------------------------------------------------- websockets
 
@@ -326,6 +743,10 @@ pub enum TaskRunLogsStreamMessage {
         #[serde(alias = "runId")]
         run_id: String,
         logs: Vec<TaskRunLogLine>,
+        /// As on `GetTaskRunLogsResponseBody`. Named explicitly for the same
+        /// reason `run_id` is not camelCased.
+        #[serde(rename = "hasMore", default)]
+        has_more: bool,
     },
     Log {
         log: TaskRunLogLine,
@@ -335,6 +756,15 @@ pub enum TaskRunLogsStreamMessage {
     },
 }
 
+/// A message pushed over `GET /api/tasks/{run_id}/input` when it is upgraded
+/// to a websocket. Each text or binary frame the client sends is written to
+/// the task as is; the server only answers when it cannot, and then closes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TaskRunInputStreamMessage {
+    Error { message: String },
+}
+
 /// A message pushed over `GET /api/tasks/{run_id}` when it is upgraded to a
 /// websocket. Each frame is a fresh snapshot of the run tree.
 pub type TaskRunStreamMessage = GetTaskRunResponse;
@@ -357,9 +787,13 @@ mod tests {
         let encoded = serde_json::to_string(&TaskRunLogsStreamMessage::Snapshot {
             run_id: "r1".to_string(),
             logs: Vec::new(),
+            has_more: true,
         })
         .unwrap();
-        assert_eq!(encoded, r#"{"type":"snapshot","run_id":"r1","logs":[]}"#);
+        assert_eq!(
+            encoded,
+            r#"{"type":"snapshot","run_id":"r1","logs":[],"hasMore":true}"#
+        );
 
         for payload in [
             r#"{"type":"snapshot","run_id":"r1","logs":[]}"#,
@@ -378,10 +812,23 @@ mod tests {
             id: "r1".to_string(),
             task: "dev:api".to_string(),
             cwd: "/tmp".to_string(),
+            working_dir: Some("/tmp/apps/api".to_string()),
             parent_run_id: Some("root".to_string()),
+            started_by_run_id: None,
             status: TaskRunStatus::Running,
+            ready: true,
             updated_at: 7,
             waiting_on: None,
+            blocked_by: None,
+            exit_code: None,
+            signal: None,
+            pid: None,
+            started_at: None,
+            finished_at: None,
+            include_tasks: Vec::new(),
+            attempt: 1,
+            restarts: 0,
+            timed_out_after_ms: None,
             children: Vec::new(),
         })
         .unwrap();
@@ -392,6 +839,38 @@ mod tests {
         assert_eq!(decoded.status, TaskRunStatus::Running);
     }
 
+    #[test]
+    fn dependencies_accept_a_key_or_a_policy() {
+        let task: Task = serde_json::from_str(
+            r#"{"dependsOn":["format",{"task":"build","satisfiedBy":{"withinSeconds":300}},
+                {"task":"lint","satisfiedBy":"sameRootRun"},{"task":"gen"}]}"#,
+        )
+        .unwrap();
+        let depends_on = task.depends_on.unwrap();
+
+        assert_eq!(depends_on[0].task(), "format");
+        assert_eq!(depends_on[0].satisfied_by(), SatisfactionPolicy::Always);
+        assert_eq!(depends_on[1].task(), "build");
+        assert_eq!(
+            depends_on[1].satisfied_by(),
+            SatisfactionPolicy::WithinSeconds(300)
+        );
+        assert_eq!(
+            depends_on[2].satisfied_by(),
+            SatisfactionPolicy::SameRootRun
+        );
+        assert_eq!(depends_on[3].satisfied_by(), SatisfactionPolicy::Always);
+    }
+
+    #[test]
+    fn env_file_accepts_one_path_or_many() {
+        let task: Task = serde_json::from_str(r#"{"envFile":".env"}"#).unwrap();
+        assert_eq!(task.env_file.unwrap().paths(), [".env"]);
+
+        let task: Task = serde_json::from_str(r#"{"envFile":[".env",".env.local"]}"#).unwrap();
+        assert_eq!(task.env_file.unwrap().paths(), [".env", ".env.local"]);
+    }
+
     #[test]
     fn untagged_responses_decode_the_error_arm() {
         let decoded: ListTasksResponse = serde_json::from_str(r#"{"message":"nope"}"#).unwrap();
@@ -0,0 +1,99 @@
+//! How fast task output can be taken in: one `INSERT` per line, awaited in
+//! turn as the server used to, against the batched `LogWriter`.
+//!
+//! Run with `cargo bench -p bizi-server --bench log_ingestion`.
+
+use std::time::{Duration, Instant};
+
+use bizi_server::api::log_writer::LogWriter;
+use bizi_server::db::entities::{task_run, task_run_log};
+use bizi_server::db::{connect_sqlite, run_migrations};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, DatabaseConnection};
+use tokio::sync::broadcast;
+
+const LINES: usize = 20_000;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let per_line = measure("one INSERT per line", |db| async move {
+        for index in 0..LINES {
+            task_run_log::ActiveModel {
+                run_id: Set("bench".to_string()),
+                task: Set("bench".to_string()),
+                line: Set(line(index)),
+                is_stderr: Set(false),
+                timestamp: Set(chrono::Utc::now().timestamp_millis()),
+                attempt: Set(1),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await?;
+        }
+        Ok(())
+    })
+    .await?;
+
+    let batched = measure("batched LogWriter", |db| async move {
+        let (events, _) = broadcast::channel(1);
+        let writer = LogWriter::spawn(db, events);
+        for index in 0..LINES {
+            writer
+                .append("bench".to_string(), "bench".to_string(), line(index), false)
+                .await?;
+        }
+        writer.flush().await;
+        Ok(())
+    })
+    .await?;
+
+    println!(
+        "batched LogWriter is {:.1}x faster",
+        per_line.as_secs_f64() / batched.as_secs_f64()
+    );
+    Ok(())
+}
+
+fn line(index: usize) -> String {
+    format!("[webpack] compiled module ./src/components/{index}.tsx in 12 ms")
+}
+
+/// Times `write` against a fresh database on disk, where commits cost what
+/// they do for the server.
+async fn measure<F, Fut>(name: &str, write: F) -> anyhow::Result<Duration>
+where
+    F: FnOnce(DatabaseConnection) -> Fut,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    let dir = std::env::temp_dir().join(format!("bizi-bench-{}", nanoid::nanoid!()));
+    std::fs::create_dir_all(&dir)?;
+    let db = connect_sqlite(&format!(
+        "sqlite://{}?mode=rwc",
+        dir.join("bizi.db").display()
+    ))
+    .await?;
+    run_migrations(&db).await?;
+    task_run::ActiveModel {
+        id: Set("bench".to_string()),
+        task: Set("bench".to_string()),
+        cwd: Set(dir.display().to_string()),
+        status: Set(task_run::TaskRunStatus::Running),
+        updated_at: Set(0),
+        attempt: Set(1),
+        ..Default::default()
+    }
+    .insert(&db)
+    .await?;
+
+    let started = Instant::now();
+    write(db.clone()).await?;
+    let elapsed = started.elapsed();
+    db.close().await?;
+    std::fs::remove_dir_all(&dir)?;
+
+    println!(
+        "{name}: {LINES} lines in {:.2?} ({:.0} lines/s)",
+        elapsed,
+        LINES as f64 / elapsed.as_secs_f64()
+    );
+    Ok(elapsed)
+}
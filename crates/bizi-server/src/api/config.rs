@@ -0,0 +1,62 @@
+pub use bizi_api::{
+    ConfigDiagnostic, DiagnosticSeverity, ValidateConfigRequest, ValidateConfigResponse,
+    ValidateConfigResponseBody,
+};
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+
+use crate::{
+    api::{AppState, error::ErrorResponse},
+    config::{config_path, diagnose_config},
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/config/validate",
+    params(
+        ("cwd" = String, Query, description = "The current working directory to load the task config from"),
+    ),
+    responses(
+        (status = 200, description = "Success", body = ValidateConfigResponse),
+        (status = 404, description = "Not Found", body = ErrorResponse),
+        (status = 500, description = "Internal Server Error", body = ErrorResponse),
+    )
+)]
+pub async fn validate_config(
+    State(_state): State<AppState>,
+    Query(payload): Query<ValidateConfigRequest>,
+) -> (StatusCode, Json<ValidateConfigResponse>) {
+    let source = match tokio::fs::read_to_string(config_path(&payload.cwd)).await {
+        Ok(source) => source,
+        Err(e) => {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(ValidateConfigResponse::Error(ErrorResponse {
+                        message: "Task config file not found".to_string(),
+                    })),
+                );
+            }
+
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ValidateConfigResponse::Error(ErrorResponse {
+                    message: "Failed to read task config file".to_string(),
+                })),
+            );
+        }
+    };
+
+    (
+        StatusCode::OK,
+        Json(ValidateConfigResponse::Success(
+            ValidateConfigResponseBody {
+                diagnostics: diagnose_config(&source),
+            },
+        )),
+    )
+}
@@ -0,0 +1,317 @@
+//! Input for running tasks: what a client sends is written to the stdin of a
+//! task with `stdin: true`, or typed at its terminal for `pty: true`.
+
+pub use bizi_api::{
+    SendTaskRunInputRequest, SendTaskRunInputResponse, SendTaskRunInputResponseBody,
+    TaskRunInputStreamMessage,
+};
+
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    Json,
+    extract::{
+        Path, State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    http::StatusCode,
+    response::Response,
+};
+use sea_orm::EntityTrait;
+use tokio::io::AsyncWriteExt;
+use tokio::process::ChildStdin;
+use tokio::sync::Mutex;
+
+use crate::{
+    api::{AppState, error::ErrorResponse, pty::Pty, tasks::send_ws_json},
+    db::entities::task_run,
+};
+
+// A task that never reads its stdin fills the pipe, and a write would wait on
+// it forever.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where a running process reads its input from. Clones share it.
+#[derive(Clone)]
+pub enum ProcessInput {
+    Stdin(Arc<Mutex<ChildStdin>>),
+    Pty(Pty),
+}
+
+impl ProcessInput {
+    pub fn stdin(stdin: ChildStdin) -> ProcessInput {
+        ProcessInput::Stdin(Arc::new(Mutex::new(stdin)))
+    }
+
+    pub async fn write(&self, bytes: &[u8]) -> io::Result<()> {
+        let write = async {
+            match self {
+                ProcessInput::Stdin(stdin) => {
+                    let mut stdin = stdin.lock().await;
+                    stdin.write_all(bytes).await?;
+                    stdin.flush().await
+                }
+                ProcessInput::Pty(pty) => pty.write_all(bytes).await,
+            }
+        };
+        tokio::time::timeout(WRITE_TIMEOUT, write)
+            .await
+            .unwrap_or_else(|_| {
+                Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "the task is not reading its input",
+                ))
+            })
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/tasks/{run_id}/input",
+    params(
+        ("run_id" = String, Path, description = "The running task run to write to"),
+    ),
+    request_body = SendTaskRunInputRequest,
+    responses(
+        (status = 200, description = "Success", body = SendTaskRunInputResponse),
+        (status = 404, description = "Not Found", body = ErrorResponse),
+        (status = 409, description = "Conflict", body = ErrorResponse),
+        (status = 500, description = "Internal Server Error", body = ErrorResponse),
+    )
+)]
+pub async fn send_task_run_input(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    Json(payload): Json<SendTaskRunInputRequest>,
+) -> (StatusCode, Json<SendTaskRunInputResponse>) {
+    let result = match find_process_input(&state, &run_id).await {
+        Ok(input) => write_input(&input, payload.data.as_bytes()).await,
+        Err(err) => Err(err),
+    };
+    match result {
+        Ok(()) => (
+            StatusCode::OK,
+            Json(SendTaskRunInputResponse::Success(
+                SendTaskRunInputResponseBody {
+                    bytes_written: payload.data.len(),
+                },
+            )),
+        ),
+        Err((status, message)) => (
+            status,
+            Json(SendTaskRunInputResponse::Error(ErrorResponse { message })),
+        ),
+    }
+}
+
+/// The websocket variant of `send_task_run_input`, for clients that forward
+/// keystrokes as they are typed. Not part of the OpenAPI document.
+pub async fn stream_task_run_input(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    ws.on_upgrade(move |socket| forward_task_run_input(socket, state, run_id))
+}
+
+async fn forward_task_run_input(mut socket: WebSocket, state: AppState, run_id: String) {
+    let result = async {
+        let input = find_process_input(&state, &run_id).await?;
+        while let Some(Ok(message)) = socket.recv().await {
+            let bytes = match message {
+                Message::Text(text) => text.into_bytes(),
+                Message::Binary(bytes) => bytes,
+                Message::Close(_) => break,
+                Message::Ping(_) | Message::Pong(_) => continue,
+            };
+            write_input(&input, &bytes).await?;
+        }
+        Ok(())
+    }
+    .await;
+
+    if let Err((_, message)) = result {
+        let _ = send_ws_json(&mut socket, &TaskRunInputStreamMessage::Error { message }).await;
+        let _ = socket.send(Message::Close(None)).await;
+    }
+}
+
+async fn find_process_input(
+    state: &AppState,
+    run_id: &str,
+) -> Result<ProcessInput, (StatusCode, String)> {
+    let input = state
+        .running_processes
+        .lock()
+        .await
+        .get(run_id)
+        .and_then(|entry| entry.input.clone());
+    if let Some(input) = input {
+        return Ok(input);
+    }
+
+    match task_run::Entity::find_by_id(run_id.to_string())
+        .one(&state.db)
+        .await
+    {
+        // Also the case for a task without `stdin: true`, or a process adopted
+        // after the server restarted, whose stdin is out of reach.
+        Ok(Some(_)) => Err((
+            StatusCode::CONFLICT,
+            "Task run is not running a process that takes input".to_string(),
+        )),
+        Ok(None) => Err((StatusCode::NOT_FOUND, "Task run not found".to_string())),
+        Err(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to load task run".to_string(),
+        )),
+    }
+}
+
+async fn write_input(input: &ProcessInput, bytes: &[u8]) -> Result<(), (StatusCode, String)> {
+    input.write(bytes).await.map_err(|err| match err.kind() {
+        io::ErrorKind::BrokenPipe | io::ErrorKind::TimedOut => (
+            StatusCode::CONFLICT,
+            format!("Failed to write to the task: {}", err),
+        ),
+        _ => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to write to the task: {}", err),
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::RunningProcessEntry;
+    use crate::api::test_support::{insert_run, serve, test_state};
+    use crate::db::entities::task_run::TaskRunStatus;
+    use futures_util::{SinkExt, StreamExt};
+    use std::process::Stdio;
+    use tokio::io::AsyncReadExt;
+    use tokio::process::{Child, Command};
+    use tokio::sync::oneshot;
+    use tokio_tungstenite::tungstenite;
+
+    /// A running `head -n1` registered as the process of run `run_id`.
+    async fn running_head(state: &AppState, run_id: &str) -> Child {
+        insert_run(&state.db, run_id, "echo", TaskRunStatus::Running).await;
+        let mut child = Command::new("head")
+            .arg("-n1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let (cancel_tx, _) = oneshot::channel();
+        let (_, stopped_rx) = oneshot::channel();
+        state.running_processes.lock().await.insert(
+            run_id.to_string(),
+            RunningProcessEntry {
+                execution_id: "e1".to_string(),
+                cancel_tx,
+                stopped_rx,
+                pgid: None,
+                pty: None,
+                input: child.stdin.take().map(ProcessInput::stdin),
+            },
+        );
+        child
+    }
+
+    async fn output(mut child: Child) -> String {
+        let mut output = String::new();
+        child
+            .stdout
+            .take()
+            .unwrap()
+            .read_to_string(&mut output)
+            .await
+            .unwrap();
+        output
+    }
+
+    async fn send(state: &AppState, run_id: &str, data: &str) -> StatusCode {
+        let (status, _) = send_task_run_input(
+            State(state.clone()),
+            Path(run_id.to_string()),
+            Json(SendTaskRunInputRequest {
+                data: data.to_string(),
+            }),
+        )
+        .await;
+        status
+    }
+
+    #[tokio::test]
+    async fn sends_input_only_to_a_run_whose_process_takes_it() {
+        let state = test_state().await;
+        let child = running_head(&state, "r1").await;
+        insert_run(&state.db, "r2", "serve", TaskRunStatus::Running).await;
+
+        assert_eq!(send(&state, "r1", "hello\n").await, StatusCode::OK);
+        assert_eq!(output(child).await, "hello\n");
+        assert_eq!(send(&state, "r2", "hello\n").await, StatusCode::CONFLICT);
+        assert_eq!(
+            send(&state, "missing", "hello\n").await,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn forwards_websocket_messages_as_input() {
+        let state = test_state().await;
+        let child = running_head(&state, "r1").await;
+        insert_run(&state.db, "r2", "serve", TaskRunStatus::Running).await;
+        let addr = serve(state.clone()).await;
+
+        let (mut socket, _) =
+            tokio_tungstenite::connect_async(format!("ws://{addr}/api/tasks/r1/input"))
+                .await
+                .unwrap();
+        socket
+            .send(tungstenite::Message::text("hello\n"))
+            .await
+            .unwrap();
+        assert_eq!(output(child).await, "hello\n");
+
+        let (mut socket, _) =
+            tokio_tungstenite::connect_async(format!("ws://{addr}/api/tasks/r2/input"))
+                .await
+                .unwrap();
+        let message = socket.next().await.unwrap().unwrap();
+        match serde_json::from_str(message.to_text().unwrap()).unwrap() {
+            TaskRunInputStreamMessage::Error { message } => {
+                assert!(message.contains("not running a process that takes input"))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn writes_reach_the_process_and_fail_once_it_stops_reading() {
+        let mut child = tokio::process::Command::new("head")
+            .arg("-n1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let input = ProcessInput::stdin(child.stdin.take().unwrap());
+
+        input.write(b"hello\n").await.unwrap();
+        let mut output = String::new();
+        child
+            .stdout
+            .take()
+            .unwrap()
+            .read_to_string(&mut output)
+            .await
+            .unwrap();
+        assert_eq!(output, "hello\n");
+        child.wait().await.unwrap();
+
+        let err = input.write(b"again\n").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+}
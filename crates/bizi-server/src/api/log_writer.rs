@@ -0,0 +1,244 @@
+//! Persists task output off the path that reads it. A line gets its sequence
+//! and goes out to subscribers as soon as it is read; a single writer task
+//! then commits lines in batches, so a chatty process never waits on SQLite.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use sea_orm::{
+    ActiveValue::Set, DatabaseConnection, DbErr, EntityTrait, QuerySelect, TransactionTrait,
+    sea_query::Expr,
+};
+use tokio::sync::{OnceCell, broadcast, mpsc, oneshot};
+use tokio::time::Instant;
+
+use crate::{
+    api::tasks::TaskRunLogLine,
+    db::entities::{task_run, task_run_log},
+};
+
+/// How long a line may sit in memory before it is committed.
+pub const FLUSH_INTERVAL: Duration = Duration::from_millis(50);
+/// Lines committed in one transaction at most.
+pub const MAX_BATCH_SIZE: usize = 1000;
+// Lines queued for the writer task before appending waits for it to catch up.
+const QUEUE_CAPACITY: usize = 16 * 1024;
+// Kept well under SQLite's limit on bound parameters per statement.
+const ROWS_PER_INSERT: usize = 500;
+
+enum Command {
+    Write(TaskRunLogLine),
+    Flush(oneshot::Sender<()>),
+}
+
+#[derive(Clone)]
+pub struct LogWriter {
+    db: DatabaseConnection,
+    events: broadcast::Sender<TaskRunLogLine>,
+    commands: mpsc::Sender<Command>,
+    /// The sequence the next line gets, read from the database on first use.
+    next_sequence: Arc<OnceCell<Mutex<u64>>>,
+    /// Each run's current attempt, cached so tagging a line takes no query.
+    attempts: Arc<Mutex<HashMap<String, u32>>>,
+}
+
+impl LogWriter {
+    /// Starts the writer task, which commits lines to `db` until every handle
+    /// to it is dropped.
+    pub fn spawn(db: DatabaseConnection, events: broadcast::Sender<TaskRunLogLine>) -> LogWriter {
+        let (commands, queued) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(write_batches(db.clone(), queued));
+        LogWriter {
+            db,
+            events,
+            commands,
+            next_sequence: Arc::new(OnceCell::new()),
+            attempts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Tags the line with its run's current attempt and the next sequence,
+    /// broadcasts it and queues it to be committed. A line for a run that does
+    /// not exist is dropped.
+    pub async fn append(
+        &self,
+        run_id: String,
+        task: String,
+        line: String,
+        is_stderr: bool,
+    ) -> Result<(), DbErr> {
+        let Some(attempt) = self.attempt(&run_id).await? else {
+            return Ok(());
+        };
+        let next_sequence = self
+            .next_sequence
+            .get_or_try_init(|| async {
+                let max_id: Option<Option<i64>> = task_run_log::Entity::find()
+                    .select_only()
+                    .column_as(Expr::col(task_run_log::Column::Id).max(), "max_id")
+                    .into_tuple()
+                    .one(&self.db)
+                    .await?;
+                Ok::<_, DbErr>(Mutex::new(max_id.flatten().unwrap_or(0) as u64 + 1))
+            })
+            .await?;
+
+        let log_line = {
+            let mut next_sequence = next_sequence.lock().unwrap();
+            let log_line = TaskRunLogLine {
+                run_id,
+                task,
+                line,
+                is_stderr,
+                timestamp: chrono::Utc::now().timestamp_millis(),
+                sequence: *next_sequence,
+                attempt,
+            };
+            *next_sequence += 1;
+            // Sent while holding the lock so subscribers see sequences in order.
+            let _ = self.events.send(log_line.clone());
+            log_line
+        };
+        // This only fails once the writer task is gone with the runtime.
+        let _ = self.commands.send(Command::Write(log_line)).await;
+        Ok(())
+    }
+
+    /// Waits until every line appended so far is committed.
+    pub async fn flush(&self) {
+        let (done_tx, done_rx) = oneshot::channel();
+        if self.commands.send(Command::Flush(done_tx)).await.is_ok() {
+            let _ = done_rx.await;
+        }
+    }
+
+    /// Drops what is known of the runs' attempts, so lines they write from
+    /// now on are tagged with the one they just started.
+    pub fn forget_attempts(&self, run_ids: &[String]) {
+        let mut attempts = self.attempts.lock().unwrap();
+        for run_id in run_ids {
+            attempts.remove(run_id);
+        }
+    }
+
+    async fn attempt(&self, run_id: &str) -> Result<Option<u32>, DbErr> {
+        if let Some(attempt) = self.attempts.lock().unwrap().get(run_id) {
+            return Ok(Some(*attempt));
+        }
+        let attempt: Option<i32> = task_run::Entity::find_by_id(run_id.to_string())
+            .select_only()
+            .column(task_run::Column::Attempt)
+            .into_tuple()
+            .one(&self.db)
+            .await?;
+        let Some(attempt) = attempt else {
+            return Ok(None);
+        };
+        self.attempts
+            .lock()
+            .unwrap()
+            .insert(run_id.to_string(), attempt as u32);
+        Ok(Some(attempt as u32))
+    }
+}
+
+async fn write_batches(db: DatabaseConnection, mut queued: mpsc::Receiver<Command>) {
+    let mut batch = Vec::new();
+    let mut flushed = Vec::new();
+
+    // Nothing is pending while waiting for the first line of a batch; after
+    // that, the batch fills until it is full, flushed or `FLUSH_INTERVAL` is up.
+    while let Some(mut command) = queued.recv().await {
+        let deadline = Instant::now() + FLUSH_INTERVAL;
+        loop {
+            match command {
+                Command::Write(log_line) => batch.push(log_line),
+                Command::Flush(done) => {
+                    flushed.push(done);
+                    break;
+                }
+            }
+            if batch.len() >= MAX_BATCH_SIZE {
+                break;
+            }
+            match tokio::time::timeout_at(deadline, queued.recv()).await {
+                Ok(Some(next)) => command = next,
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        if let Err(err) = insert_batch(&db, &batch).await {
+            eprintln!("Failed to persist {} task log lines: {}", batch.len(), err);
+        }
+        batch.clear();
+        for done in flushed.drain(..) {
+            let _ = done.send(());
+        }
+    }
+}
+
+async fn insert_batch(db: &DatabaseConnection, batch: &[TaskRunLogLine]) -> Result<(), DbErr> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+
+    let txn = db.begin().await?;
+    for chunk in batch.chunks(ROWS_PER_INSERT) {
+        task_run_log::Entity::insert_many(chunk.iter().map(|log_line| task_run_log::ActiveModel {
+            id: Set(log_line.sequence as i64),
+            run_id: Set(log_line.run_id.clone()),
+            task: Set(log_line.task.clone()),
+            line: Set(log_line.line.clone()),
+            is_stderr: Set(log_line.is_stderr),
+            timestamp: Set(log_line.timestamp),
+            attempt: Set(log_line.attempt as i32),
+        }))
+        .exec(&txn)
+        .await?;
+    }
+    txn.commit().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_support::{insert_run, test_state};
+    use crate::db::entities::task_run::TaskRunStatus;
+
+    #[tokio::test]
+    async fn broadcasts_right_away_and_numbers_on_from_committed_lines() {
+        let db = test_state().await.db;
+        insert_run(&db, "r1", "dev", TaskRunStatus::Running).await;
+
+        let (events, mut received) = broadcast::channel(16);
+        let writer = LogWriter::spawn(db.clone(), events.clone());
+        for line in ["one", "two"] {
+            writer
+                .append("r1".into(), "dev".into(), line.into(), false)
+                .await
+                .unwrap();
+        }
+        assert_eq!(received.recv().await.unwrap().sequence, 1);
+        assert_eq!(received.recv().await.unwrap().sequence, 2);
+        writer.flush().await;
+
+        // As a server started on the same database would.
+        let writer = LogWriter::spawn(db.clone(), events);
+        writer
+            .append("r1".into(), "dev".into(), "three".into(), false)
+            .await
+            .unwrap();
+        assert_eq!(received.recv().await.unwrap().sequence, 3);
+        writer.flush().await;
+
+        let committed = task_run_log::Entity::find().all(&db).await.unwrap();
+        assert_eq!(
+            committed
+                .iter()
+                .map(|log| (log.id, log.line.as_str()))
+                .collect::<Vec<_>>(),
+            [(1, "one"), (2, "two"), (3, "three")]
+        );
+    }
+}
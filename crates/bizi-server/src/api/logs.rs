@@ -0,0 +1,201 @@
+pub use bizi_api::{LogSearchMatch, SearchLogsRequest, SearchLogsResponse, SearchLogsResponseBody};
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, Statement, Value};
+
+use crate::api::{AppState, error::ErrorResponse};
+
+const DEFAULT_SEARCH_LIMIT: u64 = 100;
+const MAX_SEARCH_LIMIT: u64 = 1000;
+// The trigram index has nothing to look up for anything shorter.
+const MIN_SEARCH_CHARS: usize = 3;
+
+#[utoipa::path(
+    get,
+    path = "/api/logs/search",
+    params(
+        ("cwd" = String, Query, description = "The project whose runs to search"),
+        ("q" = String, Query, description = "Text to find anywhere in a line, ignoring case. At least three characters"),
+        ("task" = Option<String>, Query, description = "Only search lines written by this task"),
+        ("since" = Option<i64>, Query, description = "Only search lines written at or after this time, in milliseconds since the epoch"),
+        ("limit" = Option<u64>, Query, description = "Return at most this many matches, newest first. Defaults to 100, and at most 1000"),
+    ),
+    responses(
+        (status = 200, description = "Success", body = SearchLogsResponse),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 500, description = "Internal Server Error", body = ErrorResponse),
+    )
+)]
+pub async fn search_logs(
+    State(state): State<AppState>,
+    Query(payload): Query<SearchLogsRequest>,
+) -> (StatusCode, Json<SearchLogsResponse>) {
+    if payload.q.chars().count() < MIN_SEARCH_CHARS {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(SearchLogsResponse::Error(ErrorResponse {
+                message: format!("Search for at least {MIN_SEARCH_CHARS} characters"),
+            })),
+        );
+    }
+
+    // Lines already sent to subscribers may not be committed yet.
+    state.log_writer.flush().await;
+    match search_task_logs(&state.db, &payload).await {
+        Ok(body) => (StatusCode::OK, Json(SearchLogsResponse::Success(body))),
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(SearchLogsResponse::Error(ErrorResponse {
+                message: "Failed to search task logs".to_string(),
+            })),
+        ),
+    }
+}
+
+async fn search_task_logs(
+    db: &DatabaseConnection,
+    request: &SearchLogsRequest,
+) -> Result<SearchLogsResponseBody, DbErr> {
+    let limit = request
+        .limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .min(MAX_SEARCH_LIMIT);
+
+    let mut sql = String::from(
+        "SELECT logs.run_id, logs.task, logs.id, logs.attempt, logs.timestamp, logs.is_stderr,
+            snippet(task_run_logs_fts, 0, '**', '**', '…', 48) AS snippet
+        FROM task_run_logs_fts
+        JOIN task_run_logs AS logs ON logs.id = task_run_logs_fts.rowid
+        JOIN task_runs AS runs ON runs.id = logs.run_id
+        WHERE task_run_logs_fts MATCH ? AND runs.cwd = ?",
+    );
+    let mut values: Vec<Value> = vec![fts_phrase(&request.q).into(), request.cwd.clone().into()];
+    if let Some(task) = &request.task {
+        sql.push_str(" AND logs.task = ?");
+        values.push(task.clone().into());
+    }
+    if let Some(since) = request.since {
+        sql.push_str(" AND logs.timestamp >= ?");
+        values.push(since.into());
+    }
+    // One extra row tells whether `limit` cut anything off.
+    sql.push_str(" ORDER BY logs.id DESC LIMIT ?");
+    values.push((limit.saturating_add(1) as i64).into());
+
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            sql,
+            values,
+        ))
+        .await?;
+    let has_more = rows.len() as u64 > limit;
+
+    let matches = rows
+        .iter()
+        .take(limit as usize)
+        .map(|row| {
+            Ok(LogSearchMatch {
+                run_id: row.try_get("", "run_id")?,
+                task: row.try_get("", "task")?,
+                sequence: row.try_get::<i64>("", "id")? as u64,
+                attempt: row.try_get::<i32>("", "attempt")? as u32,
+                timestamp: row.try_get("", "timestamp")?,
+                is_stderr: row.try_get("", "is_stderr")?,
+                snippet: row.try_get("", "snippet")?,
+            })
+        })
+        .collect::<Result<Vec<_>, DbErr>>()?;
+    Ok(SearchLogsResponseBody { matches, has_more })
+}
+
+/// Quotes `query` as a single FTS5 string, so it is matched as literal text
+/// rather than parsed as query syntax.
+fn fts_phrase(query: &str) -> String {
+    format!("\"{}\"", query.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_support::{new_run, test_state};
+    use crate::db::entities::task_run::{self, TaskRunStatus};
+    use sea_orm::{ActiveModelTrait, ActiveValue::Set};
+
+    #[tokio::test]
+    async fn finds_lines_containing_the_text_in_one_project() {
+        let state = test_state().await;
+        for (id, task, cwd) in [
+            ("api-1", "dev:api", "/repo"),
+            ("web-1", "dev:web", "/repo"),
+            ("other-1", "dev:api", "/other"),
+        ] {
+            task_run::ActiveModel {
+                cwd: Set(cwd.to_string()),
+                ..new_run(id, task, TaskRunStatus::Running)
+            }
+            .insert(&state.db)
+            .await
+            .unwrap();
+        }
+        for (run_id, task, line) in [
+            ("api-1", "dev:api", "listening on :3000"),
+            (
+                "api-1",
+                "dev:api",
+                "Error: listen EADDRINUSE: address already in use",
+            ),
+            ("web-1", "dev:web", "proxy error: eaddrinuse"),
+            ("other-1", "dev:api", "Error: listen EADDRINUSE"),
+        ] {
+            state
+                .log_writer
+                .append(run_id.into(), task.into(), line.into(), true)
+                .await
+                .unwrap();
+        }
+        state.log_writer.flush().await;
+
+        let search = |q: &str, task: Option<&str>| SearchLogsRequest {
+            cwd: "/repo".to_string(),
+            q: q.to_string(),
+            task: task.map(str::to_string),
+            since: None,
+            limit: None,
+        };
+
+        let body = search_task_logs(&state.db, &search("AddrInUse", None))
+            .await
+            .unwrap();
+        assert_eq!(
+            body.matches
+                .iter()
+                .map(|found| (found.run_id.as_str(), found.sequence))
+                .collect::<Vec<_>>(),
+            [("web-1", 3), ("api-1", 2)]
+        );
+        assert_eq!(
+            body.matches[1].snippet,
+            "Error: listen E**ADDRINUSE**: address already in use"
+        );
+
+        let body = search_task_logs(&state.db, &search("addrinuse", Some("dev:api")))
+            .await
+            .unwrap();
+        assert_eq!(body.matches.len(), 1);
+
+        // Query syntax is matched as plain text.
+        let body = search_task_logs(&state.db, &search("error: \"listen", None))
+            .await
+            .unwrap();
+        assert!(body.matches.is_empty());
+        let body = search_task_logs(&state.db, &search("error: listen", None))
+            .await
+            .unwrap();
+        assert_eq!(body.matches.len(), 1);
+    }
+}
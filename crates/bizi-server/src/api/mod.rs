@@ -1,53 +1,98 @@
 use axum::routing::post;
 use axum::{Router, routing::get};
 use sea_orm::DatabaseConnection;
-use std::{
-    collections::{HashMap, HashSet},
-    sync::Arc,
-};
+use std::{collections::HashMap, sync::Arc};
 use tokio::sync::{Mutex, broadcast, oneshot};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
+use crate::api::config::{
+    ConfigDiagnostic, DiagnosticSeverity, ValidateConfigRequest, ValidateConfigResponse,
+    ValidateConfigResponseBody, validate_config,
+};
 use crate::api::error::ErrorResponse;
+use crate::api::input::{
+    SendTaskRunInputRequest, SendTaskRunInputResponse, SendTaskRunInputResponseBody,
+    send_task_run_input, stream_task_run_input,
+};
+use crate::api::log_writer::LogWriter;
+use crate::api::logs::{
+    LogSearchMatch, SearchLogsRequest, SearchLogsResponse, SearchLogsResponseBody, search_logs,
+};
 use crate::api::tasks::{
-    CancelTaskRequest, CancelTaskResponse, CancelTaskResponseBody, GetTaskRunLogsRequest,
-    GetTaskRunLogsResponse, GetTaskRunLogsResponseBody, GetTaskRunResponse, GetTaskRunResponseBody,
+    CancelTaskRequest, CancelTaskResponse, CancelTaskResponseBody, DeleteTaskRunResponse,
+    DeleteTaskRunResponseBody, ExcludeTaskRequest, ExcludeTaskResponse, ExcludeTaskResponseBody,
+    GetTaskRunLogsRequest, GetTaskRunLogsResponse, GetTaskRunLogsResponseBody, GetTaskRunResponse,
+    GetTaskRunResponseBody, IncludeTaskRequest, IncludeTaskResponse, IncludeTaskResponseBody,
     ListTaskRunsRequest, ListTaskRunsResponse, ListTaskRunsResponseBody, ListTasksRequest,
-    ListTasksResponse, ListTasksResponseBody, RestartTaskRequest, RestartTaskResponse,
-    RestartTaskResponseBody, StartTaskRequest, StartTaskResponse, StartTaskResponseBody,
-    TaskRunLogLine, TaskRunTreeNode, cancel_task, get_task_run, get_task_run_logs, list_task_runs,
-    list_tasks, restart_task, run_task,
+    ListTasksResponse, ListTasksResponseBody, ResizeTaskRunRequest, ResizeTaskRunResponse,
+    ResizeTaskRunResponseBody, RestartTaskRequest, RestartTaskResponse, RestartTaskResponseBody,
+    SignalTaskRunRequest, SignalTaskRunResponse, SignalTaskRunResponseBody, StartTaskRequest,
+    StartTaskResponse, StartTaskResponseBody, TaskRunLogLine, TaskRunTreeNode, cancel_task,
+    delete_task_run, exclude_task, get_task_run, get_task_run_logs, include_task, list_task_runs,
+    list_tasks, resize_task_run, restart_task, run_task, signal_task_run,
+};
+use crate::config::{
+    Dependency, DetailedDependency, EnvFile, ReadyProbe, RestartOn, RestartPolicy,
+    SatisfactionPolicy, Task,
 };
-use crate::config::Task;
 use crate::db::entities::task_run::TaskRunStatus;
 
+pub mod config;
 pub mod error;
+pub mod input;
+pub mod log_writer;
+pub mod logs;
+#[cfg(unix)]
+mod process_group;
+mod pty;
+mod readiness;
+mod restart;
+pub mod retention;
 pub mod tasks;
+#[cfg(test)]
+mod test_support;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: DatabaseConnection,
     pub task_events: broadcast::Sender<tasks::TaskRunStatusChangedEvent>,
     pub task_log_events: broadcast::Sender<tasks::TaskRunLogLine>,
+    /// Where task output goes to be broadcast and committed.
+    pub log_writer: LogWriter,
+    /// The processes of runs underway: those this server started, and process
+    /// groups a previous server left running that `reconcile_orphaned_task_runs`
+    /// adopted.
     pub running_processes: Arc<Mutex<HashMap<String, RunningProcessEntry>>>,
-    pub run_include_tasks: Arc<Mutex<HashMap<String, HashSet<String>>>>,
+    /// When this server started, in milliseconds since the epoch, for
+    /// dependencies that only count runs since then.
+    pub started_at: i64,
 }
 
 pub struct RunningProcessEntry {
     pub execution_id: String,
     pub cancel_tx: oneshot::Sender<()>,
+    /// Closes once the run's process has exited, however it was stopped.
+    pub stopped_rx: oneshot::Receiver<()>,
+    /// The process group the run's command leads.
+    pub pgid: Option<u32>,
+    /// The terminal the process runs under, for a task with `pty: true`.
+    pub pty: Option<pty::Pty>,
+    /// Where input sent to the run is written, for a task with `stdin: true`
+    /// or `pty: true` whose process this server started.
+    pub input: Option<input::ProcessInput>,
 }
 
 pub fn create_app_state(db: DatabaseConnection) -> AppState {
     let (task_events, _) = broadcast::channel(256);
     let (task_log_events, _) = broadcast::channel(2048);
     AppState {
+        log_writer: LogWriter::spawn(db.clone(), task_log_events.clone()),
         db,
         task_events,
         task_log_events,
         running_processes: Arc::new(Mutex::new(HashMap::new())),
-        run_include_tasks: Arc::new(Mutex::new(HashMap::new())),
+        started_at: chrono::Utc::now().timestamp_millis(),
     }
 }
 
@@ -57,11 +102,24 @@ pub fn create_router(state: AppState) -> Router {
     Router::new()
         .route("/api/tasks", get(list_tasks))
         .route("/api/tasks/runs", get(list_task_runs))
-        .route("/api/tasks/:run_id", get(get_task_run))
+        .route(
+            "/api/tasks/:run_id",
+            get(get_task_run).delete(delete_task_run),
+        )
         .route("/api/tasks/:run_id/logs", get(get_task_run_logs))
+        .route("/api/tasks/:run_id/include", post(include_task))
+        .route("/api/tasks/:run_id/exclude", post(exclude_task))
+        .route("/api/tasks/:run_id/resize", post(resize_task_run))
+        .route("/api/tasks/:run_id/signal", post(signal_task_run))
+        .route(
+            "/api/tasks/:run_id/input",
+            get(stream_task_run_input).post(send_task_run_input),
+        )
         .route("/api/tasks/run", post(run_task))
         .route("/api/tasks/cancel", post(cancel_task))
         .route("/api/tasks/restart", post(restart_task))
+        .route("/api/logs/search", get(search_logs))
+        .route("/api/config/validate", get(validate_config))
         .merge(SwaggerUi::new("/swagger-ui").url("/openapi.json", ApiDoc::openapi()))
         .with_state(state)
 }
@@ -72,10 +130,18 @@ pub fn create_router(state: AppState) -> Router {
         tasks::list_tasks,
         tasks::list_task_runs,
         tasks::get_task_run,
+        tasks::delete_task_run,
         tasks::get_task_run_logs,
         tasks::run_task,
         tasks::cancel_task,
-        tasks::restart_task
+        tasks::restart_task,
+        tasks::include_task,
+        tasks::exclude_task,
+        tasks::resize_task_run,
+        tasks::signal_task_run,
+        input::send_task_run_input,
+        logs::search_logs,
+        config::validate_config
     ),
     components(schemas(
         ListTasksRequest,
@@ -86,13 +152,26 @@ pub fn create_router(state: AppState) -> Router {
         ListTaskRunsResponseBody,
         GetTaskRunResponse,
         GetTaskRunResponseBody,
+        DeleteTaskRunResponse,
+        DeleteTaskRunResponseBody,
         GetTaskRunLogsResponse,
         GetTaskRunLogsResponseBody,
         GetTaskRunLogsRequest,
         TaskRunLogLine,
         TaskRunTreeNode,
+        SearchLogsRequest,
+        SearchLogsResponse,
+        SearchLogsResponseBody,
+        LogSearchMatch,
         ErrorResponse,
         Task,
+        EnvFile,
+        ReadyProbe,
+        RestartPolicy,
+        RestartOn,
+        Dependency,
+        DetailedDependency,
+        SatisfactionPolicy,
         StartTaskRequest,
         StartTaskResponse,
         StartTaskResponseBody,
@@ -102,7 +181,27 @@ pub fn create_router(state: AppState) -> Router {
         RestartTaskRequest,
         RestartTaskResponse,
         RestartTaskResponseBody,
+        IncludeTaskRequest,
+        IncludeTaskResponse,
+        IncludeTaskResponseBody,
+        ExcludeTaskRequest,
+        ExcludeTaskResponse,
+        ExcludeTaskResponseBody,
+        ResizeTaskRunRequest,
+        ResizeTaskRunResponse,
+        ResizeTaskRunResponseBody,
+        SendTaskRunInputRequest,
+        SendTaskRunInputResponse,
+        SendTaskRunInputResponseBody,
+        SignalTaskRunRequest,
+        SignalTaskRunResponse,
+        SignalTaskRunResponseBody,
         TaskRunStatus,
+        ValidateConfigRequest,
+        ValidateConfigResponse,
+        ValidateConfigResponseBody,
+        ConfigDiagnostic,
+        DiagnosticSeverity,
     ))
 )]
 pub struct ApiDoc;
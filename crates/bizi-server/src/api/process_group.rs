@@ -0,0 +1,162 @@
+//! Every run's command is started in a process group of its own, led by the
+//! shell, so the group's ID is the shell's PID. Signalling the group reaches
+//! whatever the command started too.
+
+use std::time::Duration;
+
+use crate::signal::Signal;
+
+/// Returns whether anything in the group received it.
+pub fn signal(pgid: u32, signal: Signal) -> bool {
+    // Negative PID targets the entire process group.
+    unsafe { libc::kill(-(pgid as i32), signal.number()) == 0 }
+}
+
+/// Whether anything in the group is still running. Once the server that
+/// started a group is gone, its exited members wait as zombies for init to
+/// reap them, which some container inits are slow to do, so on Linux those do
+/// not count.
+pub fn is_alive(pgid: u32) -> bool {
+    #[cfg(target_os = "linux")]
+    if let Some(members) = linux::members(pgid) {
+        return members.iter().any(|member| !member.is_zombie);
+    }
+    unsafe { libc::kill(-(pgid as i32), 0) == 0 }
+}
+
+pub async fn wait_for_exit(pgid: u32, poll_interval: Duration) {
+    while is_alive(pgid) {
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+/// Like `is_alive`, but for a group recorded before the server restarted: its
+/// ID may since have been reused, so on Linux it only counts if something in
+/// it started no earlier than `started_at`, in milliseconds since the epoch.
+pub fn is_alive_since(pgid: u32, started_at: i64) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        // Start times are only known to the clock tick, and boot time to the
+        // second.
+        const SLACK_MS: i64 = 2_000;
+        match linux::members(pgid) {
+            Some(members) => members
+                .iter()
+                .any(|member| !member.is_zombie && member.started_at >= started_at - SLACK_MS),
+            None => is_alive(pgid),
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = started_at;
+        is_alive(pgid)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::fs;
+
+    pub struct Member {
+        /// In milliseconds since the epoch.
+        pub started_at: i64,
+        pub is_zombie: bool,
+    }
+
+    /// The processes in the group, or `None` if `/proc` cannot be read.
+    pub fn members(pgid: u32) -> Option<Vec<Member>> {
+        let boot_time_ms = boot_time_secs()? * 1000;
+        let ticks_per_sec = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        if ticks_per_sec <= 0 {
+            return None;
+        }
+
+        let mut members = Vec::new();
+        for entry in fs::read_dir("/proc").ok()?.flatten() {
+            let is_pid = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.bytes().all(|byte| byte.is_ascii_digit()));
+            if !is_pid {
+                continue;
+            }
+            // The process can exit between listing and reading it.
+            let Ok(stat) = fs::read_to_string(entry.path().join("stat")) else {
+                continue;
+            };
+            if let Some(stat) = parse_stat(&stat)
+                && stat.group == pgid
+            {
+                members.push(Member {
+                    started_at: boot_time_ms + stat.start_ticks * 1000 / ticks_per_sec as i64,
+                    is_zombie: stat.state == 'Z',
+                });
+            }
+        }
+        Some(members)
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub(super) struct Stat {
+        pub state: char,
+        pub group: u32,
+        /// In clock ticks since boot.
+        pub start_ticks: i64,
+    }
+
+    /// Reads a `/proc/<pid>/stat` line. The command name in parentheses can
+    /// itself contain spaces and parentheses, so fields are counted from the
+    /// last `)`.
+    pub(super) fn parse_stat(stat: &str) -> Option<Stat> {
+        let (_, fields) = stat.rsplit_once(')')?;
+        let fields = fields.split_whitespace().collect::<Vec<_>>();
+        // `fields[0]` is field 3, `state`; `pgrp` is field 5 and `starttime` 22.
+        Some(Stat {
+            state: fields.first()?.chars().next()?,
+            group: fields.get(2)?.parse().ok()?,
+            start_ticks: fields.get(19)?.parse().ok()?,
+        })
+    }
+
+    fn boot_time_secs() -> Option<i64> {
+        fs::read_to_string("/proc/stat")
+            .ok()?
+            .lines()
+            .find_map(|line| line.strip_prefix("btime "))?
+            .trim()
+            .parse()
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parses_stat_lines_with_awkward_command_names() {
+        let stat = "4242 (my (weird) cmd) S 1 4242 4242 0 -1 4194560 120 0 0 0 3 1 0 0 20 0 1 0 987654 1000 100";
+        assert_eq!(
+            linux::parse_stat(stat),
+            Some(linux::Stat {
+                state: 'S',
+                group: 4242,
+                start_ticks: 987654
+            })
+        );
+        assert_eq!(linux::parse_stat("4242 (truncated"), None);
+    }
+
+    #[test]
+    fn finds_its_own_process_group() {
+        let pgid = unsafe { libc::getpgrp() } as u32;
+        assert!(is_alive(pgid));
+        assert!(is_alive_since(pgid, 0));
+        #[cfg(target_os = "linux")]
+        assert!(!is_alive_since(
+            pgid,
+            chrono::Utc::now().timestamp_millis() + 60_000
+        ));
+    }
+}
@@ -0,0 +1,248 @@
+//! Runs a task's command under a pseudo-terminal, for tasks with `pty: true`.
+//! The command gets the terminal's other end as stdin, stdout and stderr and
+//! as its controlling terminal, so `isatty` checks pass and prompts work. The
+//! server keeps the master end, to read the output from, type input into and
+//! resize.
+
+#[cfg(target_os = "linux")]
+pub use linux::Pty;
+#[cfg(not(target_os = "linux"))]
+pub use unsupported::Pty;
+
+/// Columns and rows a terminal starts with, until a client sets its own.
+pub const DEFAULT_SIZE: (u16, u16) = (80, 24);
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::pin::Pin;
+    use std::process::Stdio;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, ready};
+
+    use tokio::io::unix::AsyncFd;
+    use tokio::io::{AsyncRead, ReadBuf};
+    use tokio::process::Command;
+
+    /// The server's end of a run's terminal. Clones share it.
+    #[derive(Clone)]
+    pub struct Pty {
+        master: Arc<AsyncFd<OwnedFd>>,
+    }
+
+    impl Pty {
+        /// Opens a terminal of `cols` by `rows` and sets `command` up to run
+        /// attached to it, in a session of its own. The session is also the
+        /// command's process group, led by the shell, as `process_group`
+        /// expects, so `command` must not be given one.
+        ///
+        /// `command` holds the server's copies of the command's end until it
+        /// is dropped, and output only ends once it is, so drop it right after
+        /// spawning.
+        pub fn attach(command: &mut Command, (cols, rows): (u16, u16)) -> io::Result<Pty> {
+            let mut master = -1;
+            let mut slave = -1;
+            let size = window_size(cols, rows);
+            let opened = unsafe {
+                libc::openpty(
+                    &mut master,
+                    &mut slave,
+                    std::ptr::null_mut(),
+                    std::ptr::null(),
+                    &size,
+                )
+            };
+            if opened != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let (master, slave) =
+                unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+            // Neither end should leak into what the command starts; it gets
+            // its own copies of the slave end as stdin, stdout and stderr.
+            for fd in [&master, &slave] {
+                set_flags(fd, libc::F_GETFD, libc::F_SETFD, libc::FD_CLOEXEC)?;
+            }
+            set_flags(&master, libc::F_GETFL, libc::F_SETFL, libc::O_NONBLOCK)?;
+
+            command
+                .stdin(Stdio::from(slave.try_clone()?))
+                .stdout(Stdio::from(slave.try_clone()?))
+                .stderr(Stdio::from(slave));
+            unsafe {
+                command.pre_exec(|| {
+                    if libc::setsid() == -1 || libc::ioctl(0, libc::TIOCSCTTY, 0) == -1 {
+                        return Err(io::Error::last_os_error());
+                    }
+                    Ok(())
+                });
+            }
+
+            Ok(Pty {
+                master: Arc::new(AsyncFd::new(master)?),
+            })
+        }
+
+        /// Sets the terminal's size, which sends the command `SIGWINCH`.
+        pub fn resize(&self, cols: u16, rows: u16) -> io::Result<()> {
+            let size = window_size(cols, rows);
+            if unsafe { libc::ioctl(self.master.as_raw_fd(), libc::TIOCSWINSZ, &size) } == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        /// Writes `bytes` as if they were typed at the terminal.
+        pub async fn write_all(&self, mut bytes: &[u8]) -> io::Result<()> {
+            while !bytes.is_empty() {
+                let mut guard = self.master.writable().await?;
+                let written = guard.try_io(|master| {
+                    let written = unsafe {
+                        libc::write(master.as_raw_fd(), bytes.as_ptr().cast(), bytes.len())
+                    };
+                    if written < 0 {
+                        Err(io::Error::last_os_error())
+                    } else {
+                        Ok(written as usize)
+                    }
+                });
+                match written {
+                    Ok(written) => bytes = &bytes[written?..],
+                    Err(_would_block) => continue,
+                }
+            }
+            Ok(())
+        }
+
+        /// Everything the command writes, stdout and stderr alike.
+        pub fn reader(&self) -> PtyReader {
+            PtyReader {
+                master: self.master.clone(),
+            }
+        }
+    }
+
+    pub struct PtyReader {
+        master: Arc<AsyncFd<OwnedFd>>,
+    }
+
+    impl AsyncRead for PtyReader {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            loop {
+                let mut guard = ready!(self.master.poll_read_ready(cx))?;
+                let unfilled = buf.initialize_unfilled();
+                let read = guard.try_io(|master| {
+                    let read = unsafe {
+                        libc::read(
+                            master.as_raw_fd(),
+                            unfilled.as_mut_ptr().cast(),
+                            unfilled.len(),
+                        )
+                    };
+                    if read < 0 {
+                        Err(io::Error::last_os_error())
+                    } else {
+                        Ok(read as usize)
+                    }
+                });
+                match read {
+                    Ok(Ok(read)) => {
+                        buf.advance(read);
+                        return Poll::Ready(Ok(()));
+                    }
+                    // Once nothing has the other end open any more, Linux
+                    // reports `EIO` rather than the end of the output.
+                    Ok(Err(err)) if err.raw_os_error() == Some(libc::EIO) => {
+                        return Poll::Ready(Ok(()));
+                    }
+                    Ok(Err(err)) => return Poll::Ready(Err(err)),
+                    Err(_would_block) => continue,
+                }
+            }
+        }
+    }
+
+    fn window_size(cols: u16, rows: u16) -> libc::winsize {
+        libc::winsize {
+            ws_row: rows,
+            ws_col: cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        }
+    }
+
+    fn set_flags(
+        fd: &OwnedFd,
+        get: libc::c_int,
+        set: libc::c_int,
+        flags: libc::c_int,
+    ) -> io::Result<()> {
+        let current = unsafe { libc::fcntl(fd.as_raw_fd(), get) };
+        if current == -1 || unsafe { libc::fcntl(fd.as_raw_fd(), set, current | flags) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+/// Stands in where there are no pseudo-terminals to run under; `attach`
+/// always fails, so none is ever made.
+#[cfg(not(target_os = "linux"))]
+mod unsupported {
+    use std::convert::Infallible;
+    use std::io;
+
+    use tokio::process::Command;
+
+    #[derive(Clone)]
+    pub struct Pty(Infallible);
+
+    pub type PtyReader = tokio::io::Empty;
+
+    impl Pty {
+        pub fn attach(_command: &mut Command, _size: (u16, u16)) -> io::Result<Pty> {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "pseudo-terminals are only supported on Linux",
+            ))
+        }
+
+        pub fn resize(&self, _cols: u16, _rows: u16) -> io::Result<()> {
+            match self.0 {}
+        }
+
+        pub async fn write_all(&self, _bytes: &[u8]) -> io::Result<()> {
+            match self.0 {}
+        }
+
+        pub fn reader(&self) -> PtyReader {
+            match self.0 {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn the_command_sees_a_terminal_of_the_size_set() {
+        use tokio::io::AsyncReadExt;
+
+        let mut command = tokio::process::Command::new("sh");
+        command.arg("-c").arg("test -t 0 && test -t 1 && stty size");
+        let pty = Pty::attach(&mut command, (100, 30)).unwrap();
+        let mut child = command.spawn().unwrap();
+        drop(command);
+
+        let mut output = String::new();
+        pty.reader().read_to_string(&mut output).await.unwrap();
+        assert!(child.wait().await.unwrap().success());
+        assert_eq!(output.trim(), "30 100");
+    }
+}
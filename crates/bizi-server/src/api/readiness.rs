@@ -0,0 +1,182 @@
+//! Readiness probes let a task that never exits, like a dev server, tell the
+//! runs waiting on it that it is up.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use regex::Regex;
+use tokio::{net::TcpStream, sync::broadcast};
+
+pub use bizi_api::ReadyProbe;
+
+use crate::api::tasks::TaskRunLogLine;
+
+const DEFAULT_TIMEOUT_MS: u64 = 60_000;
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+const HTTP_REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Resolves once every probe in `probe` has passed for `run_id`, or with the
+/// message to log when one never will.
+///
+/// `logs` must be subscribed before the task's process starts writing, or the
+/// line a log probe is looking for can go by unseen.
+pub async fn wait_until_ready(
+    probe: &ReadyProbe,
+    run_id: &str,
+    logs: broadcast::Receiver<TaskRunLogLine>,
+) -> Result<(), String> {
+    let log_pattern = probe
+        .log
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
+        .map_err(|err| format!("Invalid ready.log pattern: {}", err))?;
+    let timeout = Duration::from_millis(probe.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+
+    let probes = async {
+        tokio::join!(
+            async {
+                if let Some(pattern) = &log_pattern {
+                    wait_for_log_line(pattern, run_id, logs).await;
+                }
+            },
+            async {
+                if let Some(port) = probe.port {
+                    wait_for_port(port).await;
+                }
+            },
+            async {
+                if let Some(url) = &probe.http {
+                    wait_for_http(url).await;
+                }
+            },
+        )
+    };
+
+    tokio::time::timeout(timeout, probes)
+        .await
+        .map(|_| ())
+        .map_err(|_| {
+            format!(
+                "Task did not become ready within {}",
+                format_timeout(timeout)
+            )
+        })
+}
+
+async fn wait_for_log_line(
+    pattern: &Regex,
+    run_id: &str,
+    mut logs: broadcast::Receiver<TaskRunLogLine>,
+) {
+    loop {
+        match logs.recv().await {
+            Ok(log) if log.run_id == run_id && pattern.is_match(&strip_ansi(&log.line)) => {
+                return;
+            }
+            Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+            // Nothing more will be logged, so let the timeout report it.
+            Err(broadcast::error::RecvError::Closed) => std::future::pending().await,
+        }
+    }
+}
+
+async fn wait_for_port(port: u16) {
+    while TcpStream::connect(("localhost", port)).await.is_err() {
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+async fn wait_for_http(url: &str) {
+    let client = reqwest::Client::new();
+    loop {
+        let response = client.get(url).timeout(HTTP_REQUEST_TIMEOUT).send().await;
+        if response.is_ok_and(|response| response.status().is_success()) {
+            return;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Log lines keep their color codes, which would otherwise sit between the
+/// words a pattern is looking for.
+fn strip_ansi(line: &str) -> std::borrow::Cow<'_, str> {
+    static ANSI_ESCAPE: OnceLock<Regex> = OnceLock::new();
+    ANSI_ESCAPE
+        .get_or_init(|| Regex::new(r"\x1b\[[0-9;?]*[ -/]*[@-~]").expect("valid regex"))
+        .replace_all(line, "")
+}
+
+fn format_timeout(timeout: Duration) -> String {
+    if timeout.subsec_millis() == 0 {
+        format!("{}s", timeout.as_secs())
+    } else {
+        format!("{}ms", timeout.as_millis())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(run_id: &str, line: &str) -> TaskRunLogLine {
+        TaskRunLogLine {
+            run_id: run_id.to_string(),
+            task: "dev:api".to_string(),
+            line: line.to_string(),
+            is_stderr: false,
+            timestamp: 0,
+            sequence: 0,
+            attempt: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn log_probe_matches_lines_from_its_own_run_without_color_codes() {
+        let (sender, logs) = broadcast::channel(8);
+        let probe = ReadyProbe {
+            log: Some("listening on :\\d+".to_string()),
+            timeout_ms: Some(1_000),
+            ..ReadyProbe::default()
+        };
+
+        sender.send(log("other", "listening on :3000")).unwrap();
+        sender.send(log("r1", "compiling...")).unwrap();
+        sender
+            .send(log("r1", "\u{1b}[32mlistening\u{1b}[0m on :3000"))
+            .unwrap();
+
+        assert_eq!(wait_until_ready(&probe, "r1", logs).await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn port_probe_times_out_when_nothing_listens() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let (_sender, logs) = broadcast::channel(1);
+        let probe = ReadyProbe {
+            port: Some(port),
+            timeout_ms: Some(300),
+            ..ReadyProbe::default()
+        };
+
+        assert_eq!(
+            wait_until_ready(&probe, "r1", logs).await,
+            Err("Task did not become ready within 300ms".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_log_patterns_up_front() {
+        let (_sender, logs) = broadcast::channel(1);
+        let probe = ReadyProbe {
+            log: Some("(unclosed".to_string()),
+            ..ReadyProbe::default()
+        };
+
+        let err = wait_until_ready(&probe, "r1", logs).await.unwrap_err();
+        assert!(err.starts_with("Invalid ready.log pattern"));
+    }
+}
@@ -0,0 +1,130 @@
+//! Restart policies start a task's command again when it exits on its own, so
+//! a dev server that crashes comes back without anyone restarting it by hand.
+
+use std::time::Duration;
+
+pub use bizi_api::{RestartOn, RestartPolicy};
+
+use crate::db::entities::task_run::TaskRunStatus;
+
+const DEFAULT_BACKOFF_MS: u64 = 1_000;
+const DEFAULT_RESET_AFTER_MS: u64 = 60_000;
+// Doubling stops here, unless `backoffMs` alone is already longer.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// What to do once a run's command has exited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Restart {
+    /// Start the command again after this long.
+    After(Duration),
+    /// The policy would restart, but the run has already been restarted
+    /// `maxRestarts` times in a row.
+    Exhausted(u32),
+}
+
+/// Counts one run's restarts in a row against its task's policy.
+pub struct RestartTracker {
+    policy: Option<RestartPolicy>,
+    in_a_row: u32,
+}
+
+impl RestartTracker {
+    pub fn new(policy: Option<RestartPolicy>) -> Self {
+        Self {
+            policy,
+            in_a_row: 0,
+        }
+    }
+
+    /// Whether to restart a command that exited as `status` after running
+    /// for `uptime`. `None` when the policy does not cover the exit, or the
+    /// task has none.
+    pub fn after_exit(&mut self, status: TaskRunStatus, uptime: Duration) -> Option<Restart> {
+        let policy = self.policy.as_ref()?;
+        let covered = match status {
+            TaskRunStatus::Failed => true,
+            TaskRunStatus::Success => policy.on == RestartOn::Always,
+            _ => false,
+        };
+        if !covered {
+            return None;
+        }
+
+        let reset_after =
+            Duration::from_millis(policy.reset_after_ms.unwrap_or(DEFAULT_RESET_AFTER_MS));
+        if uptime >= reset_after {
+            self.in_a_row = 0;
+        }
+        if let Some(max_restarts) = policy.max_restarts
+            && self.in_a_row >= max_restarts
+        {
+            return Some(Restart::Exhausted(max_restarts));
+        }
+
+        let backoff = Duration::from_millis(policy.backoff_ms.unwrap_or(DEFAULT_BACKOFF_MS));
+        let delay = backoff
+            .saturating_mul(2u32.saturating_pow(self.in_a_row))
+            .min(MAX_BACKOFF.max(backoff));
+        self.in_a_row += 1;
+        Some(Restart::After(delay))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(on: RestartOn) -> RestartPolicy {
+        RestartPolicy {
+            on,
+            max_restarts: Some(3),
+            backoff_ms: Some(500),
+            reset_after_ms: Some(10_000),
+        }
+    }
+
+    #[test]
+    fn backs_off_until_out_of_restarts_in_a_row() {
+        let mut tracker = RestartTracker::new(Some(policy(RestartOn::Failure)));
+        let crash = Duration::from_millis(100);
+        assert_eq!(tracker.after_exit(TaskRunStatus::Success, crash), None);
+        assert_eq!(tracker.after_exit(TaskRunStatus::Cancelled, crash), None);
+
+        let delays = (0..4)
+            .map(|_| tracker.after_exit(TaskRunStatus::Failed, crash))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            delays,
+            [
+                Some(Restart::After(Duration::from_millis(500))),
+                Some(Restart::After(Duration::from_millis(1_000))),
+                Some(Restart::After(Duration::from_millis(2_000))),
+                Some(Restart::Exhausted(3)),
+            ]
+        );
+
+        // Staying up long enough starts the count over.
+        assert_eq!(
+            tracker.after_exit(TaskRunStatus::Failed, Duration::from_secs(10)),
+            Some(Restart::After(Duration::from_millis(500)))
+        );
+    }
+
+    #[test]
+    fn always_restarts_after_success_and_caps_the_backoff() {
+        let mut tracker = RestartTracker::new(Some(RestartPolicy {
+            max_restarts: None,
+            ..policy(RestartOn::Always)
+        }));
+        let mut last = None;
+        for _ in 0..12 {
+            last = tracker.after_exit(TaskRunStatus::Success, Duration::ZERO);
+        }
+        assert_eq!(last, Some(Restart::After(MAX_BACKOFF)));
+
+        assert_eq!(
+            RestartTracker::new(None).after_exit(TaskRunStatus::Failed, Duration::ZERO),
+            None
+        );
+    }
+}
@@ -0,0 +1,414 @@
+//! Deletes old runs and log lines so `bizi.db` does not grow without bound.
+//! Limits come from the server's flags, which each project's `retention`
+//! settings can override.
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, TransactionTrait,
+};
+
+use crate::{
+    api::{AppState, tasks::collect_descendant_run_ids},
+    config::{Config, Retention},
+    db::entities::{task_run, task_run_include_task, task_run_log},
+};
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+// Kept well under SQLite's limit on bound parameters per statement.
+const DELETE_CHUNK_SIZE: usize = 500;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Pruned {
+    pub runs: u64,
+    pub log_lines: u64,
+}
+
+/// Prunes once right away and then every hour, for as long as the server runs.
+pub fn spawn_pruning(state: AppState, defaults: Retention) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            interval.tick().await;
+            // Runs that just finished may still have lines on their way in.
+            state.log_writer.flush().await;
+            match prune(&state.db, defaults, chrono::Utc::now().timestamp_millis()).await {
+                Ok(pruned) if pruned != Pruned::default() => {
+                    println!(
+                        "Pruned {} task runs and {} log lines",
+                        pruned.runs, pruned.log_lines
+                    );
+                    if let Err(err) = reclaim_space(&state.db).await {
+                        eprintln!("Failed to vacuum the database: {}", err);
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => eprintln!("Failed to prune task runs: {}", err),
+            }
+        }
+    });
+}
+
+/// Applies each project's retention, falling back to `defaults`, as of `now`
+/// in milliseconds since the epoch. A run is only deleted along with its whole
+/// subtree, and never while anything in it is queued or running.
+pub async fn prune(
+    db: &DatabaseConnection,
+    defaults: Retention,
+    now: i64,
+) -> Result<Pruned, DbErr> {
+    let cwds: Vec<String> = task_run::Entity::find()
+        .select_only()
+        .column(task_run::Column::Cwd)
+        .distinct()
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let mut pruned = Pruned::default();
+    for cwd in cwds {
+        // A project whose config is gone or broken still gets the defaults.
+        let retention = match Config::load(&cwd).await {
+            Ok(config) => config.retention.unwrap_or_default().or(defaults),
+            Err(_) => defaults,
+        };
+        let runs = task_run::Entity::find()
+            .filter(task_run::Column::Cwd.eq(cwd.clone()))
+            .all(db)
+            .await?;
+
+        let expired = expired_run_ids(&runs, retention, now);
+        pruned.runs += expired.len() as u64;
+        pruned.log_lines += delete_task_runs(db, &expired).await?;
+
+        if let Some(max_log_lines) = limit(retention.max_log_lines) {
+            let expired = expired.into_iter().collect::<HashSet<_>>();
+            for run in runs.iter().filter(|run| !expired.contains(&run.id)) {
+                pruned.log_lines += cap_log_lines(db, &run.id, max_log_lines).await?;
+            }
+        }
+    }
+    Ok(pruned)
+}
+
+/// `0` turns a limit off.
+fn limit(value: Option<u64>) -> Option<u64> {
+    value.filter(|value| *value > 0)
+}
+
+struct Subtree {
+    last_updated_at: i64,
+    is_active: bool,
+    run_ids: Vec<String>,
+}
+
+/// Every run in the subtrees `retention` no longer keeps, out of one project's
+/// `runs`.
+fn expired_run_ids(runs: &[task_run::Model], retention: Retention, now: i64) -> Vec<String> {
+    let runs_by_id = runs
+        .iter()
+        .map(|run| (run.id.as_str(), run))
+        .collect::<HashMap<_, _>>();
+
+    // Subtrees of each task, with when anything in them last changed.
+    let mut trees_by_task: HashMap<&str, Vec<Subtree>> = HashMap::new();
+    for root in runs.iter().filter(|run| run.parent_run_id.is_none()) {
+        let run_ids = collect_descendant_run_ids(runs, &root.id);
+        let subtree = run_ids.iter().filter_map(|id| runs_by_id.get(id.as_str()));
+        let last_updated_at = subtree.clone().map(|run| run.updated_at).max();
+        let is_active = subtree.clone().any(|run| run.status.is_active());
+        trees_by_task
+            .entry(root.task.as_str())
+            .or_default()
+            .push(Subtree {
+                last_updated_at: last_updated_at.unwrap_or(root.updated_at),
+                is_active,
+                run_ids,
+            });
+    }
+
+    let max_age_cutoff =
+        limit(retention.max_age_days).map(|days| now - days.saturating_mul(DAY_MS as u64) as i64);
+    let keep_runs = limit(retention.keep_runs).map(|keep| keep as usize);
+
+    let mut expired = Vec::new();
+    for trees in trees_by_task.values_mut() {
+        trees.sort_by_key(|tree| std::cmp::Reverse(tree.last_updated_at));
+        for (index, tree) in trees.drain(..).enumerate() {
+            let too_many = keep_runs.is_some_and(|keep| index >= keep);
+            let too_old = max_age_cutoff.is_some_and(|cutoff| tree.last_updated_at < cutoff);
+            if (too_many || too_old) && !tree.is_active {
+                expired.extend(tree.run_ids);
+            }
+        }
+    }
+    expired
+}
+
+/// Deletes the runs with their logs and include sets, returning how many log
+/// lines went with them.
+pub async fn delete_task_runs(db: &DatabaseConnection, run_ids: &[String]) -> Result<u64, DbErr> {
+    let mut deleted_log_lines = 0;
+    for chunk in run_ids.chunks(DELETE_CHUNK_SIZE) {
+        let txn = db.begin().await?;
+        deleted_log_lines += task_run_log::Entity::delete_many()
+            .filter(task_run_log::Column::RunId.is_in(chunk.to_vec()))
+            .exec(&txn)
+            .await?
+            .rows_affected;
+        task_run_include_task::Entity::delete_many()
+            .filter(task_run_include_task::Column::RootRunId.is_in(chunk.to_vec()))
+            .exec(&txn)
+            .await?;
+        task_run::Entity::delete_many()
+            .filter(task_run::Column::Id.is_in(chunk.to_vec()))
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+    }
+    Ok(deleted_log_lines)
+}
+
+/// Deletes all but the run's newest `max_log_lines` lines.
+async fn cap_log_lines(
+    db: &DatabaseConnection,
+    run_id: &str,
+    max_log_lines: u64,
+) -> Result<u64, DbErr> {
+    let newest_dropped_id: Option<i64> = task_run_log::Entity::find()
+        .select_only()
+        .column(task_run_log::Column::Id)
+        .filter(task_run_log::Column::RunId.eq(run_id))
+        .order_by_desc(task_run_log::Column::Id)
+        .offset(max_log_lines)
+        .limit(1)
+        .into_tuple()
+        .one(db)
+        .await?;
+    let Some(newest_dropped_id) = newest_dropped_id else {
+        return Ok(0);
+    };
+
+    Ok(task_run_log::Entity::delete_many()
+        .filter(task_run_log::Column::RunId.eq(run_id))
+        .filter(task_run_log::Column::Id.lte(newest_dropped_id))
+        .exec(db)
+        .await?
+        .rows_affected)
+}
+
+/// Hands the pages deleted rows leave behind back to the file system. A
+/// database created before pruning existed needs one full `VACUUM` to switch
+/// to incremental mode; after that, freeing pages is cheap.
+async fn reclaim_space(db: &DatabaseConnection) -> Result<(), DbErr> {
+    const INCREMENTAL: i32 = 2;
+    let auto_vacuum = db
+        .query_one(sea_orm::Statement::from_string(
+            db.get_database_backend(),
+            "PRAGMA auto_vacuum",
+        ))
+        .await?
+        .map(|row| row.try_get_by_index::<i32>(0))
+        .transpose()?;
+
+    if auto_vacuum == Some(INCREMENTAL) {
+        db.execute_unprepared("PRAGMA incremental_vacuum").await?;
+    } else {
+        db.execute_unprepared("PRAGMA auto_vacuum = INCREMENTAL")
+            .await?;
+        db.execute_unprepared("VACUUM").await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{ActiveModelTrait, ActiveValue::Set, PaginatorTrait};
+
+    use super::*;
+    use crate::api::test_support::{insert_run, test_state};
+    use crate::db::entities::task_run::TaskRunStatus;
+
+    fn run(id: &str, task: &str, parent: Option<&str>, updated_at: i64) -> task_run::Model {
+        task_run::Model {
+            id: id.to_string(),
+            task: task.to_string(),
+            cwd: "/repo".to_string(),
+            parent_run_id: parent.map(str::to_string),
+            started_by_run_id: None,
+            status: TaskRunStatus::Success,
+            updated_at,
+            waiting_on: None,
+            blocked_by: None,
+            working_dir: None,
+            env: None,
+            ready_at: None,
+            exit_code: None,
+            signal: None,
+            pid: None,
+            started_at: None,
+            finished_at: None,
+            attempt: 1,
+            restarts: 0,
+            timed_out_after_ms: None,
+        }
+    }
+
+    fn sorted(mut run_ids: Vec<String>) -> Vec<String> {
+        run_ids.sort();
+        run_ids
+    }
+
+    #[test]
+    fn keeps_the_newest_runs_of_each_task_with_their_subtasks() {
+        let runs = vec![
+            run("dev-1", "dev", None, 1),
+            run("dev-1-api", "dev:api", Some("dev-1"), 1),
+            // Its subtask changed most recently, so this tree counts as newest.
+            run("dev-2", "dev", None, 2),
+            run("dev-2-api", "dev:api", Some("dev-2"), 9),
+            run("dev-3", "dev", None, 5),
+            run("build-1", "build", None, 0),
+        ];
+        let retention = Retention {
+            keep_runs: Some(2),
+            ..Retention::default()
+        };
+        assert_eq!(
+            sorted(expired_run_ids(&runs, retention, 10)),
+            ["dev-1", "dev-1-api"]
+        );
+
+        let mut active = runs.clone();
+        active[1].status = TaskRunStatus::Running;
+        assert!(expired_run_ids(&active, retention, 10).is_empty());
+
+        let unlimited = Retention {
+            keep_runs: Some(0),
+            ..Retention::default()
+        };
+        assert!(expired_run_ids(&runs, unlimited, 10).is_empty());
+    }
+
+    #[test]
+    fn drops_runs_older_than_the_maximum_age() {
+        let now = 10 * DAY_MS;
+        let runs = vec![
+            run("old", "dev", None, now - 8 * DAY_MS),
+            run("recent", "dev", None, now - 6 * DAY_MS),
+        ];
+        let retention = Retention {
+            max_age_days: Some(7),
+            ..Retention::default()
+        };
+        assert_eq!(expired_run_ids(&runs, retention, now), ["old"]);
+    }
+
+    #[test]
+    fn project_settings_override_the_server_defaults() {
+        let defaults = Retention {
+            keep_runs: Some(50),
+            max_log_lines: Some(10_000),
+            max_age_days: Some(30),
+        };
+        let project = Retention {
+            keep_runs: Some(5),
+            max_age_days: Some(0),
+            ..Retention::default()
+        };
+        assert_eq!(
+            project.or(defaults),
+            Retention {
+                keep_runs: Some(5),
+                max_log_lines: Some(10_000),
+                max_age_days: Some(0),
+            }
+        );
+    }
+
+    async fn insert_log_lines(db: &DatabaseConnection, run_id: &str, count: usize) {
+        for index in 0..count {
+            task_run_log::ActiveModel {
+                run_id: Set(run_id.to_string()),
+                task: Set("dev".to_string()),
+                line: Set(format!("line {index}")),
+                is_stderr: Set(false),
+                timestamp: Set(0),
+                attempt: Set(1),
+                ..Default::default()
+            }
+            .insert(db)
+            .await
+            .unwrap();
+        }
+    }
+
+    async fn log_lines(db: &DatabaseConnection, run_id: &str) -> Vec<String> {
+        task_run_log::Entity::find()
+            .filter(task_run_log::Column::RunId.eq(run_id))
+            .order_by_asc(task_run_log::Column::Id)
+            .all(db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|log| log.line)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn caps_a_run_at_its_newest_log_lines() {
+        let state = test_state().await;
+        insert_run(&state.db, "dev-1", "dev", TaskRunStatus::Success).await;
+        insert_run(&state.db, "dev-2", "dev", TaskRunStatus::Success).await;
+        insert_log_lines(&state.db, "dev-1", 5).await;
+        insert_log_lines(&state.db, "dev-2", 2).await;
+
+        assert_eq!(cap_log_lines(&state.db, "dev-1", 2).await.unwrap(), 3);
+        assert_eq!(log_lines(&state.db, "dev-1").await, ["line 3", "line 4"]);
+        assert_eq!(cap_log_lines(&state.db, "dev-2", 2).await.unwrap(), 0);
+        assert_eq!(log_lines(&state.db, "dev-2").await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn deletes_runs_with_their_logs_and_include_sets() {
+        let state = test_state().await;
+        insert_run(&state.db, "dev-1", "dev", TaskRunStatus::Success).await;
+        insert_run(&state.db, "dev-2", "dev", TaskRunStatus::Success).await;
+        insert_log_lines(&state.db, "dev-1", 3).await;
+        insert_log_lines(&state.db, "dev-2", 1).await;
+        task_run_include_task::ActiveModel {
+            root_run_id: Set("dev-1".to_string()),
+            task: Set("dev:storybook".to_string()),
+        }
+        .insert(&state.db)
+        .await
+        .unwrap();
+
+        assert_eq!(
+            delete_task_runs(&state.db, &["dev-1".to_string()])
+                .await
+                .unwrap(),
+            3
+        );
+        let remaining = task_run::Entity::find().all(&state.db).await.unwrap();
+        assert_eq!(
+            remaining
+                .iter()
+                .map(|run| run.id.as_str())
+                .collect::<Vec<_>>(),
+            ["dev-2"]
+        );
+        assert!(log_lines(&state.db, "dev-1").await.is_empty());
+        assert_eq!(log_lines(&state.db, "dev-2").await, ["line 0"]);
+        assert_eq!(
+            task_run_include_task::Entity::find()
+                .count(&state.db)
+                .await
+                .unwrap(),
+            0
+        );
+    }
+}
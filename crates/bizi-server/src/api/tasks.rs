@@ -1,20 +1,27 @@
 use std::collections::{HashMap, HashSet};
 
+use indexmap::IndexMap;
+
 // Every type on this list is the wire contract shared with the Rust clients.
 // Adding a field here cannot drift from what a client reads, because there is
 // only one definition.
 pub use bizi_api::{
-    CancelTaskRequest, CancelTaskResponse, CancelTaskResponseBody, GetTaskRunLogsRequest,
-    GetTaskRunLogsResponse, GetTaskRunLogsResponseBody, GetTaskRunResponse, GetTaskRunResponseBody,
+    CancelTaskRequest, CancelTaskResponse, CancelTaskResponseBody, DeleteTaskRunResponse,
+    DeleteTaskRunResponseBody, ExcludeTaskRequest, ExcludeTaskResponse, ExcludeTaskResponseBody,
+    GetTaskRunLogsRequest, GetTaskRunLogsResponse, GetTaskRunLogsResponseBody, GetTaskRunResponse,
+    GetTaskRunResponseBody, IncludeTaskRequest, IncludeTaskResponse, IncludeTaskResponseBody,
     ListTaskRunsRequest, ListTaskRunsResponse, ListTaskRunsResponseBody, ListTasksRequest,
-    ListTasksResponse, ListTasksResponseBody, RestartTaskRequest, RestartTaskResponse,
-    RestartTaskResponseBody, StartTaskRequest, StartTaskResponse, StartTaskResponseBody,
-    TaskRunLogLine, TaskRunLogsStreamMessage, TaskRunTreeNode,
+    ListTasksResponse, ListTasksResponseBody, ResizeTaskRunRequest, ResizeTaskRunResponse,
+    ResizeTaskRunResponseBody, RestartTaskRequest, RestartTaskResponse, RestartTaskResponseBody,
+    SignalTaskRunRequest, SignalTaskRunResponse, SignalTaskRunResponseBody, StartTaskRequest,
+    StartTaskResponse, StartTaskResponseBody, TaskRunLogLine, TaskRunLogsStreamMessage,
+    TaskRunTreeNode,
 };
 
-use std::path::PathBuf;
+use std::path::{Path as FsPath, PathBuf};
 use std::process::Stdio;
 use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
 use axum::{
     Json,
@@ -28,9 +35,8 @@ use axum::{
 use futures_util::StreamExt;
 use nanoid::nanoid;
 use sea_orm::{
-    ActiveModelTrait,
-    ActiveValue::{NotSet, Set},
-    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder,
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, Condition, DatabaseConnection, DbErr,
+    EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, QuerySelect, sea_query::Expr,
 };
 use serde::Serialize;
 use tokio::{
@@ -39,15 +45,32 @@ use tokio::{
     sync::{Mutex, broadcast, oneshot},
 };
 
+#[cfg(unix)]
+use crate::api::process_group;
 use crate::{
-    api::{AppState, RunningProcessEntry, error::ErrorResponse},
-    config::{Config, Task},
+    api::{
+        AppState, RunningProcessEntry,
+        error::ErrorResponse,
+        input::ProcessInput,
+        pty::{self, Pty},
+        readiness::{ReadyProbe, wait_until_ready},
+        restart::{Restart, RestartTracker},
+        retention,
+    },
+    config::{
+        Config, Dependency, EnvLayer, SatisfactionPolicy, Task, resolve_task_env,
+        resolve_task_working_dir,
+    },
     db::entities::{
         task_run::{self, TaskRunStatus},
-        task_run_log,
+        task_run_include_task, task_run_log,
     },
+    signal::Signal,
 };
 
+#[cfg(unix)]
+const STOP_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
 const TASK_RUN_ID_ALPHABET: [char; 63] = [
     '_', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h',
     'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', 'A',
@@ -63,6 +86,7 @@ const TASK_RUN_ID_ALPHABET: [char; 63] = [
     ),
     responses(
         (status = 200, description = "Success", body = ListTasksResponse),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
         (status = 404, description = "Not Found", body = ErrorResponse),
         (status = 500, description = "Internal Server Error", body = ErrorResponse),
     )
@@ -83,6 +107,15 @@ pub async fn list_tasks(
                 );
             }
 
+            if e.is_invalid() {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(ListTasksResponse::Error(ErrorResponse {
+                        message: e.to_string(),
+                    })),
+                );
+            }
+
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ListTasksResponse::Error(ErrorResponse {
@@ -146,9 +179,29 @@ pub async fn list_task_runs(
         runs_by_id.insert(run.id.clone(), run);
     }
 
+    let include_tasks_by_root = match load_include_tasks_by_root(&state.db, &runs_by_id).await {
+        Ok(include_tasks_by_root) => include_tasks_by_root,
+        Err(_) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ListTaskRunsResponse::Error(ErrorResponse {
+                    message: "Failed to load included tasks".to_string(),
+                })),
+            );
+        }
+    };
+
     let mut task_runs = root_run_ids
         .into_iter()
-        .filter_map(|run_id| build_task_run_tree(&run_id, &runs_by_id, &children_by_parent))
+        .filter_map(|run_id| {
+            // A run started to satisfy a dependency is listed as a root of its
+            // own, but belongs to the invocation that started it.
+            let include_tasks = include_tasks_by_root
+                .get(&loaded_invocation_root_id(&runs_by_id, &run_id))
+                .cloned()
+                .unwrap_or_default();
+            build_task_run_tree(&run_id, &runs_by_id, &children_by_parent, &include_tasks)
+        })
         .collect::<Vec<_>>();
     task_runs.sort_by_key(|task_run| std::cmp::Reverse(task_run.updated_at));
 
@@ -208,15 +261,113 @@ pub async fn get_task_run(
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/tasks/{run_id}",
+    params(
+        ("run_id" = String, Path, description = "The task run id"),
+    ),
+    responses(
+        (status = 200, description = "Success", body = DeleteTaskRunResponse),
+        (status = 404, description = "Not Found", body = ErrorResponse),
+        (status = 409, description = "Conflict", body = ErrorResponse),
+        (status = 500, description = "Internal Server Error", body = ErrorResponse),
+    )
+)]
+pub async fn delete_task_run(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+) -> (StatusCode, Json<DeleteTaskRunResponse>) {
+    let task_run = match task_run::Entity::find_by_id(run_id.clone())
+        .one(&state.db)
+        .await
+    {
+        Ok(Some(task_run)) => task_run,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(DeleteTaskRunResponse::Error(ErrorResponse {
+                    message: "Task run not found".to_string(),
+                })),
+            );
+        }
+        Err(_) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(DeleteTaskRunResponse::Error(ErrorResponse {
+                    message: "Failed to load task run".to_string(),
+                })),
+            );
+        }
+    };
+
+    let all_runs = match task_run::Entity::find()
+        .filter(task_run::Column::Cwd.eq(task_run.cwd.clone()))
+        .all(&state.db)
+        .await
+    {
+        Ok(runs) => runs,
+        Err(_) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(DeleteTaskRunResponse::Error(ErrorResponse {
+                    message: "Failed to load child task runs".to_string(),
+                })),
+            );
+        }
+    };
+
+    let run_ids_to_delete = collect_descendant_run_ids(&all_runs, &run_id);
+    let is_active = all_runs
+        .iter()
+        .any(|run| run.status.is_active() && run_ids_to_delete.contains(&run.id));
+    if is_active {
+        return (
+            StatusCode::CONFLICT,
+            Json(DeleteTaskRunResponse::Error(ErrorResponse {
+                message: "Task run is still queued or running; cancel it first".to_string(),
+            })),
+        );
+    }
+
+    // Lines the runs wrote last may still be on their way to the database.
+    state.log_writer.flush().await;
+    if retention::delete_task_runs(&state.db, &run_ids_to_delete)
+        .await
+        .is_err()
+    {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(DeleteTaskRunResponse::Error(ErrorResponse {
+                message: "Failed to delete task runs".to_string(),
+            })),
+        );
+    }
+
+    (
+        StatusCode::OK,
+        Json(DeleteTaskRunResponse::Success(DeleteTaskRunResponseBody {
+            deleted_run_ids: run_ids_to_delete,
+        })),
+    )
+}
+
 #[utoipa::path(
     get,
     path = "/api/tasks/{run_id}/logs",
     params(
         ("run_id" = String, Path, description = "The task run id"),
         ("includeChildren" = Option<bool>, Query, description = "Whether to include logs from descendant task runs"),
+        ("attempt" = Option<u32>, Query, description = "Which attempt of the run to return logs from. Defaults to the latest"),
+        ("afterSequence" = Option<u64>, Query, description = "Only return lines with a greater sequence"),
+        ("beforeSequence" = Option<u64>, Query, description = "Only return lines with a smaller sequence"),
+        ("limit" = Option<u64>, Query, description = "Return at most this many lines, starting from the oldest that match"),
+        ("tail" = Option<u64>, Query, description = "Return at most this many lines, ending with the newest that match. Cannot be combined with limit"),
+        ("fromSequence" = Option<u64>, Query, description = "Websocket only: the last line a reconnecting client already has. The lines after it are sent as log messages instead of a snapshot"),
     ),
     responses(
         (status = 200, description = "Success", body = GetTaskRunLogsResponse),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
         (status = 404, description = "Not Found", body = ErrorResponse),
         (status = 500, description = "Internal Server Error", body = ErrorResponse),
     )
@@ -228,18 +379,41 @@ pub async fn get_task_run_logs(
     ws: Option<WebSocketUpgrade>,
 ) -> Response {
     let include_children = payload.include_children.unwrap_or(false);
+    let page = match LogPage::from_request(&payload) {
+        Ok(page) => page,
+        Err(message) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(GetTaskRunLogsResponse::Error(ErrorResponse { message })),
+            )
+                .into_response();
+        }
+    };
 
     if let Some(ws) = ws {
         return ws
-            .on_upgrade(move |socket| stream_task_run_logs(socket, state, run_id, include_children))
+            .on_upgrade(move |socket| {
+                stream_task_run_logs(
+                    socket,
+                    state,
+                    run_id,
+                    include_children,
+                    page,
+                    payload.from_sequence,
+                )
+            })
             .into_response();
     }
 
-    match load_task_run_logs(&state, &run_id, include_children).await {
-        Ok(Some(logs)) => (
+    match load_task_run_logs(&state, &run_id, include_children, payload.attempt, page).await {
+        Ok(Some(TaskRunLogsPage { logs, has_more })) => (
             StatusCode::OK,
             Json(GetTaskRunLogsResponse::Success(
-                GetTaskRunLogsResponseBody { run_id, logs },
+                GetTaskRunLogsResponseBody {
+                    run_id,
+                    logs,
+                    has_more,
+                },
             )),
         )
             .into_response(),
@@ -266,6 +440,13 @@ pub struct TaskRunStatusChangedEvent {
     pub task: String,
     pub cwd: String,
     pub status: TaskRunStatus,
+    /// Set once a running task's `ready` probe passes. Runs waiting on the task
+    /// treat that the same as it succeeding.
+    pub ready: bool,
+    /// Whether runs waiting on the task give up on it when this run ends without
+    /// succeeding. Not so for a run cancelled only to be restarted, or for an
+    /// optional subtask skipped because it was not included.
+    pub blocks_waiting_runs: bool,
 }
 
 #[utoipa::path(
@@ -295,6 +476,15 @@ pub async fn run_task(
                 );
             }
 
+            if e.is_invalid() {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(StartTaskResponse::Error(ErrorResponse {
+                        message: e.to_string(),
+                    })),
+                );
+            }
+
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(StartTaskResponse::Error(ErrorResponse {
@@ -351,9 +541,12 @@ pub async fn run_task(
         &state,
         payload.task.clone(),
         task,
+        config.get_task_env_layers(&payload.task),
         payload.cwd.clone(),
         None,
+        None,
         &included_optional_tasks,
+        encode_run_env(payload.env),
         false,
         false,
     )
@@ -370,6 +563,10 @@ pub async fn run_task(
         }
     };
 
+    if let Some(timeout_ms) = payload.timeout_ms {
+        spawn_task_tree_timeout(state.clone(), &task_run, Duration::from_millis(timeout_ms));
+    }
+
     (
         StatusCode::OK,
         Json(StartTaskResponse::Success(StartTaskResponseBody {
@@ -433,7 +630,10 @@ pub async fn cancel_task(
 
     let run_ids_to_cancel = collect_descendant_run_ids(&all_runs, &payload.run_id);
 
-    if let Err(_) = cancel_task_runs(&state, &run_ids_to_cancel).await {
+    if cancel_task_runs(&state, &run_ids_to_cancel, true)
+        .await
+        .is_err()
+    {
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(CancelTaskResponse::Error(ErrorResponse {
@@ -456,6 +656,7 @@ pub async fn cancel_task(
     request_body = RestartTaskRequest,
     responses(
         (status = 200, description = "Success", body = RestartTaskResponse),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
         (status = 404, description = "Not Found", body = ErrorResponse),
         (status = 500, description = "Internal Server Error", body = ErrorResponse),
     )
@@ -499,6 +700,15 @@ pub async fn restart_task(
                 );
             }
 
+            if e.is_invalid() {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(RestartTaskResponse::Error(ErrorResponse {
+                        message: e.to_string(),
+                    })),
+                );
+            }
+
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(RestartTaskResponse::Error(ErrorResponse {
@@ -537,7 +747,10 @@ pub async fn restart_task(
     };
 
     let run_ids_to_cancel = collect_descendant_run_ids(&all_runs, &payload.run_id);
-    if let Err(_) = cancel_task_runs(&state, &run_ids_to_cancel).await {
+    if cancel_task_runs(&state, &run_ids_to_cancel, false)
+        .await
+        .is_err()
+    {
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(RestartTaskResponse::Error(ErrorResponse {
@@ -546,16 +759,23 @@ pub async fn restart_task(
         );
     }
 
-    if let Err(_) = clear_task_run_logs_for_restart(&state, &run_ids_to_cancel).await {
+    // A restart by hand starts the `restart` policy's count over too.
+    if start_new_task_run_attempts(&state, &run_ids_to_cancel)
+        .await
+        .is_err()
+        || reset_task_run_restarts(&state, &run_ids_to_cancel)
+            .await
+            .is_err()
+    {
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(RestartTaskResponse::Error(ErrorResponse {
-                message: "Failed to clear task run logs for restart".to_string(),
+                message: "Failed to start new task run attempts".to_string(),
             })),
         );
     }
 
-    if let Err(_) = prepare_task_runs_for_restart(
+    if prepare_task_runs_for_restart(
         &state,
         &config,
         &all_runs,
@@ -563,6 +783,7 @@ pub async fn restart_task(
         &run_ids_to_cancel,
     )
     .await
+    .is_err()
     {
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -601,7 +822,8 @@ pub async fn restart_task(
             task_run.id.clone(),
             task_run.task.clone(),
             task_run.cwd.clone(),
-            root_task.command.clone(),
+            root_task,
+            config.get_task_env_layers(&task_run.task),
         );
     }
 
@@ -613,101 +835,567 @@ pub async fn restart_task(
     )
 }
 
-pub fn spawn_task_completion_listener(state: AppState) {
-    let mut events = state.task_events.subscribe();
-
-    tokio::spawn(async move {
-        loop {
-            match events.recv().await {
-                Ok(event) => {
-                    if event.status != TaskRunStatus::Success {
-                        continue;
-                    }
+#[utoipa::path(
+    post,
+    path = "/api/tasks/{run_id}/include",
+    params(
+        ("run_id" = String, Path, description = "The run of the optional subtask's parent"),
+    ),
+    request_body = IncludeTaskRequest,
+    responses(
+        (status = 200, description = "Success", body = IncludeTaskResponse),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 404, description = "Not Found", body = ErrorResponse),
+        (status = 500, description = "Internal Server Error", body = ErrorResponse),
+    )
+)]
+pub async fn include_task(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    Json(payload): Json<IncludeTaskRequest>,
+) -> (StatusCode, Json<IncludeTaskResponse>) {
+    let (parent_run, subtask, env_layers) =
+        match load_optional_subtask(&state, &run_id, &payload.task).await {
+            Ok(loaded) => loaded,
+            Err((status, message)) => {
+                return (
+                    status,
+                    Json(IncludeTaskResponse::Error(ErrorResponse { message })),
+                );
+            }
+        };
 
-                    if let Err(err) = trigger_waiting_task_runs(&state, &event).await {
-                        eprintln!(
-                            "Failed to trigger waiting task runs for {}: {}",
-                            event.task, err
-                        );
-                    }
+    match start_included_subtask(&state, &parent_run, payload.task, subtask, env_layers).await {
+        Ok(run_id) => (
+            StatusCode::OK,
+            Json(IncludeTaskResponse::Success(IncludeTaskResponseBody {
+                run_id,
+            })),
+        ),
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(IncludeTaskResponse::Error(ErrorResponse {
+                message: "Failed to include task".to_string(),
+            })),
+        ),
+    }
+}
 
-                    if let Err(err) = trigger_subtasks(&state, &event).await {
-                        eprintln!("Failed to trigger subtasks for {}: {}", event.task, err);
-                    }
-                }
-                Err(broadcast::error::RecvError::Lagged(_)) => {}
-                Err(broadcast::error::RecvError::Closed) => break,
-            }
+#[utoipa::path(
+    post,
+    path = "/api/tasks/{run_id}/exclude",
+    params(
+        ("run_id" = String, Path, description = "The run of the optional subtask's parent"),
+    ),
+    request_body = ExcludeTaskRequest,
+    responses(
+        (status = 200, description = "Success", body = ExcludeTaskResponse),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 404, description = "Not Found", body = ErrorResponse),
+        (status = 500, description = "Internal Server Error", body = ErrorResponse),
+    )
+)]
+pub async fn exclude_task(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    Json(payload): Json<ExcludeTaskRequest>,
+) -> (StatusCode, Json<ExcludeTaskResponse>) {
+    let (parent_run, _, _) = match load_optional_subtask(&state, &run_id, &payload.task).await {
+        Ok(loaded) => loaded,
+        Err((status, message)) => {
+            return (
+                status,
+                Json(ExcludeTaskResponse::Error(ErrorResponse { message })),
+            );
         }
-    });
+    };
+
+    match cancel_excluded_subtask(&state, &parent_run, &payload.task).await {
+        Ok(cancelled_run_ids) => (
+            StatusCode::OK,
+            Json(ExcludeTaskResponse::Success(ExcludeTaskResponseBody {
+                cancelled_run_ids,
+            })),
+        ),
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ExcludeTaskResponse::Error(ErrorResponse {
+                message: "Failed to exclude task".to_string(),
+            })),
+        ),
+    }
 }
 
-async fn stream_task_run_updates(mut socket: WebSocket, state: AppState, run_id: String) {
-    // Subscribe first so status transitions cannot be missed between
-    // initial snapshot and entering the receive loop.
-    let mut task_events = state.task_events.subscribe();
+#[utoipa::path(
+    post,
+    path = "/api/tasks/{run_id}/resize",
+    params(
+        ("run_id" = String, Path, description = "A run of a task with `pty: true`"),
+    ),
+    request_body = ResizeTaskRunRequest,
+    responses(
+        (status = 200, description = "Success", body = ResizeTaskRunResponse),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 404, description = "Not Found", body = ErrorResponse),
+        (status = 409, description = "Conflict", body = ErrorResponse),
+        (status = 500, description = "Internal Server Error", body = ErrorResponse),
+    )
+)]
+pub async fn resize_task_run(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    Json(payload): Json<ResizeTaskRunRequest>,
+) -> (StatusCode, Json<ResizeTaskRunResponse>) {
+    let error = |status: StatusCode, message: &str| {
+        (
+            status,
+            Json(ResizeTaskRunResponse::Error(ErrorResponse {
+                message: message.to_string(),
+            })),
+        )
+    };
 
-    if send_task_run_snapshot(&mut socket, &state, &run_id)
-        .await
-        .is_err()
-    {
-        return;
+    if payload.cols == 0 || payload.rows == 0 {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "A terminal needs at least one column and one row",
+        );
     }
 
-    loop {
-        tokio::select! {
-            next_message = socket.next() => {
-                match next_message {
-                    Some(Ok(Message::Close(_))) | None => break,
-                    Some(Ok(Message::Ping(payload))) => {
-                        if socket.send(Message::Pong(payload)).await.is_err() {
-                            break;
-                        }
-                    }
-                    Some(Ok(_)) => {}
-                    Some(Err(_)) => break,
-                }
-            }
-            event = task_events.recv() => match event {
-                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {
-                    if send_task_run_snapshot(&mut socket, &state, &run_id).await.is_err() {
-                        break;
-                    }
-                }
-                Err(broadcast::error::RecvError::Closed) => break,
-            },
-        }
+    let pty = state
+        .running_processes
+        .lock()
+        .await
+        .get(&run_id)
+        .and_then(|entry| entry.pty.clone());
+    let Some(pty) = pty else {
+        return match task_run::Entity::find_by_id(run_id).one(&state.db).await {
+            Ok(Some(_)) => error(
+                StatusCode::CONFLICT,
+                "Task run is not running under a terminal",
+            ),
+            Ok(None) => error(StatusCode::NOT_FOUND, "Task run not found"),
+            Err(_) => error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to load task run"),
+        };
+    };
+
+    match pty.resize(payload.cols, payload.rows) {
+        Ok(()) => (
+            StatusCode::OK,
+            Json(ResizeTaskRunResponse::Success(ResizeTaskRunResponseBody {
+                cols: payload.cols,
+                rows: payload.rows,
+            })),
+        ),
+        Err(_) => error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to resize the terminal",
+        ),
     }
 }
 
-async fn stream_task_run_logs(
-    mut socket: WebSocket,
-    state: AppState,
-    run_id: String,
-    include_children: bool,
-) {
-    // Subscribe first so we do not miss new log lines while sending snapshot.
-    let mut log_events = state.task_log_events.subscribe();
+#[utoipa::path(
+    post,
+    path = "/api/tasks/{run_id}/signal",
+    params(
+        ("run_id" = String, Path, description = "The running task run to signal"),
+    ),
+    request_body = SignalTaskRunRequest,
+    responses(
+        (status = 200, description = "Success", body = SignalTaskRunResponse),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 404, description = "Not Found", body = ErrorResponse),
+        (status = 409, description = "Conflict", body = ErrorResponse),
+        (status = 500, description = "Internal Server Error", body = ErrorResponse),
+        (status = 501, description = "Not Implemented", body = ErrorResponse),
+    )
+)]
+pub async fn signal_task_run(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    Json(payload): Json<SignalTaskRunRequest>,
+) -> (StatusCode, Json<SignalTaskRunResponse>) {
+    let error = |status: StatusCode, message: String| {
+        (
+            status,
+            Json(SignalTaskRunResponse::Error(ErrorResponse { message })),
+        )
+    };
 
-    let existing_logs = match load_task_run_logs(&state, &run_id, include_children).await {
-        Ok(Some(logs)) => logs,
-        Ok(None) => {
-            let payload = TaskRunLogsStreamMessage::Error {
-                message: "Task run not found".to_string(),
-            };
-            let _ = send_ws_json(&mut socket, &payload).await;
-            let _ = socket.send(Message::Close(None)).await;
-            return;
+    let Some(signal) = Signal::parse(&payload.signal) else {
+        return error(
+            StatusCode::BAD_REQUEST,
+            format!("Unknown signal \"{}\"", payload.signal),
+        );
+    };
+    if !cfg!(unix) {
+        return error(
+            StatusCode::NOT_IMPLEMENTED,
+            "Signals are not supported on this platform".to_string(),
+        );
+    }
+
+    let run = match task_run::Entity::find_by_id(run_id.clone())
+        .one(&state.db)
+        .await
+    {
+        Ok(Some(run)) => run,
+        Ok(None) => return error(StatusCode::NOT_FOUND, "Task run not found".to_string()),
+        Err(_) => {
+            return error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to load task run".to_string(),
+            );
         }
+    };
+    let pgid = state
+        .running_processes
+        .lock()
+        .await
+        .get(&run_id)
+        .and_then(|entry| entry.pgid);
+    let Some(pgid) = pgid else {
+        return error(
+            StatusCode::CONFLICT,
+            "Task run is not running a process".to_string(),
+        );
+    };
+
+    #[cfg(unix)]
+    let delivered = process_group::signal(pgid, signal);
+    #[cfg(not(unix))]
+    let delivered = {
+        let _ = pgid;
+        false
+    };
+    if !delivered {
+        return error(
+            StatusCode::CONFLICT,
+            format!("Could not send {} to the task run's processes", signal),
+        );
+    }
+
+    append_task_log_line(&state, run.id, run.task, format!("Sent {}", signal), true).await;
+    (
+        StatusCode::OK,
+        Json(SignalTaskRunResponse::Success(SignalTaskRunResponseBody {
+            signal: signal.name().to_string(),
+        })),
+    )
+}
+
+/// Loads the run `run_id` and its optional subtask `task_key`, or the status and
+/// message to answer with when either is missing or the task is not one.
+async fn load_optional_subtask(
+    state: &AppState,
+    run_id: &str,
+    task_key: &str,
+) -> Result<(task_run::Model, Task, Vec<EnvLayer>), (StatusCode, String)> {
+    let parent_run = match task_run::Entity::find_by_id(run_id.to_string())
+        .one(&state.db)
+        .await
+    {
+        Ok(Some(parent_run)) => parent_run,
+        Ok(None) => return Err((StatusCode::NOT_FOUND, "Task run not found".to_string())),
         Err(_) => {
-            let payload = TaskRunLogsStreamMessage::Error {
-                message: "Failed to load task run logs".to_string(),
-            };
-            let _ = send_ws_json(&mut socket, &payload).await;
-            let _ = socket.send(Message::Close(None)).await;
-            return;
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to load task run".to_string(),
+            ));
+        }
+    };
+
+    let config = match Config::load(&parent_run.cwd).await {
+        Ok(config) => config,
+        Err(e) if e.is_not_found() => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                "Task config file not found".to_string(),
+            ));
+        }
+        Err(e) if e.is_invalid() => return Err((StatusCode::BAD_REQUEST, e.to_string())),
+        Err(_) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to load task config file".to_string(),
+            ));
+        }
+    };
+
+    if task_key.rsplit_once(':').map(|(parent, _)| parent) != Some(parent_run.task.as_str()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Task '{}' is not a subtask of '{}'",
+                task_key, parent_run.task
+            ),
+        ));
+    }
+    let Some(subtask) = config.get_task(task_key.to_string()) else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Task '{}' was not found in task config", task_key),
+        ));
+    };
+    if !subtask.optional.unwrap_or(false) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Task '{}' is not optional", task_key),
+        ));
+    }
+
+    let env_layers = config.get_task_env_layers(task_key);
+    Ok((parent_run, subtask, env_layers))
+}
+
+/// Adds `task_key` to the include set of `parent_run`'s invocation and starts
+/// its run under `parent_run`, or queues it behind `parent_run` while that is
+/// still going. A parent that failed or was cancelled only has the set updated,
+/// for when it is restarted.
+async fn start_included_subtask(
+    state: &AppState,
+    parent_run: &task_run::Model,
+    task_key: String,
+    subtask: Task,
+    env_layers: Vec<EnvLayer>,
+) -> Result<Option<String>, DbErr> {
+    let root_run_id = invocation_root_id(&state.db, &parent_run.id).await?;
+    let mut include_tasks = load_include_tasks(&state.db, &root_run_id).await?;
+    if include_tasks.insert(task_key.clone()) {
+        save_include_tasks(&state.db, &root_run_id, &HashSet::from([task_key.clone()])).await?;
+    }
+
+    let existing_child = task_run::Entity::find()
+        .filter(task_run::Column::ParentRunId.eq(Some(parent_run.id.clone())))
+        .filter(task_run::Column::Task.eq(task_key.clone()))
+        .order_by_desc(task_run::Column::UpdatedAt)
+        .one(&state.db)
+        .await?;
+
+    match (parent_run.status, existing_child) {
+        // Left cancelled by an earlier restart or exclude, which would keep
+        // `trigger_subtasks` from starting another.
+        (status, Some(child)) if status.is_active() => {
+            if child.status == TaskRunStatus::Cancelled {
+                update_task_run_status(
+                    state,
+                    &child.id,
+                    TaskRunStatus::Queued,
+                    Some(parent_run.task.clone()),
+                )
+                .await?;
+            }
+            Ok(Some(child.id))
+        }
+        (status, None) if status.is_active() => Ok(None),
+        (TaskRunStatus::Success, Some(child)) => {
+            if child.status != TaskRunStatus::Cancelled {
+                return Ok(Some(child.id));
+            }
+            let waiting_on =
+                next_unmet_dependency(state, &child.cwd, &root_run_id, &subtask).await?;
+            update_task_run_status(state, &child.id, TaskRunStatus::Queued, waiting_on.clone())
+                .await?;
+            if waiting_on.is_none() {
+                start_task_run_execution(
+                    state.clone(),
+                    child.id.clone(),
+                    child.task.clone(),
+                    child.cwd.clone(),
+                    subtask,
+                    env_layers,
+                );
+            } else if subtask.auto_start_dependencies.unwrap_or(true) {
+                start_unmet_dependencies(state, &child, &subtask, &include_tasks).await?;
+            }
+            Ok(Some(child.id))
+        }
+        (TaskRunStatus::Success, None) => {
+            let child = create_task_run(
+                state,
+                task_key,
+                subtask,
+                env_layers,
+                parent_run.cwd.clone(),
+                Some(parent_run.id.clone()),
+                None,
+                &include_tasks,
+                parent_run.env.clone(),
+                false,
+                true,
+            )
+            .await?;
+            Ok(Some(child.id))
+        }
+        (_, child) => Ok(child.map(|child| child.id)),
+    }
+}
+
+/// Removes `task_key` from the include set of `parent_run`'s invocation and
+/// cancels its run under `parent_run`, with everything that run started. A run
+/// that already finished is marked cancelled too, so a restart doesn't take it
+/// for one that was included.
+async fn cancel_excluded_subtask(
+    state: &AppState,
+    parent_run: &task_run::Model,
+    task_key: &str,
+) -> Result<Vec<String>, DbErr> {
+    let root_run_id = invocation_root_id(&state.db, &parent_run.id).await?;
+    task_run_include_task::Entity::delete_by_id((root_run_id, task_key.to_string()))
+        .exec(&state.db)
+        .await?;
+
+    let Some(child) = task_run::Entity::find()
+        .filter(task_run::Column::ParentRunId.eq(Some(parent_run.id.clone())))
+        .filter(task_run::Column::Task.eq(task_key.to_string()))
+        .order_by_desc(task_run::Column::UpdatedAt)
+        .one(&state.db)
+        .await?
+    else {
+        return Ok(Vec::new());
+    };
+
+    let all_runs = task_run::Entity::find()
+        .filter(task_run::Column::Cwd.eq(child.cwd.clone()))
+        .all(&state.db)
+        .await?;
+    let run_ids_to_cancel = collect_descendant_run_ids(&all_runs, &child.id);
+    cancel_task_runs(state, &run_ids_to_cancel, true).await?;
+    if matches!(
+        child.status,
+        TaskRunStatus::Success | TaskRunStatus::Failed | TaskRunStatus::Interrupted
+    ) {
+        skip_task_run(state, &child.id).await?;
+    }
+    Ok(run_ids_to_cancel)
+}
+
+pub fn spawn_task_completion_listener(state: AppState) {
+    let mut events = state.task_events.subscribe();
+
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    if matches!(
+                        event.status,
+                        TaskRunStatus::Failed
+                            | TaskRunStatus::Cancelled
+                            | TaskRunStatus::Blocked
+                            | TaskRunStatus::Interrupted
+                    ) {
+                        if let Err(err) = block_waiting_task_runs(&state, &event).await {
+                            eprintln!(
+                                "Failed to block waiting task runs for {}: {}",
+                                event.task, err
+                            );
+                        }
+                        continue;
+                    }
+
+                    let succeeded = event.status == TaskRunStatus::Success;
+                    let became_ready = event.status == TaskRunStatus::Running && event.ready;
+                    if !succeeded && !became_ready {
+                        continue;
+                    }
+
+                    if let Err(err) = trigger_waiting_task_runs(&state, &event).await {
+                        eprintln!(
+                            "Failed to trigger waiting task runs for {}: {}",
+                            event.task, err
+                        );
+                    }
+
+                    if succeeded && let Err(err) = trigger_subtasks(&state, &event).await {
+                        eprintln!("Failed to trigger subtasks for {}: {}", event.task, err);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+async fn stream_task_run_updates(mut socket: WebSocket, state: AppState, run_id: String) {
+    // Subscribe first so status transitions cannot be missed between
+    // initial snapshot and entering the receive loop.
+    let mut task_events = state.task_events.subscribe();
+
+    if send_task_run_snapshot(&mut socket, &state, &run_id)
+        .await
+        .is_err()
+    {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            next_message = socket.next() => {
+                match next_message {
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(Message::Ping(payload))) => {
+                        if socket.send(Message::Pong(payload)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(_)) => break,
+                }
+            }
+            event = task_events.recv() => match event {
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {
+                    if send_task_run_snapshot(&mut socket, &state, &run_id).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
         }
+    }
+}
+
+async fn stream_task_run_logs(
+    mut socket: WebSocket,
+    state: AppState,
+    run_id: String,
+    include_children: bool,
+    // Only shapes the snapshot; lines written from then on stream regardless.
+    page: LogPage,
+    // The last line a reconnecting client already has. Instead of a snapshot,
+    // it gets the lines after that one, the same way live lines arrive.
+    from_sequence: Option<u64>,
+) {
+    // Subscribe first so we do not miss new log lines while sending snapshot.
+    let mut log_events = state.task_log_events.subscribe();
+
+    let page = match from_sequence {
+        Some(from_sequence) => LogPage {
+            after_sequence: Some(from_sequence),
+            ..LogPage::default()
+        },
+        None => page,
     };
+    let existing_logs =
+        match load_task_run_logs(&state, &run_id, include_children, None, page).await {
+            Ok(Some(logs)) => logs,
+            Ok(None) => {
+                let payload = TaskRunLogsStreamMessage::Error {
+                    message: "Task run not found".to_string(),
+                };
+                let _ = send_ws_json(&mut socket, &payload).await;
+                let _ = socket.send(Message::Close(None)).await;
+                return;
+            }
+            Err(_) => {
+                let payload = TaskRunLogsStreamMessage::Error {
+                    message: "Failed to load task run logs".to_string(),
+                };
+                let _ = send_ws_json(&mut socket, &payload).await;
+                let _ = socket.send(Message::Close(None)).await;
+                return;
+            }
+        };
 
     let mut included_run_ids = if include_children {
         match load_descendant_run_ids(&state, &run_id).await {
@@ -733,13 +1421,28 @@ async fn stream_task_run_logs(
         HashSet::from([run_id.clone()])
     };
 
-    let mut latest_sequence = existing_logs.last().map(|log| log.sequence).unwrap_or(0);
-    let snapshot = TaskRunLogsStreamMessage::Snapshot {
-        run_id: run_id.clone(),
-        logs: existing_logs,
-    };
-    if send_ws_json(&mut socket, &snapshot).await.is_err() {
-        return;
+    let mut latest_sequence = existing_logs
+        .logs
+        .last()
+        .map(|log| log.sequence)
+        .or(from_sequence)
+        .unwrap_or(0);
+    if from_sequence.is_some() {
+        if send_log_lines(&mut socket, existing_logs.logs)
+            .await
+            .is_err()
+        {
+            return;
+        }
+    } else {
+        let snapshot = TaskRunLogsStreamMessage::Snapshot {
+            run_id: run_id.clone(),
+            logs: existing_logs.logs,
+            has_more: existing_logs.has_more,
+        };
+        if send_ws_json(&mut socket, &snapshot).await.is_err() {
+            return;
+        }
     }
 
     loop {
@@ -774,8 +1477,13 @@ async fn stream_task_run_logs(
                     }
                 }
                 Err(broadcast::error::RecvError::Lagged(_)) => {
-                    let logs = match load_task_run_logs(&state, &run_id, include_children).await {
-                        Ok(Some(logs)) => logs,
+                    // Catch up from storage on the lines the channel dropped.
+                    let missed = LogPage {
+                        after_sequence: Some(latest_sequence),
+                        ..LogPage::default()
+                    };
+                    let missed = match load_task_run_logs(&state, &run_id, include_children, None, missed).await {
+                        Ok(Some(missed)) => missed.logs,
                         _ => break,
                     };
                     if include_children {
@@ -784,12 +1492,8 @@ async fn stream_task_run_logs(
                             _ => break,
                         }
                     }
-                    latest_sequence = logs.last().map(|log| log.sequence).unwrap_or(latest_sequence);
-                    let payload = TaskRunLogsStreamMessage::Snapshot {
-                        run_id: run_id.clone(),
-                        logs,
-                    };
-                    if send_ws_json(&mut socket, &payload).await.is_err() {
+                    latest_sequence = missed.last().map(|log| log.sequence).unwrap_or(latest_sequence);
+                    if send_log_lines(&mut socket, missed).await.is_err() {
                         break;
                     }
                 }
@@ -799,6 +1503,13 @@ async fn stream_task_run_logs(
     }
 }
 
+async fn send_log_lines(socket: &mut WebSocket, logs: Vec<TaskRunLogLine>) -> Result<(), ()> {
+    for log in logs {
+        send_ws_json(socket, &TaskRunLogsStreamMessage::Log { log }).await?;
+    }
+    Ok(())
+}
+
 async fn send_task_run_snapshot(
     socket: &mut WebSocket,
     state: &AppState,
@@ -822,49 +1533,132 @@ async fn send_task_run_snapshot(
     send_ws_json(socket, &payload).await
 }
 
+/// Which of the matching log lines to return, so long logs can be paged
+/// through rather than sent whole.
+#[derive(Debug, Clone, Copy, Default)]
+struct LogPage {
+    after_sequence: Option<u64>,
+    before_sequence: Option<u64>,
+    limit: Option<u64>,
+    /// Whether `limit` counts back from the newest line instead of forward
+    /// from the oldest.
+    from_end: bool,
+}
+
+impl LogPage {
+    fn from_request(request: &GetTaskRunLogsRequest) -> Result<LogPage, String> {
+        if request.limit.is_some() && request.tail.is_some() {
+            return Err("Pass either limit or tail, not both".to_string());
+        }
+        Ok(LogPage {
+            after_sequence: request.after_sequence,
+            before_sequence: request.before_sequence,
+            limit: request.limit.or(request.tail),
+            from_end: request.tail.is_some(),
+        })
+    }
+}
+
+struct TaskRunLogsPage {
+    /// Oldest first, whichever end the page was taken from.
+    logs: Vec<TaskRunLogLine>,
+    has_more: bool,
+}
+
+/// The run's logs from `attempt`, or its latest attempt. With
+/// `include_children`, each descendant contributes the attempt it was on that
+/// many restarts ago, which lines up with the run's own attempts whenever the
+/// tree was restarted as a whole.
 async fn load_task_run_logs(
     state: &AppState,
     run_id: &str,
     include_children: bool,
-) -> Result<Option<Vec<TaskRunLogLine>>, DbErr> {
-    let run_ids = if include_children {
-        match load_descendant_run_ids(state, run_id).await? {
-            Some(run_ids) => run_ids,
-            None => return Ok(None),
-        }
+    attempt: Option<u32>,
+    page: LogPage,
+) -> Result<Option<TaskRunLogsPage>, DbErr> {
+    // Lines already sent to subscribers may not be committed yet.
+    state.log_writer.flush().await;
+    let Some(task_run) = task_run::Entity::find_by_id(run_id.to_string())
+        .one(&state.db)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let runs = if include_children {
+        let all_runs = task_run::Entity::find()
+            .filter(task_run::Column::Cwd.eq(task_run.cwd.clone()))
+            .all(&state.db)
+            .await?;
+        let run_ids = collect_descendant_run_ids(&all_runs, run_id)
+            .into_iter()
+            .collect::<HashSet<_>>();
+        all_runs
+            .into_iter()
+            .filter(|run| run_ids.contains(&run.id))
+            .collect()
     } else {
-        let task_run_exists = task_run::Entity::find_by_id(run_id.to_string())
-            .one(&state.db)
-            .await?
-            .is_some();
-        if !task_run_exists {
-            return Ok(None);
-        }
-        HashSet::from([run_id.to_string()])
+        vec![task_run.clone()]
     };
 
-    if run_ids.is_empty() {
-        return Ok(None);
+    let restarts_back = attempt.map_or(0, |attempt| task_run.attempt - attempt as i32);
+    let mut attempts = Condition::any();
+    for run in &runs {
+        let run_attempt = run.attempt - restarts_back;
+        if run_attempt >= 1 {
+            attempts = attempts.add(
+                Condition::all()
+                    .add(task_run_log::Column::RunId.eq(run.id.clone()))
+                    .add(task_run_log::Column::Attempt.eq(run_attempt)),
+            );
+        }
+    }
+    if attempts.is_empty() {
+        return Ok(Some(TaskRunLogsPage {
+            logs: Vec::new(),
+            has_more: false,
+        }));
     }
 
-    let logs = task_run_log::Entity::find()
-        .filter(task_run_log::Column::RunId.is_in(run_ids.into_iter().collect::<Vec<_>>()))
-        .order_by_asc(task_run_log::Column::Id)
-        .all(&state.db)
-        .await
-        .map(|rows| {
-            rows.into_iter()
-                .map(|row| TaskRunLogLine {
-                    run_id: row.run_id,
-                    task: row.task,
-                    line: row.line,
-                    is_stderr: row.is_stderr,
-                    timestamp: row.timestamp,
-                    sequence: row.id as u64,
-                })
-                .collect::<Vec<_>>()
-        })?;
-    Ok(Some(logs))
+    let mut query = task_run_log::Entity::find().filter(attempts);
+    if let Some(after_sequence) = page.after_sequence {
+        query = query.filter(task_run_log::Column::Id.gt(after_sequence as i64));
+    }
+    if let Some(before_sequence) = page.before_sequence {
+        query = query.filter(task_run_log::Column::Id.lt(before_sequence as i64));
+    }
+    query = if page.from_end {
+        query.order_by_desc(task_run_log::Column::Id)
+    } else {
+        query.order_by_asc(task_run_log::Column::Id)
+    };
+    // One extra row tells whether anything is left past the page.
+    if let Some(limit) = page.limit {
+        query = query.limit(limit.saturating_add(1));
+    }
+
+    let mut rows = query.all(&state.db).await?;
+    let has_more = page.limit.is_some_and(|limit| rows.len() as u64 > limit);
+    if let Some(limit) = page.limit {
+        rows.truncate(limit as usize);
+    }
+    if page.from_end {
+        rows.reverse();
+    }
+
+    let logs = rows
+        .into_iter()
+        .map(|row| TaskRunLogLine {
+            run_id: row.run_id,
+            task: row.task,
+            line: row.line,
+            is_stderr: row.is_stderr,
+            timestamp: row.timestamp,
+            sequence: row.id as u64,
+            attempt: row.attempt as u32,
+        })
+        .collect();
+    Ok(Some(TaskRunLogsPage { logs, has_more }))
 }
 
 async fn load_descendant_run_ids(
@@ -888,27 +1682,37 @@ async fn load_descendant_run_ids(
     Ok(Some(run_ids))
 }
 
-async fn send_ws_json<T: Serialize>(socket: &mut WebSocket, payload: &T) -> Result<(), ()> {
+pub(crate) async fn send_ws_json<T: Serialize>(
+    socket: &mut WebSocket,
+    payload: &T,
+) -> Result<(), ()> {
     let message = serde_json::to_string(payload).map_err(|_| ())?;
     socket.send(Message::Text(message)).await.map_err(|_| ())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn create_task_run(
     state: &AppState,
     task_key: String,
     task: Task,
+    env_layers: Vec<EnvLayer>,
     cwd: String,
     parent_run_id: Option<String>,
+    started_by_run_id: Option<String>,
     include_tasks: &HashSet<String>,
+    env: Option<String>,
     start_cancelled: bool,
     respect_dependencies: bool,
 ) -> Result<task_run::Model, DbErr> {
-    let waiting_on = if start_cancelled {
-        None
-    } else if !respect_dependencies {
+    let id = nanoid!(21, &TASK_RUN_ID_ALPHABET);
+    let waiting_on = if start_cancelled || !respect_dependencies {
         None
     } else {
-        next_unmet_dependency(&state.db, &cwd, &task).await?
+        let root_run_id = match parent_run_id.as_ref().or(started_by_run_id.as_ref()) {
+            Some(linked_run_id) => invocation_root_id(&state.db, linked_run_id).await?,
+            None => id.clone(),
+        };
+        next_unmet_dependency(state, &cwd, &root_run_id, &task).await?
     };
     let status = if start_cancelled {
         TaskRunStatus::Cancelled
@@ -916,27 +1720,41 @@ async fn create_task_run(
         TaskRunStatus::Queued
     };
 
+    let working_dir = resolve_task_working_dir(&cwd, &task);
     let model = task_run::ActiveModel {
-        id: Set(nanoid!(21, &TASK_RUN_ID_ALPHABET)),
+        id: Set(id),
         task: Set(task_key),
         cwd: Set(cwd),
         parent_run_id: Set(parent_run_id),
+        started_by_run_id: Set(started_by_run_id),
         status: Set(status),
         updated_at: Set(chrono::Utc::now().timestamp_millis()),
         waiting_on: Set(waiting_on),
+        blocked_by: Set(None),
+        working_dir: Set(Some(working_dir.to_string_lossy().to_string())),
+        env: Set(env),
+        ready_at: Set(None),
+        exit_code: Set(None),
+        signal: Set(None),
+        pid: Set(None),
+        started_at: Set(None),
+        finished_at: Set(None),
+        attempt: Set(1),
+        restarts: Set(0),
+        timed_out_after_ms: Set(None),
     };
 
     let task_run = model.insert(&state.db).await?;
-    state
-        .run_include_tasks
-        .lock()
-        .await
-        .insert(task_run.id.clone(), include_tasks.clone());
+    if task_run.parent_run_id.is_none() && task_run.started_by_run_id.is_none() {
+        save_include_tasks(&state.db, &task_run.id, include_tasks).await?;
+    }
     let _ = state.task_events.send(TaskRunStatusChangedEvent {
         run_id: task_run.id.clone(),
         task: task_run.task.clone(),
         cwd: task_run.cwd.clone(),
         status: task_run.status,
+        ready: task_run.ready_at.is_some(),
+        blocks_waiting_runs: !start_cancelled,
     });
 
     if task_run.status == TaskRunStatus::Queued && task_run.waiting_on.is_none() {
@@ -945,75 +1763,390 @@ async fn create_task_run(
             task_run.id.clone(),
             task_run.task.clone(),
             task_run.cwd.clone(),
-            task.command.clone(),
+            task,
+            env_layers,
         );
+    } else if task_run.waiting_on.is_some() && task.auto_start_dependencies.unwrap_or(true) {
+        start_unmet_dependencies(state, &task_run, &task, include_tasks).await?;
     }
 
     Ok(task_run)
 }
 
+/// Starts a run for every dependency of `run` that has neither succeeded nor
+/// has a run underway, so `run` is not left waiting on something that never
+/// happens. A dependency on a sibling subtask starts as a child of the same
+/// parent run, which then finds it already there instead of starting another.
+async fn start_unmet_dependencies(
+    state: &AppState,
+    run: &task_run::Model,
+    task: &Task,
+    include_tasks: &HashSet<String>,
+) -> Result<(), DbErr> {
+    let config = match Config::load(&run.cwd).await {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!(
+                "Failed to load config for dependencies of {}: {}",
+                run.task, err
+            );
+            return Ok(());
+        }
+    };
+    let parent_task_key = run.task.rsplit_once(':').map(|(parent, _)| parent);
+    let root_run_id = invocation_root_id(&state.db, &run.id).await?;
+
+    for dependency in task.depends_on.iter().flatten() {
+        if is_dependency_satisfied(state, &run.cwd, &root_run_id, dependency).await?
+            || has_active_run_for(state, &run.cwd, &root_run_id, dependency).await?
+        {
+            continue;
+        }
+        let dependency_key = dependency.task();
+        let Some(dependency_task) = config.get_task(dependency_key.to_string()) else {
+            continue;
+        };
+
+        let is_sibling = parent_task_key.is_some()
+            && dependency_key.rsplit_once(':').map(|(parent, _)| parent) == parent_task_key;
+        // Boxed because starting a dependency can start its dependencies in turn.
+        Box::pin(create_task_run(
+            state,
+            dependency_key.to_string(),
+            dependency_task,
+            config.get_task_env_layers(dependency_key),
+            run.cwd.clone(),
+            run.parent_run_id.clone().filter(|_| is_sibling),
+            Some(run.id.clone()),
+            include_tasks,
+            run.env.clone(),
+            false,
+            true,
+        ))
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn has_active_run(db: &DatabaseConnection, cwd: &str, task_key: &str) -> Result<bool, DbErr> {
+    let active_run = task_run::Entity::find()
+        .filter(task_run::Column::Cwd.eq(cwd.to_string()))
+        .filter(task_run::Column::Task.eq(task_key.to_string()))
+        .filter(task_run::Column::Status.is_in([TaskRunStatus::Queued, TaskRunStatus::Running]))
+        .one(db)
+        .await?;
+    Ok(active_run.is_some())
+}
+
+/// Like `has_active_run`, but a `sameRootRun` dependency only counts a run
+/// underway in the same invocation.
+async fn has_active_run_for(
+    state: &AppState,
+    cwd: &str,
+    root_run_id: &str,
+    dependency: &Dependency,
+) -> Result<bool, DbErr> {
+    if dependency.satisfied_by() != SatisfactionPolicy::SameRootRun {
+        return has_active_run(&state.db, cwd, dependency.task()).await;
+    }
+
+    let run_ids = invocation_run_ids(&state.db, root_run_id).await?;
+    let active_run = task_run::Entity::find()
+        .filter(task_run::Column::Cwd.eq(cwd.to_string()))
+        .filter(task_run::Column::Task.eq(dependency.task().to_string()))
+        .filter(task_run::Column::Status.is_in([TaskRunStatus::Queued, TaskRunStatus::Running]))
+        .filter(task_run::Column::Id.is_in(run_ids))
+        .one(&state.db)
+        .await?;
+    Ok(active_run.is_some())
+}
+
+async fn save_include_tasks(
+    db: &DatabaseConnection,
+    root_run_id: &str,
+    include_tasks: &HashSet<String>,
+) -> Result<(), DbErr> {
+    if include_tasks.is_empty() {
+        return Ok(());
+    }
+    task_run_include_task::Entity::insert_many(include_tasks.iter().map(|task| {
+        task_run_include_task::ActiveModel {
+            root_run_id: Set(root_run_id.to_string()),
+            task: Set(task.clone()),
+        }
+    }))
+    .exec(db)
+    .await?;
+    Ok(())
+}
+
+/// The optional subtasks included by the invocation `root_run_id` started.
+async fn load_include_tasks(
+    db: &DatabaseConnection,
+    root_run_id: &str,
+) -> Result<HashSet<String>, DbErr> {
+    Ok(task_run_include_task::Entity::find()
+        .filter(task_run_include_task::Column::RootRunId.eq(root_run_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|include| include.task)
+        .collect())
+}
+
+/// The run that started the invocation `run_id` belongs to, found by following
+/// parent runs and the runs that started dependencies back to one with neither.
+async fn invocation_root_id(db: &DatabaseConnection, run_id: &str) -> Result<String, DbErr> {
+    let mut root_run_id = run_id.to_string();
+    let mut seen = HashSet::new();
+    while seen.insert(root_run_id.clone()) {
+        let Some(run) = task_run::Entity::find_by_id(root_run_id.clone())
+            .one(db)
+            .await?
+        else {
+            break;
+        };
+        match run.parent_run_id.or(run.started_by_run_id) {
+            Some(linked_run_id) => root_run_id = linked_run_id,
+            None => break,
+        }
+    }
+    Ok(root_run_id)
+}
+
+/// Like `invocation_root_id`, but only follows runs already in `runs_by_id`.
+fn loaded_invocation_root_id(
+    runs_by_id: &HashMap<String, task_run::Model>,
+    run_id: &str,
+) -> String {
+    let mut root_run_id = run_id.to_string();
+    let mut seen = HashSet::new();
+    while seen.insert(root_run_id.clone()) {
+        match runs_by_id
+            .get(&root_run_id)
+            .and_then(|run| run.parent_run_id.clone().or(run.started_by_run_id.clone()))
+        {
+            Some(linked_run_id) if runs_by_id.contains_key(&linked_run_id) => {
+                root_run_id = linked_run_id
+            }
+            _ => break,
+        }
+    }
+    root_run_id
+}
+
+/// Every run whose `invocation_root_id` is `root_run_id`, found by walking
+/// down from it one generation per query.
+async fn invocation_run_ids(
+    db: &DatabaseConnection,
+    root_run_id: &str,
+) -> Result<Vec<String>, DbErr> {
+    let mut run_ids = vec![root_run_id.to_string()];
+    let mut seen = HashSet::from([root_run_id.to_string()]);
+    let mut generation = run_ids.clone();
+    while !generation.is_empty() {
+        // A run with a parent belongs to its parent's invocation, even when
+        // another run started it.
+        let linked_here = Condition::any()
+            .add(task_run::Column::ParentRunId.is_in(generation.clone()))
+            .add(
+                Condition::all()
+                    .add(task_run::Column::ParentRunId.is_null())
+                    .add(task_run::Column::StartedByRunId.is_in(generation)),
+            );
+        generation = task_run::Entity::find()
+            .select_only()
+            .column(task_run::Column::Id)
+            .filter(linked_here)
+            .into_tuple::<String>()
+            .all(db)
+            .await?
+            .into_iter()
+            .filter(|run_id| seen.insert(run_id.clone()))
+            .collect();
+        run_ids.extend(generation.iter().cloned());
+    }
+    Ok(run_ids)
+}
+
 fn start_task_run_execution(
     state: AppState,
     run_id: String,
     task_key: String,
     cwd: String,
-    command: Option<String>,
+    task: Task,
+    env_layers: Vec<EnvLayer>,
 ) {
     tokio::spawn(async move {
-        let running_updated_at = match mark_task_run_running(&state, &run_id).await {
-            Ok(Some(updated_at)) => updated_at,
-            Ok(None) => return,
-            Err(err) => {
-                eprintln!("Failed to set task run {} to running: {}", run_id, err);
-                return;
-            }
-        };
+        let mut restarts = RestartTracker::new(task.restart.clone());
+        loop {
+            // Resolved on every start rather than once at creation so a
+            // restart picks up a `cwd` edited in the meantime.
+            let working_dir = resolve_task_working_dir(&cwd, &task);
+            let running_run = match mark_task_run_running(&state, &run_id, &working_dir).await {
+                Ok(Some(running_run)) => running_run,
+                Ok(None) => return,
+                Err(err) => {
+                    eprintln!("Failed to set task run {} to running: {}", run_id, err);
+                    return;
+                }
+            };
 
-        let final_status = run_command(
-            state.clone(),
-            state.running_processes.clone(),
-            run_id.clone(),
-            &cwd,
-            &task_key,
-            command,
-        )
-        .await;
+            let run_env = decode_run_env(running_run.env.as_deref());
+            let started = Instant::now();
+            let outcome = run_command(
+                state.clone(),
+                run_id.clone(),
+                &cwd,
+                &working_dir,
+                &task_key,
+                &task,
+                &env_layers,
+                &run_env,
+            )
+            .await;
 
-        let existing_run = match task_run::Entity::find_by_id(run_id.clone())
-            .one(&state.db)
-            .await
-        {
-            Ok(Some(task_run)) => task_run,
-            Ok(None) => return,
-            Err(err) => {
-                eprintln!(
-                    "Failed to load task run {} after execution: {}",
-                    run_id, err
-                );
+            let existing_run = match task_run::Entity::find_by_id(run_id.clone())
+                .one(&state.db)
+                .await
+            {
+                Ok(Some(task_run)) => task_run,
+                Ok(None) => return,
+                Err(err) => {
+                    eprintln!(
+                        "Failed to load task run {} after execution: {}",
+                        run_id, err
+                    );
+                    return;
+                }
+            };
+
+            // Recorded even for a run that was cancelled meanwhile, as how its
+            // process went down is what explains it.
+            if existing_run.started_at == running_run.started_at
+                && let Err(err) = record_task_run_exit(&state, existing_run.clone(), &outcome).await
+            {
+                eprintln!("Failed to record exit of task run {}: {}", run_id, err);
+            }
+
+            if existing_run.status == TaskRunStatus::Cancelled {
                 return;
             }
-        };
 
-        if existing_run.status == TaskRunStatus::Cancelled {
-            return;
-        }
+            if existing_run.status != TaskRunStatus::Running
+                || existing_run.started_at != running_run.started_at
+            {
+                // A newer execution already changed this run state.
+                // Ignore stale completion from a previous process instance.
+                return;
+            }
 
-        if existing_run.status != TaskRunStatus::Running
-            || existing_run.updated_at != running_updated_at
-        {
-            // A newer execution already changed this run state.
-            // Ignore stale completion from a previous process instance.
-            return;
-        }
+            let restart = if task_has_no_command(&task) {
+                None
+            } else {
+                restarts.after_exit(outcome.status, started.elapsed())
+            };
+            match restart {
+                Some(Restart::After(delay)) => {
+                    match restart_task_run_after(&state, &existing_run, &outcome, delay).await {
+                        Ok(true) => continue,
+                        Ok(false) => return,
+                        Err(err) => {
+                            eprintln!("Failed to restart task run {}: {}", run_id, err);
+                        }
+                    }
+                }
+                Some(Restart::Exhausted(max_restarts)) => {
+                    append_task_log_line(
+                        &state,
+                        run_id.clone(),
+                        task_key.clone(),
+                        format!("Not restarting after {max_restarts} restarts in a row"),
+                        true,
+                    )
+                    .await;
+                }
+                None => {}
+            }
 
-        if let Err(err) = update_task_run_status(&state, &run_id, final_status, None).await {
-            eprintln!("Failed to set task run {} to running: {}", run_id, err);
+            if let Err(err) = update_task_run_status(&state, &run_id, outcome.status, None).await {
+                eprintln!("Failed to set task run {} to running: {}", run_id, err);
+            }
             return;
         }
     });
 }
 
-async fn mark_task_run_running(state: &AppState, run_id: &str) -> Result<Option<i64>, DbErr> {
+/// Waits out `delay` and moves `task_run` on to a new attempt, for its
+/// `restart` policy to start again. The run stays `Running` in between, so
+/// nothing depending on it reacts to the exit. Returns false when the run was
+/// cancelled or restarted by hand while waiting.
+async fn restart_task_run_after(
+    state: &AppState,
+    task_run: &task_run::Model,
+    outcome: &RunOutcome,
+    delay: Duration,
+) -> Result<bool, DbErr> {
+    let exited = match (outcome.exit_code, &outcome.signal) {
+        (_, Some(signal)) => format!("Killed by {signal}"),
+        (Some(exit_code), None) => format!("Exited with code {exit_code}"),
+        (None, None) => "Failed to start".to_string(),
+    };
+    append_task_log_line(
+        state,
+        task_run.id.clone(),
+        task_run.task.clone(),
+        format!("{exited}, restarting in {}", format_log_duration(delay)),
+        outcome.status != TaskRunStatus::Success,
+    )
+    .await;
+
+    tokio::time::sleep(delay).await;
+    let Some(current) = task_run::Entity::find_by_id(task_run.id.clone())
+        .one(&state.db)
+        .await?
+    else {
+        return Ok(false);
+    };
+    if current.status != TaskRunStatus::Running || current.started_at != task_run.started_at {
+        return Ok(false);
+    }
+
+    let run_ids = [task_run.id.clone()];
+    start_new_task_run_attempts(state, &run_ids).await?;
+    task_run::Entity::update_many()
+        .col_expr(
+            task_run::Column::Restarts,
+            Expr::col(task_run::Column::Restarts).add(1),
+        )
+        .filter(task_run::Column::Id.eq(task_run.id.clone()))
+        .exec(&state.db)
+        .await?;
+    append_task_log_line(
+        state,
+        task_run.id.clone(),
+        task_run.task.clone(),
+        format!("↻ Restart {}", current.restarts + 1),
+        false,
+    )
+    .await;
+    Ok(true)
+}
+
+fn format_log_duration(duration: Duration) -> String {
+    if duration.subsec_millis() == 0 {
+        format!("{}s", duration.as_secs())
+    } else {
+        format!("{}ms", duration.as_millis())
+    }
+}
+
+async fn mark_task_run_running(
+    state: &AppState,
+    run_id: &str,
+    working_dir: &FsPath,
+) -> Result<Option<task_run::Model>, DbErr> {
     let Some(task_run) = task_run::Entity::find_by_id(run_id.to_string())
         .one(&state.db)
         .await?
@@ -1021,38 +2154,115 @@ async fn mark_task_run_running(state: &AppState, run_id: &str) -> Result<Option<
         return Ok(None);
     };
 
-    let updated_at = chrono::Utc::now().timestamp_millis();
     let mut active = task_run.into_active_model();
     active.status = Set(TaskRunStatus::Running);
     active.waiting_on = Set(None);
-    active.updated_at = Set(updated_at);
+    active.blocked_by = Set(None);
+    active.working_dir = Set(Some(working_dir.to_string_lossy().to_string()));
+    active.ready_at = Set(None);
+    active.exit_code = Set(None);
+    active.signal = Set(None);
+    active.pid = Set(None);
+    active.timed_out_after_ms = Set(None);
+    let now = chrono::Utc::now().timestamp_millis();
+    active.started_at = Set(Some(now));
+    active.finished_at = Set(None);
+    active.updated_at = Set(now);
     let updated = active.update(&state.db).await?;
     let _ = state.task_events.send(TaskRunStatusChangedEvent {
-        run_id: updated.id,
-        task: updated.task,
-        cwd: updated.cwd,
+        run_id: updated.id.clone(),
+        task: updated.task.clone(),
+        cwd: updated.cwd.clone(),
         status: updated.status,
+        ready: updated.ready_at.is_some(),
+        blocks_waiting_runs: true,
     });
 
-    Ok(Some(updated_at))
+    Ok(Some(updated))
+}
+
+async fn record_task_run_pid(state: &AppState, run_id: &str, pid: u32) -> Result<(), DbErr> {
+    let Some(task_run) = task_run::Entity::find_by_id(run_id.to_string())
+        .one(&state.db)
+        .await?
+    else {
+        return Ok(());
+    };
+
+    let mut active = task_run.into_active_model();
+    active.pid = Set(i32::try_from(pid).ok());
+    active.update(&state.db).await?;
+    Ok(())
+}
+
+/// Leaves `status` and `updated_at` alone; the caller decides whether the
+/// outcome's status still applies.
+async fn record_task_run_exit(
+    state: &AppState,
+    task_run: task_run::Model,
+    outcome: &RunOutcome,
+) -> Result<(), DbErr> {
+    let mut active = task_run.into_active_model();
+    active.exit_code = Set(outcome.exit_code);
+    active.signal = Set(outcome.signal.clone());
+    if let Some(timeout) = outcome.timed_out_after {
+        active.timed_out_after_ms = Set(Some(timeout.as_millis() as i64));
+    }
+    active.finished_at = Set(Some(chrono::Utc::now().timestamp_millis()));
+    active.update(&state.db).await?;
+    Ok(())
+}
+
+fn encode_run_env(env: Option<IndexMap<String, String>>) -> Option<String> {
+    env.filter(|env| !env.is_empty())
+        .and_then(|env| serde_json::to_string(&env).ok())
+}
+
+fn decode_run_env(env: Option<&str>) -> IndexMap<String, String> {
+    env.and_then(|env| serde_json::from_str(env).ok())
+        .unwrap_or_default()
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn run_command(
     state: AppState,
-    running_processes: std::sync::Arc<Mutex<HashMap<String, RunningProcessEntry>>>,
     run_id: String,
     cwd: &str,
+    working_dir: &FsPath,
     task_key: &str,
-    command: Option<String>,
-) -> TaskRunStatus {
-    let Some(command) = command else {
-        return TaskRunStatus::Success;
+    task: &Task,
+    env_layers: &[EnvLayer],
+    run_env: &IndexMap<String, String>,
+) -> RunOutcome {
+    let running_processes = state.running_processes.clone();
+    let Some(command) = task.command.clone() else {
+        return TaskRunStatus::Success.into();
     };
 
     if command.trim().is_empty() {
-        return TaskRunStatus::Success;
+        return TaskRunStatus::Success.into();
+    }
+
+    if !working_dir.is_dir() {
+        append_task_log_line(
+            &state,
+            run_id.clone(),
+            task_key.to_string(),
+            format!("Working directory {} does not exist", working_dir.display()),
+            true,
+        )
+        .await;
+        return TaskRunStatus::Failed.into();
     }
 
+    let env = match resolve_task_env(cwd, env_layers, run_env).await {
+        Ok(env) => env,
+        Err(message) => {
+            append_task_log_line(&state, run_id.clone(), task_key.to_string(), message, true).await;
+            return TaskRunStatus::Failed.into();
+        }
+    };
+
     append_task_log_line(
         &state,
         run_id.clone(),
@@ -1062,54 +2272,124 @@ async fn run_command(
     )
     .await;
 
-    let resolved_cwd = resolve_command_cwd(cwd);
+    let resolved_cwd = resolve_command_cwd(working_dir);
     let mut command_builder = Command::new(resolve_command_shell());
     if let Some(path) = build_task_command_path() {
         command_builder.env("PATH", path);
     }
     command_builder.env("PWD", &resolved_cwd);
-    if std::env::var_os("HOME").is_none() {
-        if let Some(home) = infer_home_from_path(&resolved_cwd) {
-            command_builder.env("HOME", home);
-        }
+    if std::env::var_os("HOME").is_none()
+        && let Some(home) = infer_home_from_path(&resolved_cwd)
+    {
+        command_builder.env("HOME", home);
     }
-    // Signal to shell and child processes that no interactive TTY is available,
-    // while still advertising color support for rich log output.
+    // Without a terminal, signal to shell and child processes that no
+    // interactive TTY is available, while still advertising color support
+    // for rich log output.
+    let use_pty = task.pty.unwrap_or(false);
     command_builder.env("TERM", "xterm-256color");
-    command_builder.env("CI", "true");
+    if !use_pty {
+        command_builder.env("CI", "true");
+    }
     if std::env::var_os("NO_COLOR").is_none() {
         command_builder.env("FORCE_COLOR", "1");
         command_builder.env("CLICOLOR", "1");
         command_builder.env("CLICOLOR_FORCE", "1");
     }
+    // Applied last so a task can override any of the defaults above.
+    command_builder.envs(&env);
     #[cfg(windows)]
     command_builder.arg("/c").arg(command.as_str());
     #[cfg(not(windows))]
     command_builder.arg("-lc").arg(command.as_str());
-    command_builder
-        .current_dir(&resolved_cwd)
-        .stdin(Stdio::null())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
-    #[cfg(unix)]
-    {
-        command_builder.process_group(0);
-    }
+    command_builder.current_dir(&resolved_cwd);
+    let pty = if use_pty {
+        match Pty::attach(&mut command_builder, pty::DEFAULT_SIZE) {
+            Ok(pty) => Some(pty),
+            Err(err) => {
+                append_task_log_line(
+                    &state,
+                    run_id.clone(),
+                    task_key.to_string(),
+                    format!("Failed to open a terminal: {}", err),
+                    true,
+                )
+                .await;
+                return TaskRunStatus::Failed.into();
+            }
+        }
+    } else {
+        let stdin = if task.stdin.unwrap_or(false) {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        };
+        command_builder
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        #[cfg(unix)]
+        {
+            command_builder.process_group(0);
+        }
+        None
+    };
 
-    match command_builder.spawn() {
+    let spawned = command_builder.spawn();
+    // Closes this end's copies of the terminal, so reading it ends when the
+    // command is done with it.
+    drop(command_builder);
+    match spawned {
         Ok(mut child) => {
             let execution_id = nanoid!(10, &TASK_RUN_ID_ALPHABET);
+            let input = match &pty {
+                Some(pty) => Some(ProcessInput::Pty(pty.clone())),
+                None => child.stdin.take().map(ProcessInput::stdin),
+            };
+            if let Some(pid) = child.id()
+                && let Err(err) = record_task_run_pid(&state, &run_id, pid).await
+            {
+                eprintln!("Failed to record pid of task run {}: {}", run_id, err);
+            }
             let (cancel_tx, cancel_rx) = oneshot::channel::<()>();
+            // Never sent on; dropped when this function returns.
+            let (_stopped_tx, stopped_rx) = oneshot::channel::<()>();
+            // Started before the output is streamed so a log probe sees every line.
+            let (not_ready_tx, not_ready_rx) = oneshot::channel::<()>();
+            let readiness_probe = task.ready.clone().map(|probe| {
+                tokio::spawn(run_readiness_probe(
+                    state.clone(),
+                    run_id.clone(),
+                    task_key.to_string(),
+                    probe,
+                    state.task_log_events.subscribe(),
+                    not_ready_tx,
+                ))
+            });
             running_processes.lock().await.insert(
                 run_id.clone(),
                 RunningProcessEntry {
                     execution_id: execution_id.clone(),
                     cancel_tx,
+                    stopped_rx,
+                    pgid: child.id(),
+                    pty: pty.clone(),
+                    input,
                 },
             );
 
             let mut stream_tasks = Vec::new();
 
+            if let Some(pty) = &pty {
+                let state = state.clone();
+                let run_id = run_id.clone();
+                let task_key = task_key.to_string();
+                let reader = pty.reader();
+                stream_tasks.push(tokio::spawn(async move {
+                    stream_task_logs(state, run_id, task_key, reader, false).await;
+                }));
+            }
+
             if let Some(stdout) = child.stdout.take() {
                 let state = state.clone();
                 let run_id = run_id.clone();
@@ -1128,29 +2408,39 @@ async fn run_command(
                 }));
             }
 
-            let status = tokio::select! {
-                wait_result = child.wait() => wait_result,
-                _ = cancel_rx => {
-                    #[cfg(unix)]
-                    {
-                        if let Some(pid) = child.id() {
-                            // Negative PID targets the entire process group.
-                            unsafe {
-                                libc::kill(-(pid as i32), libc::SIGKILL);
-                            }
-                        } else {
-                            let _ = child.kill().await;
-                        }
-                    }
-                    #[cfg(not(unix))]
-                    {
-                        if let Some(pid) = child.id() {
-                            let _ = terminate_process_tree(pid).await;
-                        } else {
-                            let _ = child.kill().await;
-                        }
+            let timeout = task.timeout_ms.map(Duration::from_millis);
+            let mut timed_out_after = None;
+            let stopped_as = tokio::select! {
+                wait_result = child.wait() => Ok(wait_result),
+                _ = cancel_rx => Err(TaskRunStatus::Cancelled),
+                // Only resolves with `Ok` when a probe gave up, not when the
+                // task has no probe and the sender is simply dropped.
+                Ok(()) = not_ready_rx => Err(TaskRunStatus::Failed),
+                _ = tokio::time::sleep(timeout.unwrap_or_default()), if timeout.is_some() => {
+                    timed_out_after = timeout;
+                    Err(TaskRunStatus::Failed)
+                }
+            };
+
+            if let Some(readiness_probe) = readiness_probe {
+                readiness_probe.abort();
+            }
+
+            let status = match stopped_as {
+                Ok(wait_result) => wait_result,
+                Err(final_status) => {
+                    if let Some(timeout) = timed_out_after {
+                        append_task_log_line(
+                            &state,
+                            run_id.clone(),
+                            task_key.to_string(),
+                            format!("Timed out after {}", format_log_duration(timeout)),
+                            true,
+                        )
+                        .await;
                     }
-                    let _ = child.wait().await;
+                    stop_process_group(&state, &run_id, task_key, task, &mut child).await;
+                    let wait_result = child.wait().await;
                     remove_running_process_if_match(
                         running_processes.clone(),
                         run_id.as_str(),
@@ -1160,7 +2450,10 @@ async fn run_command(
                     for stream_task in stream_tasks {
                         let _ = stream_task.await;
                     }
-                    return TaskRunStatus::Cancelled;
+                    return RunOutcome {
+                        timed_out_after,
+                        ..RunOutcome::exited(final_status, wait_result)
+                    };
                 }
             };
 
@@ -1175,16 +2468,225 @@ async fn run_command(
                 let _ = stream_task.await;
             }
 
-            match status {
+            let final_status = match &status {
                 Ok(status) if status.success() => TaskRunStatus::Success,
                 Ok(_) => TaskRunStatus::Failed,
                 Err(_) => TaskRunStatus::Failed,
-            }
+            };
+            RunOutcome::exited(final_status, status)
         }
-        Err(_) => TaskRunStatus::Failed,
+        Err(_) => TaskRunStatus::Failed.into(),
     }
 }
 
+/// What `run_command` ends with: the run's final status and, when a process
+/// was started, how it exited.
+struct RunOutcome {
+    status: TaskRunStatus,
+    exit_code: Option<i32>,
+    signal: Option<String>,
+    /// The task's `timeoutMs`, when the process was stopped for running past it.
+    timed_out_after: Option<Duration>,
+}
+
+impl RunOutcome {
+    fn exited(
+        status: TaskRunStatus,
+        wait_result: std::io::Result<std::process::ExitStatus>,
+    ) -> Self {
+        let (exit_code, signal) = match wait_result {
+            Ok(exit_status) => describe_exit_status(exit_status),
+            Err(_) => (None, None),
+        };
+        Self {
+            status,
+            exit_code,
+            signal,
+            timed_out_after: None,
+        }
+    }
+}
+
+impl From<TaskRunStatus> for RunOutcome {
+    fn from(status: TaskRunStatus) -> Self {
+        Self {
+            status,
+            exit_code: None,
+            signal: None,
+            timed_out_after: None,
+        }
+    }
+}
+
+/// A process killed by a signal has no exit code of its own, so it is given
+/// the `128 + n` a shell would report. A shell reporting that for a command it
+/// ran is taken to mean the same, which is how `exit 137` reads as `SIGKILL`.
+fn describe_exit_status(exit_status: std::process::ExitStatus) -> (Option<i32>, Option<String>) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        let exit_code = exit_status
+            .code()
+            .or_else(|| exit_status.signal().map(|signal| 128 + signal));
+        let signal = exit_status
+            .signal()
+            .or_else(|| {
+                exit_status
+                    .code()
+                    .filter(|code| *code > 128)
+                    .map(|code| code - 128)
+            })
+            .and_then(Signal::from_number)
+            .map(|signal| signal.name().to_string());
+        (exit_code, signal)
+    }
+    #[cfg(not(unix))]
+    {
+        (exit_status.code(), None)
+    }
+}
+
+/// Asks the task's process group to stop with its `stopSignal` so it can clean
+/// up, and kills it if anything in it is still running after `stopTimeoutMs`.
+async fn stop_process_group(
+    state: &AppState,
+    run_id: &str,
+    task_key: &str,
+    task: &Task,
+    child: &mut tokio::process::Child,
+) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // The shell exiting is not enough: whatever it started can outlive it
+        // in the same process group.
+        let exited = async {
+            let _ = child.wait().await;
+            process_group::wait_for_exit(pid, STOP_POLL_INTERVAL).await;
+        };
+        signal_then_kill(state, run_id, task_key, task, pid, exited).await;
+        return;
+    }
+    #[cfg(not(unix))]
+    let _ = (state, run_id, task_key, task);
+
+    kill_process_group(child).await;
+}
+
+/// Sends the task's `stopSignal` to the group and, unless `exited` resolves
+/// within `stopTimeoutMs`, `SIGKILL`, saying so in the run's log.
+#[cfg(unix)]
+async fn signal_then_kill(
+    state: &AppState,
+    run_id: &str,
+    task_key: &str,
+    task: &Task,
+    pgid: u32,
+    exited: impl std::future::Future<Output = ()>,
+) {
+    const DEFAULT_STOP_TIMEOUT_MS: u64 = 5_000;
+
+    let signal = task
+        .stop_signal
+        .as_deref()
+        .and_then(Signal::parse)
+        .unwrap_or(Signal::TERM);
+    let timeout_ms = task.stop_timeout_ms.unwrap_or(DEFAULT_STOP_TIMEOUT_MS);
+    if signal != Signal::KILL {
+        process_group::signal(pgid, signal);
+        let stopped =
+            tokio::time::timeout(std::time::Duration::from_millis(timeout_ms), exited).await;
+        if stopped.is_ok() {
+            return;
+        }
+        append_task_log_line(
+            state,
+            run_id.to_string(),
+            task_key.to_string(),
+            format!(
+                "Sent {}, escalating to SIGKILL after {}ms",
+                signal, timeout_ms
+            ),
+            true,
+        )
+        .await;
+    }
+    process_group::signal(pgid, Signal::KILL);
+}
+
+async fn kill_process_group(child: &mut tokio::process::Child) {
+    #[cfg(unix)]
+    {
+        if let Some(pid) = child.id() {
+            // Negative PID targets the entire process group.
+            unsafe {
+                libc::kill(-(pid as i32), libc::SIGKILL);
+            }
+        } else {
+            let _ = child.kill().await;
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        if let Some(pid) = child.id() {
+            let _ = terminate_process_tree(pid).await;
+        } else {
+            let _ = child.kill().await;
+        }
+    }
+}
+
+/// Marks the run ready once its probe passes. If the probe gives up instead,
+/// the reason is logged and `not_ready_tx` tells `run_command` to stop the
+/// process, since nothing waiting on it would ever start.
+async fn run_readiness_probe(
+    state: AppState,
+    run_id: String,
+    task_key: String,
+    probe: ReadyProbe,
+    logs: broadcast::Receiver<TaskRunLogLine>,
+    not_ready_tx: oneshot::Sender<()>,
+) {
+    match wait_until_ready(&probe, &run_id, logs).await {
+        Ok(()) => {
+            if let Err(err) = mark_task_run_ready(&state, &run_id).await {
+                eprintln!("Failed to mark task run {} ready: {}", run_id, err);
+            }
+        }
+        Err(message) => {
+            append_task_log_line(&state, run_id, task_key, message, true).await;
+            let _ = not_ready_tx.send(());
+        }
+    }
+}
+
+async fn mark_task_run_ready(state: &AppState, run_id: &str) -> Result<(), DbErr> {
+    let Some(task_run) = task_run::Entity::find_by_id(run_id.to_string())
+        .one(&state.db)
+        .await?
+    else {
+        return Ok(());
+    };
+    if task_run.status != TaskRunStatus::Running || task_run.ready_at.is_some() {
+        return Ok(());
+    }
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut active = task_run.into_active_model();
+    active.ready_at = Set(Some(now));
+    active.updated_at = Set(now);
+    let updated = active.update(&state.db).await?;
+    let _ = state.task_events.send(TaskRunStatusChangedEvent {
+        run_id: updated.id,
+        task: updated.task,
+        cwd: updated.cwd,
+        status: updated.status,
+        ready: true,
+        blocks_waiting_runs: true,
+    });
+    Ok(())
+}
+
 async fn remove_running_process_if_match(
     running_processes: std::sync::Arc<Mutex<HashMap<String, RunningProcessEntry>>>,
     run_id: &str,
@@ -1221,7 +2723,7 @@ async fn terminate_process_tree(pid: u32) -> std::io::Result<()> {
     }
 }
 
-#[cfg(not(target_os = "windows"))]
+#[cfg(not(any(unix, target_os = "windows")))]
 async fn terminate_process_tree(_pid: u32) -> std::io::Result<()> {
     Ok(())
 }
@@ -1302,7 +2804,7 @@ fn build_task_command_path() -> Option<std::ffi::OsString> {
     std::env::join_paths(entries).ok()
 }
 
-fn resolve_command_cwd(cwd: &str) -> PathBuf {
+fn resolve_command_cwd(cwd: &FsPath) -> PathBuf {
     std::fs::canonicalize(cwd).unwrap_or_else(|_| PathBuf::from(cwd))
 }
 
@@ -1312,10 +2814,10 @@ fn resolve_command_shell() -> std::ffi::OsString {
 
     #[cfg(not(target_os = "windows"))]
     {
-        if let Some(shell) = std::env::var_os("SHELL") {
-            if !shell.is_empty() {
-                return shell;
-            }
+        if let Some(shell) = std::env::var_os("SHELL")
+            && !shell.is_empty()
+        {
+            return shell;
         }
 
         #[cfg(target_os = "macos")]
@@ -1326,7 +2828,7 @@ fn resolve_command_shell() -> std::ffi::OsString {
     }
 }
 
-fn infer_home_from_path(path: &PathBuf) -> Option<PathBuf> {
+fn infer_home_from_path(path: &FsPath) -> Option<PathBuf> {
     let mut components = path.components();
     let root = components.next()?;
     let users = components.next()?;
@@ -1417,7 +2919,10 @@ fn task_has_no_command(task: &Task) -> bool {
     }
 }
 
-fn collect_descendant_run_ids(all_runs: &[task_run::Model], root_run_id: &str) -> Vec<String> {
+pub(crate) fn collect_descendant_run_ids(
+    all_runs: &[task_run::Model],
+    root_run_id: &str,
+) -> Vec<String> {
     let mut by_parent: HashMap<&str, Vec<&task_run::Model>> = HashMap::new();
     for run in all_runs {
         if let Some(parent_run_id) = run.parent_run_id.as_deref() {
@@ -1475,17 +2980,54 @@ async fn load_task_run_tree(
         runs_by_id.insert(run.id.clone(), run);
     }
 
+    let root_run_id = invocation_root_id(&state.db, run_id).await?;
+    let mut include_tasks = load_include_tasks(&state.db, &root_run_id)
+        .await?
+        .into_iter()
+        .collect::<Vec<_>>();
+    include_tasks.sort();
+
     Ok(build_task_run_tree(
         run_id,
         &runs_by_id,
         &children_by_parent,
+        &include_tasks,
     ))
 }
 
+/// The sorted include set of every root run among `runs_by_id` that has one.
+async fn load_include_tasks_by_root(
+    db: &DatabaseConnection,
+    runs_by_id: &HashMap<String, task_run::Model>,
+) -> Result<HashMap<String, Vec<String>>, DbErr> {
+    let root_run_ids = runs_by_id
+        .values()
+        .filter(|run| run.parent_run_id.is_none() && run.started_by_run_id.is_none())
+        .map(|run| run.id.clone())
+        .collect::<Vec<_>>();
+    let includes = task_run_include_task::Entity::find()
+        .filter(task_run_include_task::Column::RootRunId.is_in(root_run_ids))
+        .order_by_asc(task_run_include_task::Column::Task)
+        .all(db)
+        .await?;
+
+    let mut include_tasks_by_root: HashMap<String, Vec<String>> = HashMap::new();
+    for include in includes {
+        include_tasks_by_root
+            .entry(include.root_run_id)
+            .or_default()
+            .push(include.task);
+    }
+    Ok(include_tasks_by_root)
+}
+
+/// `include_tasks` is the include set of the invocation the run belongs to,
+/// which its whole subtree shares.
 fn build_task_run_tree(
     run_id: &str,
     runs_by_id: &HashMap<String, task_run::Model>,
     children_by_parent: &HashMap<String, Vec<String>>,
+    include_tasks: &[String],
 ) -> Option<TaskRunTreeNode> {
     let run = runs_by_id.get(run_id)?;
 
@@ -1495,7 +3037,7 @@ fn build_task_run_tree(
         .unwrap_or_default()
         .into_iter()
         .filter_map(|child_run_id| {
-            build_task_run_tree(&child_run_id, runs_by_id, children_by_parent)
+            build_task_run_tree(&child_run_id, runs_by_id, children_by_parent, include_tasks)
         })
         .collect::<Vec<_>>();
     children.sort_by_key(|child| child.updated_at);
@@ -1504,15 +3046,36 @@ fn build_task_run_tree(
         id: run.id.clone(),
         task: run.task.clone(),
         cwd: run.cwd.clone(),
+        working_dir: run.working_dir.clone(),
         parent_run_id: run.parent_run_id.clone(),
+        started_by_run_id: run.started_by_run_id.clone(),
         status: run.status,
+        ready: run.ready_at.is_some(),
         updated_at: run.updated_at,
         waiting_on: run.waiting_on.clone(),
+        blocked_by: run.blocked_by.clone(),
+        exit_code: run.exit_code,
+        signal: run.signal.clone(),
+        pid: run.pid.and_then(|pid| u32::try_from(pid).ok()),
+        started_at: run.started_at,
+        finished_at: run.finished_at,
+        include_tasks: include_tasks.to_vec(),
+        attempt: run.attempt as u32,
+        restarts: run.restarts as u32,
+        timed_out_after_ms: run
+            .timed_out_after_ms
+            .and_then(|timeout_ms| u64::try_from(timeout_ms).ok()),
         children,
     })
 }
 
-async fn cancel_task_runs(state: &AppState, run_ids: &[String]) -> Result<(), DbErr> {
+/// `blocks_waiting_runs` is false when the runs are only cancelled to be
+/// restarted.
+async fn cancel_task_runs(
+    state: &AppState,
+    run_ids: &[String],
+    blocks_waiting_runs: bool,
+) -> Result<(), DbErr> {
     for run_id in run_ids {
         let Some(task_run) = task_run::Entity::find_by_id(run_id.clone())
             .one(&state.db)
@@ -1523,7 +3086,7 @@ async fn cancel_task_runs(state: &AppState, run_ids: &[String]) -> Result<(), Db
 
         if matches!(
             task_run.status,
-            TaskRunStatus::Success | TaskRunStatus::Failed
+            TaskRunStatus::Success | TaskRunStatus::Failed | TaskRunStatus::Interrupted
         ) {
             continue;
         }
@@ -1541,7 +3104,162 @@ async fn cancel_task_runs(state: &AppState, run_ids: &[String]) -> Result<(), Db
         )
         .await;
 
-        update_task_run_status(state, run_id, TaskRunStatus::Cancelled, None).await?;
+        set_task_run_status(
+            state,
+            run_id,
+            TaskRunStatus::Cancelled,
+            None,
+            blocks_waiting_runs,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Once `timeout` has passed, fails whatever in `root_run`'s tree is still
+/// queued or running, stopping its process the way cancelling it would. Gives
+/// up as soon as the tree has finished, subtasks included, or once the root is
+/// restarted by hand, which starts the tree over without a timeout.
+fn spawn_task_tree_timeout(state: AppState, root_run: &task_run::Model, timeout: Duration) {
+    // Subscribed before the tree can settle, so that is not missed.
+    let mut task_events = state.task_events.subscribe();
+    let root_run_id = root_run.id.clone();
+    let cwd = root_run.cwd.clone();
+    let generation = manual_start_generation(root_run);
+    tokio::spawn(async move {
+        let deadline = tokio::time::sleep(timeout);
+        tokio::pin!(deadline);
+        loop {
+            tokio::select! {
+                _ = &mut deadline => break,
+                event = task_events.recv() => match event {
+                    Ok(event) if event.cwd != cwd => {}
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {
+                        match is_task_tree_going(&state, &root_run_id, generation).await {
+                            Ok(true) => {}
+                            Ok(false) => return,
+                            Err(err) => {
+                                eprintln!("Failed to check task run {}: {}", root_run_id, err);
+                                return;
+                            }
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+            }
+        }
+
+        let result = match is_task_tree_going(&state, &root_run_id, generation).await {
+            Ok(true) => time_out_task_tree(&state, &root_run_id, timeout).await,
+            Ok(false) => Ok(()),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            eprintln!("Failed to time out task run {}: {}", root_run_id, err);
+        }
+    });
+}
+
+/// Goes up when `run` is restarted by hand, but not when its `restart` policy
+/// starts it again, since a restart by hand also resets `restarts`.
+fn manual_start_generation(run: &task_run::Model) -> i32 {
+    run.attempt - run.restarts
+}
+
+/// Whether `root_run_id`'s tree may still do more work, with the root not
+/// restarted by hand since it was on `generation`. That is while anything in
+/// it is queued or running, or a run in it succeeded and the subtasks it starts
+/// are not all there yet.
+async fn is_task_tree_going(
+    state: &AppState,
+    root_run_id: &str,
+    generation: i32,
+) -> Result<bool, DbErr> {
+    let Some(root_run) = task_run::Entity::find_by_id(root_run_id.to_string())
+        .one(&state.db)
+        .await?
+    else {
+        return Ok(false);
+    };
+    if manual_start_generation(&root_run) != generation {
+        return Ok(false);
+    }
+    let all_runs = task_run::Entity::find()
+        .filter(task_run::Column::Cwd.eq(root_run.cwd.clone()))
+        .all(&state.db)
+        .await?;
+    let run_ids = collect_descendant_run_ids(&all_runs, root_run_id);
+    let tree = all_runs
+        .iter()
+        .filter(|run| run_ids.contains(&run.id))
+        .collect::<Vec<_>>();
+    if tree.iter().any(|run| run.status.is_active()) {
+        return Ok(true);
+    }
+
+    // Subtasks are only created once their parent's success has gone through
+    // the completion listener, so until then the tree looks finished.
+    let Ok(config) = Config::load(&root_run.cwd).await else {
+        return Ok(false);
+    };
+    Ok(tree.iter().any(|run| {
+        let subtasks = config
+            .get_task(run.task.clone())
+            .and_then(|task| task.tasks)
+            .unwrap_or_default();
+        run.status == TaskRunStatus::Success
+            && subtasks.keys().any(|subtask_key| {
+                let full_subtask_key = format!("{}:{}", run.task, subtask_key);
+                !tree.iter().any(|child| {
+                    child.parent_run_id.as_deref() == Some(run.id.as_str())
+                        && child.task == full_subtask_key
+                })
+            })
+    }))
+}
+
+async fn time_out_task_tree(
+    state: &AppState,
+    root_run_id: &str,
+    timeout: Duration,
+) -> Result<(), DbErr> {
+    let Some(root_run) = task_run::Entity::find_by_id(root_run_id.to_string())
+        .one(&state.db)
+        .await?
+    else {
+        return Ok(());
+    };
+    let all_runs = task_run::Entity::find()
+        .filter(task_run::Column::Cwd.eq(root_run.cwd))
+        .all(&state.db)
+        .await?;
+    let run_ids = collect_descendant_run_ids(&all_runs, root_run_id);
+
+    for run in all_runs.iter().filter(|run| run_ids.contains(&run.id)) {
+        if !matches!(run.status, TaskRunStatus::Queued | TaskRunStatus::Running) {
+            continue;
+        }
+
+        append_task_log_line(
+            state,
+            run.id.clone(),
+            run.task.clone(),
+            format!("Timed out after {}", format_log_duration(timeout)),
+            true,
+        )
+        .await;
+
+        // Failed before its process is stopped, so the execution finds the
+        // run already finished and does not restart it.
+        let mut active = run.clone().into_active_model();
+        active.timed_out_after_ms = Set(Some(timeout.as_millis() as i64));
+        active.update(&state.db).await?;
+        update_task_run_status(state, &run.id, TaskRunStatus::Failed, None).await?;
+
+        if let Some(process_entry) = state.running_processes.lock().await.remove(&run.id) {
+            let _ = process_entry.cancel_tx.send(());
+        }
     }
 
     Ok(())
@@ -1564,6 +3282,9 @@ async fn prepare_task_runs_for_restart(
         .map(|run| (run.id.as_str(), run))
         .collect::<HashMap<_, _>>();
 
+    let invocation_root_run_id = invocation_root_id(&state.db, root_run_id).await?;
+    let include_tasks = load_include_tasks(&state.db, &invocation_root_run_id).await?;
+
     let mut ordered_run_ids = run_ids_to_restart.to_vec();
     ordered_run_ids.sort_by_key(|run_id| {
         let mut depth = 0usize;
@@ -1591,15 +3312,19 @@ async fn prepare_task_runs_for_restart(
             .as_ref()
             .and_then(|task| task.optional)
             .unwrap_or(false);
+        // Runs from before include sets were stored have none, so an optional
+        // subtask that ran before still counts as included.
         let was_previously_active = matches!(
             run.status,
             TaskRunStatus::Queued
                 | TaskRunStatus::Running
                 | TaskRunStatus::Success
                 | TaskRunStatus::Failed
+                | TaskRunStatus::Interrupted
         );
-        if run.id != root_run_id && is_optional && !was_previously_active {
-            update_task_run_status(state, &run.id, TaskRunStatus::Cancelled, None).await?;
+        let is_included = include_tasks.contains(&run.task) || was_previously_active;
+        if run.id != root_run_id && is_optional && !is_included {
+            skip_task_run(state, &run.id).await?;
             continue;
         }
 
@@ -1614,13 +3339,15 @@ async fn prepare_task_runs_for_restart(
                 let Some(task) = task_for_run else {
                     continue;
                 };
-                next_unmet_dependency(&state.db, &run.cwd, &task).await?
+                let root_run_id = invocation_root_id(&state.db, &run.id).await?;
+                next_unmet_dependency(state, &run.cwd, &root_run_id, &task).await?
             }
         } else {
             let Some(task) = task_for_run else {
                 continue;
             };
-            next_unmet_dependency(&state.db, &run.cwd, &task).await?
+            let root_run_id = invocation_root_id(&state.db, &run.id).await?;
+            next_unmet_dependency(state, &run.cwd, &root_run_id, &task).await?
         };
 
         update_task_run_status(state, &run.id, TaskRunStatus::Queued, waiting_on).await?;
@@ -1664,33 +3391,235 @@ fn validate_included_optional_tasks(
     Ok(included)
 }
 
-async fn clear_task_run_logs_for_restart(
-    state: &AppState,
-    run_ids: &[String],
-) -> Result<(), DbErr> {
+/// Moves every run in `run_ids` on to its next attempt, so the lines it logs
+/// from here on are kept apart from the ones before the restart.
+async fn start_new_task_run_attempts(state: &AppState, run_ids: &[String]) -> Result<(), DbErr> {
+    if run_ids.is_empty() {
+        return Ok(());
+    }
+
+    task_run::Entity::update_many()
+        .col_expr(
+            task_run::Column::Attempt,
+            Expr::col(task_run::Column::Attempt).add(1),
+        )
+        .filter(task_run::Column::Id.is_in(run_ids.to_vec()))
+        .exec(&state.db)
+        .await?;
+    state.log_writer.forget_attempts(run_ids);
+    Ok(())
+}
+
+async fn reset_task_run_restarts(state: &AppState, run_ids: &[String]) -> Result<(), DbErr> {
     if run_ids.is_empty() {
         return Ok(());
     }
 
-    task_run_log::Entity::delete_many()
-        .filter(task_run_log::Column::RunId.is_in(run_ids.to_vec()))
+    task_run::Entity::update_many()
+        .col_expr(task_run::Column::Restarts, Expr::value(0))
+        .filter(task_run::Column::Id.is_in(run_ids.to_vec()))
         .exec(&state.db)
         .await?;
     Ok(())
 }
 
+#[derive(Debug, Default)]
+pub struct OrphanedTaskRuns {
+    pub interrupted: usize,
+    pub adopted: usize,
+}
+
+/// Runs left queued or running by a previous server never finish on their
+/// own: nothing is watching their processes or left to start the queued ones.
+/// Marks them `Interrupted`, with a log line saying why, before anything can
+/// attach to them. A process group the previous server started that is still
+/// alive is stopped first the way cancelling its run would, or with
+/// `adopt_processes` kept going until it exits, its output no longer captured.
+pub async fn reconcile_orphaned_task_runs(
+    state: &AppState,
+    adopt_processes: bool,
+) -> Result<OrphanedTaskRuns, DbErr> {
+    let orphaned_runs = task_run::Entity::find()
+        .filter(task_run::Column::Status.is_in([TaskRunStatus::Queued, TaskRunStatus::Running]))
+        .all(&state.db)
+        .await?;
+
+    let mut summary = OrphanedTaskRuns::default();
+    let mut stopping = Vec::new();
+    for run in orphaned_runs {
+        #[cfg(unix)]
+        if let Some(pgid) = orphaned_process_group(&run) {
+            let task = Config::load(&run.cwd)
+                .await
+                .ok()
+                .and_then(|config| config.get_task(run.task.clone()))
+                .unwrap_or_default();
+            if adopt_processes {
+                adopt_process_group(state, run, task, pgid).await;
+                summary.adopted += 1;
+            } else {
+                // Stopped together, so startup waits for the slowest rather
+                // than for all of them in turn.
+                stopping.push(async move {
+                    append_task_log_line(
+                        state,
+                        run.id.clone(),
+                        run.task.clone(),
+                        format!(
+                            "Stopping process group {} left running by the previous server",
+                            pgid
+                        ),
+                        true,
+                    )
+                    .await;
+                    let exited = process_group::wait_for_exit(pgid, STOP_POLL_INTERVAL);
+                    signal_then_kill(state, &run.id, &run.task, &task, pgid, exited).await;
+                    interrupt_task_run(state, &run).await
+                });
+            }
+            continue;
+        }
+        #[cfg(not(unix))]
+        let _ = adopt_processes;
+
+        interrupt_task_run(state, &run).await?;
+        summary.interrupted += 1;
+    }
+
+    for stopped in futures_util::future::join_all(stopping).await {
+        stopped?;
+        summary.interrupted += 1;
+    }
+    Ok(summary)
+}
+
+/// The process group of a run the previous server had running, if it is still
+/// alive. Its ID is the run's recorded `pid`.
+#[cfg(unix)]
+fn orphaned_process_group(run: &task_run::Model) -> Option<u32> {
+    if run.status != TaskRunStatus::Running {
+        return None;
+    }
+    let pgid = u32::try_from(run.pid?).ok()?;
+    process_group::is_alive_since(pgid, run.started_at?).then_some(pgid)
+}
+
+async fn interrupt_task_run(state: &AppState, run: &task_run::Model) -> Result<(), DbErr> {
+    let message = match run.status {
+        TaskRunStatus::Running => "Interrupted: the server stopped while this run was running",
+        _ => "Interrupted: the server stopped before this run could start",
+    };
+    append_task_log_line(
+        state,
+        run.id.clone(),
+        run.task.clone(),
+        message.to_string(),
+        true,
+    )
+    .await;
+    update_task_run_status(state, &run.id, TaskRunStatus::Interrupted, None).await
+}
+
+/// Watches a process group the previous server started as if this one had,
+/// so the run can still be cancelled and finishes when the group exits. It is
+/// no child of this server, so how it exited cannot be known, and the run ends
+/// up `Interrupted` rather than succeeding or failing.
+#[cfg(unix)]
+async fn adopt_process_group(state: &AppState, run: task_run::Model, task: Task, pgid: u32) {
+    append_task_log_line(
+        state,
+        run.id.clone(),
+        run.task.clone(),
+        format!(
+            "Adopted process group {} after the server restarted; its output is no longer captured",
+            pgid
+        ),
+        true,
+    )
+    .await;
+
+    let execution_id = nanoid!(10, &TASK_RUN_ID_ALPHABET);
+    let (cancel_tx, cancel_rx) = oneshot::channel::<()>();
+    let (stopped_tx, stopped_rx) = oneshot::channel::<()>();
+    state.running_processes.lock().await.insert(
+        run.id.clone(),
+        RunningProcessEntry {
+            execution_id: execution_id.clone(),
+            cancel_tx,
+            stopped_rx,
+            pgid: Some(pgid),
+            pty: None,
+            input: None,
+        },
+    );
+
+    let state = state.clone();
+    tokio::spawn(async move {
+        let _stopped_tx = stopped_tx;
+        let final_status = tokio::select! {
+            // Checked less often than while stopping it, as it may run for hours.
+            _ = process_group::wait_for_exit(pgid, std::time::Duration::from_secs(1)) => {
+                append_task_log_line(
+                    &state,
+                    run.id.clone(),
+                    run.task.clone(),
+                    "Exited after the server restarted; its exit status is unknown".to_string(),
+                    true,
+                )
+                .await;
+                TaskRunStatus::Interrupted
+            }
+            _ = cancel_rx => {
+                let exited = process_group::wait_for_exit(pgid, STOP_POLL_INTERVAL);
+                signal_then_kill(&state, &run.id, &run.task, &task, pgid, exited).await;
+                TaskRunStatus::Cancelled
+            }
+        };
+        remove_running_process_if_match(state.running_processes.clone(), &run.id, &execution_id)
+            .await;
+
+        let result = async {
+            let Some(current_run) = task_run::Entity::find_by_id(run.id.clone())
+                .one(&state.db)
+                .await?
+            else {
+                return Ok(());
+            };
+            // Restarted or cancelled in the meantime.
+            if current_run.status != TaskRunStatus::Running
+                || current_run.started_at != run.started_at
+            {
+                return Ok(());
+            }
+            let mut active = current_run.into_active_model();
+            active.finished_at = Set(Some(chrono::Utc::now().timestamp_millis()));
+            active.update(&state.db).await?;
+            update_task_run_status(&state, &run.id, final_status, None).await
+        };
+        if let Err(err) = result.await {
+            eprintln!("Failed to finish adopted task run {}: {}", run.id, err);
+        }
+    });
+}
+
+/// Stops every running task the same way cancelling it would, and waits for
+/// them all to exit. Each escalates to `SIGKILL` on its own, so this takes no
+/// longer than the longest `stopTimeoutMs`.
 pub async fn cancel_all_running_processes(state: &AppState) {
-    let cancel_senders = {
+    let process_entries = {
         let mut running = state.running_processes.lock().await;
         running
             .drain()
-            .map(|(_, process_entry)| process_entry.cancel_tx)
+            .map(|(_, process_entry)| process_entry)
             .collect::<Vec<_>>()
     };
 
-    for sender in cancel_senders {
-        let _ = sender.send(());
+    let mut stopped = Vec::new();
+    for process_entry in process_entries {
+        let _ = process_entry.cancel_tx.send(());
+        stopped.push(process_entry.stopped_rx);
     }
+    futures_util::future::join_all(stopped).await;
 }
 
 async fn stream_task_logs<R>(
@@ -1747,31 +3676,8 @@ async fn append_task_log_line(
     line: String,
     is_stderr: bool,
 ) {
-    let timestamp = chrono::Utc::now().timestamp_millis();
-    let log = task_run_log::ActiveModel {
-        id: NotSet,
-        run_id: Set(run_id),
-        task: Set(task),
-        line: Set(line),
-        is_stderr: Set(is_stderr),
-        timestamp: Set(timestamp),
-    };
-
-    match log.insert(&state.db).await {
-        Ok(inserted) => {
-            let log_line = TaskRunLogLine {
-                run_id: inserted.run_id,
-                task: inserted.task,
-                line: inserted.line,
-                is_stderr: inserted.is_stderr,
-                timestamp: inserted.timestamp,
-                sequence: inserted.id as u64,
-            };
-            let _ = state.task_log_events.send(log_line);
-        }
-        Err(err) => {
-            eprintln!("Failed to persist task log line: {}", err);
-        }
+    if let Err(err) = state.log_writer.append(run_id, task, line, is_stderr).await {
+        eprintln!("Failed to persist task log line: {}", err);
     }
 }
 
@@ -1780,6 +3686,22 @@ async fn update_task_run_status(
     run_id: &str,
     status: TaskRunStatus,
     waiting_on: Option<String>,
+) -> Result<(), DbErr> {
+    set_task_run_status(state, run_id, status, waiting_on, true).await
+}
+
+/// Cancels a run that is about to be restarted, or an optional subtask skipped
+/// because it was not included, without blocking runs waiting on its task.
+async fn skip_task_run(state: &AppState, run_id: &str) -> Result<(), DbErr> {
+    set_task_run_status(state, run_id, TaskRunStatus::Cancelled, None, false).await
+}
+
+async fn set_task_run_status(
+    state: &AppState,
+    run_id: &str,
+    status: TaskRunStatus,
+    waiting_on: Option<String>,
+    blocks_waiting_runs: bool,
 ) -> Result<(), DbErr> {
     let Some(task_run) = task_run::Entity::find_by_id(run_id.to_string())
         .one(&state.db)
@@ -1790,6 +3712,7 @@ async fn update_task_run_status(
     let mut active = task_run.into_active_model();
     active.status = Set(status);
     active.waiting_on = Set(waiting_on);
+    active.blocked_by = Set(None);
     active.updated_at = Set(chrono::Utc::now().timestamp_millis());
     let updated = active.update(&state.db).await?;
     let _ = state.task_events.send(TaskRunStatusChangedEvent {
@@ -1797,30 +3720,12 @@ async fn update_task_run_status(
         task: updated.task,
         cwd: updated.cwd,
         status: updated.status,
+        ready: updated.ready_at.is_some(),
+        blocks_waiting_runs,
     });
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::normalize_terminal_log_line;
-
-    #[test]
-    fn normalize_terminal_log_line_keeps_ansi_sgr_sequences() {
-        let input = "\u{1b}[31merror\u{1b}[0m";
-        assert_eq!(normalize_terminal_log_line(input), input);
-    }
-
-    #[test]
-    fn normalize_terminal_log_line_uses_most_recent_carriage_segment() {
-        let input = "step 1\rstep 2\r\u{1b}[32mdone\u{1b}[0m";
-        assert_eq!(
-            normalize_terminal_log_line(input),
-            "\u{1b}[32mdone\u{1b}[0m"
-        );
-    }
-}
-
 async fn trigger_subtasks(
     state: &AppState,
     event: &TaskRunStatusChangedEvent,
@@ -1840,13 +3745,12 @@ async fn trigger_subtasks(
     let Some(subtasks) = parent_task.tasks else {
         return Ok(());
     };
-    let include_tasks = state
-        .run_include_tasks
-        .lock()
-        .await
-        .get(&event.run_id)
-        .cloned()
-        .unwrap_or_default();
+    let root_run_id = invocation_root_id(&state.db, &event.run_id).await?;
+    let include_tasks = load_include_tasks(&state.db, &root_run_id).await?;
+    let run_env = task_run::Entity::find_by_id(event.run_id.clone())
+        .one(&state.db)
+        .await?
+        .and_then(|parent_run| parent_run.env);
 
     for (subtask_key, _) in subtasks {
         let full_subtask_key = format!("{}:{}", event.task, subtask_key);
@@ -1869,13 +3773,17 @@ async fn trigger_subtasks(
             continue;
         }
 
+        let env_layers = config.get_task_env_layers(&full_subtask_key);
         create_task_run(
             state,
             full_subtask_key,
             subtask,
+            env_layers,
             event.cwd.clone(),
             Some(event.run_id.clone()),
+            None,
             &include_tasks,
+            run_env.clone(),
             should_start_cancelled,
             true,
         )
@@ -1885,9 +3793,12 @@ async fn trigger_subtasks(
     Ok(())
 }
 
+/// `root_run_id` is the invocation the waiting run belongs to, which
+/// `sameRootRun` dependencies must have run in.
 async fn next_unmet_dependency(
-    db: &DatabaseConnection,
+    state: &AppState,
     cwd: &str,
+    root_run_id: &str,
     task: &Task,
 ) -> Result<Option<String>, DbErr> {
     let Some(depends_on) = &task.depends_on else {
@@ -1895,8 +3806,8 @@ async fn next_unmet_dependency(
     };
 
     for dependency in depends_on {
-        if !is_dependency_satisfied(db, cwd, dependency).await? {
-            return Ok(Some(dependency.clone()));
+        if !is_dependency_satisfied(state, cwd, root_run_id, dependency).await? {
+            return Ok(Some(dependency.task().to_string()));
         }
     }
 
@@ -1904,21 +3815,55 @@ async fn next_unmet_dependency(
 }
 
 async fn is_dependency_satisfied(
-    db: &DatabaseConnection,
+    state: &AppState,
     cwd: &str,
-    dependency_task: &str,
+    root_run_id: &str,
+    dependency: &Dependency,
 ) -> Result<bool, DbErr> {
-    let latest = task_run::Entity::find()
+    let policy = dependency.satisfied_by();
+    let mut runs = task_run::Entity::find()
         .filter(task_run::Column::Cwd.eq(cwd.to_string()))
-        .filter(task_run::Column::Task.eq(dependency_task.to_string()))
-        .order_by_desc(task_run::Column::UpdatedAt)
-        .one(db)
-        .await?;
+        .filter(task_run::Column::Task.eq(dependency.task().to_string()))
+        .order_by_desc(task_run::Column::UpdatedAt);
+    if policy == SatisfactionPolicy::SameRootRun {
+        let run_ids = invocation_run_ids(&state.db, root_run_id).await?;
+        runs = runs.filter(task_run::Column::Id.is_in(run_ids));
+    }
+    let latest = runs.one(&state.db).await?;
 
-    Ok(matches!(
-        latest.map(|run| run.status),
-        Some(TaskRunStatus::Success)
-    ))
+    // A task that is still running counts once its `ready` probe has passed.
+    let satisfied_at = latest.and_then(|run| match run.status {
+        TaskRunStatus::Success => Some(run.updated_at),
+        TaskRunStatus::Running => run.ready_at,
+        _ => None,
+    });
+    Ok(satisfied_at.is_some_and(|satisfied_at| {
+        policy_allows(
+            policy,
+            satisfied_at,
+            state.started_at,
+            chrono::Utc::now().timestamp_millis(),
+        )
+    }))
+}
+
+/// Whether a run that satisfied a dependency at `satisfied_at` is recent
+/// enough for `policy`. All times are in milliseconds since the epoch.
+fn policy_allows(
+    policy: SatisfactionPolicy,
+    satisfied_at: i64,
+    server_started_at: i64,
+    now: i64,
+) -> bool {
+    match policy {
+        // Only runs from the same invocation were considered to begin with.
+        SatisfactionPolicy::Always | SatisfactionPolicy::SameRootRun => true,
+        SatisfactionPolicy::SinceServerStart => satisfied_at >= server_started_at,
+        SatisfactionPolicy::WithinSeconds(seconds) => {
+            let window_ms = i64::try_from(seconds.saturating_mul(1000)).unwrap_or(i64::MAX);
+            satisfied_at >= now.saturating_sub(window_ms)
+        }
+    }
 }
 
 async fn trigger_waiting_task_runs(
@@ -1949,7 +3894,9 @@ async fn trigger_waiting_task_runs(
             continue;
         };
 
-        let next_waiting_on = next_unmet_dependency(&state.db, &waiting_run.cwd, &task).await?;
+        let root_run_id = invocation_root_id(&state.db, &waiting_run.id).await?;
+        let next_waiting_on =
+            next_unmet_dependency(state, &waiting_run.cwd, &root_run_id, &task).await?;
         if let Some(next_waiting_on) = next_waiting_on {
             update_task_run_status(
                 state,
@@ -1967,9 +3914,1140 @@ async fn trigger_waiting_task_runs(
             waiting_run.id.clone(),
             waiting_run.task.clone(),
             waiting_run.cwd.clone(),
-            task.command.clone(),
+            task,
+            config.get_task_env_layers(&waiting_run.task),
         );
     }
 
     Ok(())
 }
+
+/// Runs waiting on a task whose run just failed or was cancelled, blocked or
+/// interrupted would otherwise stay queued forever, so they are blocked in
+/// turn. Blocking one emits its own event, which carries the failure on down
+/// the chain.
+async fn block_waiting_task_runs(
+    state: &AppState,
+    event: &TaskRunStatusChangedEvent,
+) -> Result<(), DbErr> {
+    if !event.blocks_waiting_runs {
+        return Ok(());
+    }
+
+    // Another run of the same task can still satisfy them.
+    if has_active_run(&state.db, &event.cwd, &event.task).await? {
+        return Ok(());
+    }
+
+    let waiting_runs = task_run::Entity::find()
+        .filter(task_run::Column::Cwd.eq(event.cwd.clone()))
+        .filter(task_run::Column::Status.eq(TaskRunStatus::Queued))
+        .filter(task_run::Column::WaitingOn.eq(Some(event.task.clone())))
+        .all(&state.db)
+        .await?;
+
+    let outcome = match event.status {
+        TaskRunStatus::Failed => "failed",
+        TaskRunStatus::Cancelled => "was cancelled",
+        TaskRunStatus::Interrupted => "was interrupted",
+        _ => "was blocked",
+    };
+    for waiting_run in waiting_runs {
+        append_task_log_line(
+            state,
+            waiting_run.id.clone(),
+            waiting_run.task.clone(),
+            format!("Not started: dependency {} {}", event.task, outcome),
+            true,
+        )
+        .await;
+
+        let mut active = waiting_run.into_active_model();
+        active.status = Set(TaskRunStatus::Blocked);
+        active.waiting_on = Set(None);
+        active.blocked_by = Set(Some(event.task.clone()));
+        active.updated_at = Set(chrono::Utc::now().timestamp_millis());
+        let updated = active.update(&state.db).await?;
+        let _ = state.task_events.send(TaskRunStatusChangedEvent {
+            run_id: updated.id,
+            task: updated.task,
+            cwd: updated.cwd,
+            status: updated.status,
+            ready: false,
+            blocks_waiting_runs: true,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::input::{SendTaskRunInputRequest, send_task_run_input};
+    use crate::api::test_support::{
+        TestProject, insert_run, new_run, serve, test_state, wait_for_runs,
+    };
+    use crate::config::DetailedDependency;
+
+    async fn start(state: &AppState, cwd: &str, task: &str) -> String {
+        start_including(state, cwd, task, None).await
+    }
+
+    async fn start_including(
+        state: &AppState,
+        cwd: &str,
+        task: &str,
+        include_tasks: Option<Vec<String>>,
+    ) -> String {
+        let (status, Json(response)) = run_task(
+            State(state.clone()),
+            Json(StartTaskRequest {
+                task: task.to_string(),
+                cwd: cwd.to_string(),
+                include_tasks,
+                env: None,
+                timeout_ms: None,
+            }),
+        )
+        .await;
+        match response {
+            StartTaskResponse::Success(body) => body.run_id,
+            StartTaskResponse::Error(err) => panic!("{}: {}", status, err.message),
+        }
+    }
+
+    fn find<'a>(runs: &'a [task_run::Model], task: &str) -> Option<&'a task_run::Model> {
+        runs.iter().find(|run| run.task == task)
+    }
+
+    #[test]
+    fn normalize_terminal_log_line_keeps_ansi_sgr_sequences() {
+        let input = "\u{1b}[31merror\u{1b}[0m";
+        assert_eq!(normalize_terminal_log_line(input), input);
+    }
+
+    #[test]
+    fn normalize_terminal_log_line_uses_most_recent_carriage_segment() {
+        let input = "step 1\rstep 2\r\u{1b}[32mdone\u{1b}[0m";
+        assert_eq!(
+            normalize_terminal_log_line(input),
+            "\u{1b}[32mdone\u{1b}[0m"
+        );
+    }
+
+    #[test]
+    fn policy_allows_runs_since_server_start_or_within_the_window() {
+        let server_started_at = 10_000;
+        let now = 100_000;
+
+        assert!(policy_allows(
+            SatisfactionPolicy::Always,
+            0,
+            server_started_at,
+            now
+        ));
+        assert!(!policy_allows(
+            SatisfactionPolicy::SinceServerStart,
+            9_999,
+            server_started_at,
+            now
+        ));
+        assert!(policy_allows(
+            SatisfactionPolicy::SinceServerStart,
+            10_000,
+            server_started_at,
+            now
+        ));
+        assert!(policy_allows(
+            SatisfactionPolicy::WithinSeconds(30),
+            70_000,
+            server_started_at,
+            now
+        ));
+        assert!(!policy_allows(
+            SatisfactionPolicy::WithinSeconds(30),
+            69_999,
+            server_started_at,
+            now
+        ));
+    }
+
+    #[tokio::test]
+    async fn log_lines_keep_the_attempt_they_were_written_in() {
+        let state = test_state().await;
+        insert_run(&state.db, "r1", "dev", TaskRunStatus::Running).await;
+
+        let line = |text: &str| {
+            append_task_log_line(&state, "r1".into(), "dev".into(), text.into(), false)
+        };
+        line("first").await;
+        start_new_task_run_attempts(&state, &["r1".to_string()])
+            .await
+            .unwrap();
+        line("second").await;
+        // Lines for a run that no longer exists are dropped.
+        append_task_log_line(&state, "gone".into(), "dev".into(), "lost".into(), false).await;
+
+        let state = &state;
+        let lines = |attempt| async move {
+            load_task_run_logs(state, "r1", false, attempt, LogPage::default())
+                .await
+                .unwrap()
+                .unwrap()
+                .logs
+                .into_iter()
+                .map(|log| (log.line, log.attempt))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(lines(None).await, [("second".to_string(), 2)]);
+        assert_eq!(lines(Some(1)).await, [("first".to_string(), 1)]);
+        assert!(lines(Some(3)).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn log_pages_walk_back_from_the_tail() {
+        let state = test_state().await;
+        insert_run(&state.db, "r1", "dev", TaskRunStatus::Running).await;
+        for line in ["1", "2", "3", "4", "5"] {
+            append_task_log_line(&state, "r1".into(), "dev".into(), line.into(), false).await;
+        }
+
+        let state = &state;
+        let page = |request: GetTaskRunLogsRequest| async move {
+            let page = LogPage::from_request(&request).unwrap();
+            let page = load_task_run_logs(state, "r1", false, None, page)
+                .await
+                .unwrap()
+                .unwrap();
+            let lines = page
+                .logs
+                .into_iter()
+                .map(|log| log.line)
+                .collect::<Vec<_>>();
+            (lines, page.has_more)
+        };
+        let request = |after_sequence, before_sequence, limit, tail| GetTaskRunLogsRequest {
+            include_children: None,
+            attempt: None,
+            after_sequence,
+            before_sequence,
+            limit,
+            tail,
+            from_sequence: None,
+        };
+
+        assert_eq!(
+            page(request(None, None, None, Some(2))).await,
+            (vec!["4".to_string(), "5".to_string()], true)
+        );
+        assert_eq!(
+            page(request(None, Some(4), None, Some(2))).await,
+            (vec!["2".to_string(), "3".to_string()], true)
+        );
+        assert_eq!(
+            page(request(None, Some(2), None, Some(2))).await,
+            (vec!["1".to_string()], false)
+        );
+        assert_eq!(
+            page(request(Some(3), None, Some(1), None)).await,
+            (vec!["4".to_string()], true)
+        );
+        assert_eq!(page(request(None, None, None, None)).await.0.len(), 5);
+        assert!(LogPage::from_request(&request(None, None, Some(1), Some(1))).is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn signals_reach_the_process_group_of_a_running_run() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let state = test_state().await;
+        insert_run(&state.db, "r1", "dev", TaskRunStatus::Running).await;
+
+        let send = |run_id: &str, signal: &str| {
+            signal_task_run(
+                State(state.clone()),
+                Path(run_id.to_string()),
+                Json(SignalTaskRunRequest {
+                    signal: signal.to_string(),
+                }),
+            )
+        };
+        assert_eq!(send("r1", "SIGNOPE").await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(send("gone", "hup").await.0, StatusCode::NOT_FOUND);
+        assert_eq!(send("r1", "hup").await.0, StatusCode::CONFLICT);
+
+        let mut child = Command::new("sleep")
+            .arg("30")
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        let (cancel_tx, _cancel_rx) = oneshot::channel();
+        let (_stopped_tx, stopped_rx) = oneshot::channel();
+        state.running_processes.lock().await.insert(
+            "r1".to_string(),
+            RunningProcessEntry {
+                execution_id: "e1".to_string(),
+                cancel_tx,
+                stopped_rx,
+                pgid: child.id(),
+                pty: None,
+                input: None,
+            },
+        );
+
+        assert_eq!(send("r1", "usr1").await.0, StatusCode::OK);
+        assert_eq!(child.wait().await.unwrap().signal(), Some(libc::SIGUSR1));
+    }
+
+    #[tokio::test]
+    async fn a_tree_timeout_fails_only_what_is_still_going() {
+        let state = test_state().await;
+        for (id, parent, status) in [
+            ("root", None, TaskRunStatus::Success),
+            ("lint", Some("root"), TaskRunStatus::Success),
+            ("test", Some("root"), TaskRunStatus::Running),
+            ("e2e", Some("root"), TaskRunStatus::Queued),
+        ] {
+            task_run::ActiveModel {
+                parent_run_id: Set(parent.map(str::to_string)),
+                ..new_run(id, &format!("check:{id}"), status)
+            }
+            .insert(&state.db)
+            .await
+            .unwrap();
+        }
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        let (_stopped_tx, stopped_rx) = oneshot::channel();
+        state.running_processes.lock().await.insert(
+            "test".to_string(),
+            RunningProcessEntry {
+                execution_id: "e1".to_string(),
+                cancel_tx,
+                stopped_rx,
+                pgid: None,
+                pty: None,
+                input: None,
+            },
+        );
+
+        time_out_task_tree(&state, "root", Duration::from_secs(600))
+            .await
+            .unwrap();
+
+        assert!(cancel_rx.await.is_ok());
+        let runs = task_run::Entity::find()
+            .order_by_asc(task_run::Column::Id)
+            .all(&state.db)
+            .await
+            .unwrap();
+        assert_eq!(
+            runs.iter()
+                .map(|run| (run.id.as_str(), run.status, run.timed_out_after_ms))
+                .collect::<Vec<_>>(),
+            [
+                ("e2e", TaskRunStatus::Failed, Some(600_000)),
+                ("lint", TaskRunStatus::Success, None),
+                ("root", TaskRunStatus::Success, None),
+                ("test", TaskRunStatus::Failed, Some(600_000)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn a_ready_run_that_exits_gets_its_final_status() {
+        let state = test_state().await;
+        let cwd = std::env::temp_dir().to_string_lossy().to_string();
+        task_run::ActiveModel {
+            cwd: Set(cwd.clone()),
+            ..new_run("r1", "dev", TaskRunStatus::Queued)
+        }
+        .insert(&state.db)
+        .await
+        .unwrap();
+
+        let task = Task {
+            command: Some("echo up; sleep 0.5; exit 3".to_string()),
+            ready: Some(ReadyProbe {
+                log: Some("up".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        start_task_run_execution(
+            state.clone(),
+            "r1".into(),
+            "dev".into(),
+            cwd,
+            task,
+            Vec::new(),
+        );
+
+        let mut was_ready = false;
+        let finished = async {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                let run = task_run::Entity::find_by_id("r1")
+                    .one(&state.db)
+                    .await
+                    .unwrap()
+                    .unwrap();
+                was_ready |= run.ready_at.is_some();
+                if run.status != TaskRunStatus::Running && run.status != TaskRunStatus::Queued {
+                    break run;
+                }
+            }
+        };
+        let run = tokio::time::timeout(std::time::Duration::from_secs(20), finished)
+            .await
+            .expect("run never left Running");
+        assert!(was_ready);
+        assert_eq!(run.status, TaskRunStatus::Failed);
+        assert_eq!(run.exit_code, Some(3));
+    }
+
+    #[tokio::test]
+    async fn starts_a_dependency_nothing_else_is_running() {
+        let project = TestProject::new(
+            r#"{"tasks":{"build":{},"dev":{"tasks":{"api":{"dependsOn":["build"]}}}}}"#,
+        );
+        let state = test_state().await;
+        spawn_task_completion_listener(state.clone());
+        start(&state, &project.cwd, "dev").await;
+
+        let runs = wait_for_runs(&state.db, |runs| {
+            find(runs, "dev:api").is_some_and(|api| api.status == TaskRunStatus::Success)
+        })
+        .await;
+        let api = find(&runs, "dev:api").unwrap();
+        let build = find(&runs, "build").unwrap();
+        assert_eq!(build.status, TaskRunStatus::Success);
+        assert_eq!(build.started_by_run_id.as_deref(), Some(api.id.as_str()));
+        assert_eq!(build.parent_run_id, None);
+    }
+
+    #[tokio::test]
+    async fn waits_without_starting_dependencies_when_told_not_to() {
+        let project = TestProject::new(
+            r#"{"tasks":{"build":{},"dev":{"autoStartDependencies":false,
+                "tasks":{"api":{"dependsOn":["build"]}}}}}"#,
+        );
+        let state = test_state().await;
+        spawn_task_completion_listener(state.clone());
+        start(&state, &project.cwd, "dev").await;
+
+        let runs = wait_for_runs(&state.db, |runs| find(runs, "dev:api").is_some()).await;
+        let api = find(&runs, "dev:api").unwrap();
+        assert_eq!(api.status, TaskRunStatus::Queued);
+        assert_eq!(api.waiting_on.as_deref(), Some("build"));
+        assert!(find(&runs, "build").is_none());
+    }
+
+    #[tokio::test]
+    async fn starts_a_sibling_dependency_under_the_same_parent() {
+        let project = TestProject::new(
+            r#"{"tasks":{"dev":{"tasks":{"api":{"dependsOn":["dev:db"]},
+                "db":{"optional":true}}}}}"#,
+        );
+        let state = test_state().await;
+        spawn_task_completion_listener(state.clone());
+        let dev_run_id = start(&state, &project.cwd, "dev").await;
+
+        let runs = wait_for_runs(&state.db, |runs| {
+            find(runs, "dev:api").is_some_and(|api| api.status == TaskRunStatus::Success)
+        })
+        .await;
+        let api = find(&runs, "dev:api").unwrap();
+        let db_runs = runs
+            .iter()
+            .filter(|run| run.task == "dev:db")
+            .collect::<Vec<_>>();
+        assert_eq!(db_runs.len(), 1);
+        assert_eq!(
+            db_runs[0].parent_run_id.as_deref(),
+            Some(dev_run_id.as_str())
+        );
+        assert_eq!(
+            db_runs[0].started_by_run_id.as_deref(),
+            Some(api.id.as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn skipping_or_restarting_a_dependency_does_not_block_its_waiters() {
+        let project = TestProject::new(
+            r#"{"tasks":{"build":{},"deploy":{"dependsOn":["build"]},
+                "dev":{"autoStartDependencies":false,
+                    "tasks":{"db":{"optional":true},"api":{"dependsOn":["dev:db"]}}}}}"#,
+        );
+        let state = test_state().await;
+        spawn_task_completion_listener(state.clone());
+
+        // `dev:db` is not included, so it is created cancelled ahead of
+        // `dev:api`, which then waits for someone to start it.
+        start(&state, &project.cwd, "dev").await;
+        wait_for_runs(&state.db, |runs| find(runs, "dev:api").is_some()).await;
+
+        for (id, task, status) in [
+            ("build-1", "build", TaskRunStatus::Success),
+            ("deploy-1", "deploy", TaskRunStatus::Queued),
+        ] {
+            task_run::ActiveModel {
+                cwd: Set(project.cwd.clone()),
+                waiting_on: Set((task == "deploy").then(|| "build".to_string())),
+                ..new_run(id, task, status)
+            }
+            .insert(&state.db)
+            .await
+            .unwrap();
+        }
+        let (status, _) = restart_task(
+            State(state.clone()),
+            Json(RestartTaskRequest {
+                run_id: "build-1".to_string(),
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let runs = wait_for_runs(&state.db, |runs| {
+            find(runs, "deploy").is_some_and(|deploy| deploy.status.is_terminal())
+        })
+        .await;
+        assert_eq!(
+            find(&runs, "deploy").unwrap().status,
+            TaskRunStatus::Success
+        );
+        let api = find(&runs, "dev:api").unwrap();
+        assert_eq!(api.status, TaskRunStatus::Queued);
+        assert_eq!(api.waiting_on.as_deref(), Some("dev:db"));
+    }
+
+    #[tokio::test]
+    async fn same_root_run_only_counts_runs_from_the_same_invocation() {
+        let state = test_state().await;
+        for (id, task, status, parent, started_by) in [
+            ("dev", "dev", TaskRunStatus::Success, None, None),
+            ("api", "dev:api", TaskRunStatus::Queued, Some("dev"), None),
+            ("ci", "ci", TaskRunStatus::Running, None, None),
+            (
+                "build-ci",
+                "build",
+                TaskRunStatus::Success,
+                None,
+                Some("ci"),
+            ),
+            // Started from `dev`'s invocation, but a subtask of `ci`'s.
+            (
+                "lint-ci",
+                "lint",
+                TaskRunStatus::Running,
+                Some("ci"),
+                Some("api"),
+            ),
+        ] {
+            task_run::ActiveModel {
+                parent_run_id: Set(parent.map(str::to_string)),
+                started_by_run_id: Set(started_by.map(str::to_string)),
+                ..new_run(id, task, status)
+            }
+            .insert(&state.db)
+            .await
+            .unwrap();
+        }
+        let same_root_run = |task: &str| {
+            Dependency::Detailed(DetailedDependency {
+                task: task.to_string(),
+                satisfied_by: Some(SatisfactionPolicy::SameRootRun),
+            })
+        };
+        let satisfied = |task: &'static str| {
+            let state = state.clone();
+            async move {
+                is_dependency_satisfied(&state, "/repo", "dev", &same_root_run(task))
+                    .await
+                    .unwrap()
+            }
+        };
+        let active = |task: &'static str| {
+            let state = state.clone();
+            async move {
+                has_active_run_for(&state, "/repo", "dev", &same_root_run(task))
+                    .await
+                    .unwrap()
+            }
+        };
+
+        assert!(!satisfied("build").await);
+        assert!(!active("lint").await);
+        assert!(
+            is_dependency_satisfied(&state, "/repo", "ci", &same_root_run("build"))
+                .await
+                .unwrap()
+        );
+
+        task_run::ActiveModel {
+            started_by_run_id: Set(Some("api".to_string())),
+            ..new_run("build-dev", "build", TaskRunStatus::Running)
+        }
+        .insert(&state.db)
+        .await
+        .unwrap();
+        assert!(active("build").await);
+        assert!(!satisfied("build").await);
+    }
+
+    #[tokio::test]
+    async fn interrupts_runs_a_previous_server_left_without_a_process() {
+        let state = test_state().await;
+        insert_run(&state.db, "queued", "build", TaskRunStatus::Queued).await;
+        insert_run(&state.db, "done", "lint", TaskRunStatus::Success).await;
+        // Its process group is long gone.
+        task_run::ActiveModel {
+            pid: Set(Some(i32::MAX)),
+            started_at: Set(Some(0)),
+            ..new_run("running", "dev", TaskRunStatus::Running)
+        }
+        .insert(&state.db)
+        .await
+        .unwrap();
+
+        let summary = reconcile_orphaned_task_runs(&state, false).await.unwrap();
+        assert_eq!((summary.interrupted, summary.adopted), (2, 0));
+
+        let runs = task_run::Entity::find()
+            .order_by_asc(task_run::Column::Id)
+            .all(&state.db)
+            .await
+            .unwrap();
+        assert_eq!(
+            runs.iter()
+                .map(|run| (run.id.as_str(), run.status))
+                .collect::<Vec<_>>(),
+            [
+                ("done", TaskRunStatus::Success),
+                ("queued", TaskRunStatus::Interrupted),
+                ("running", TaskRunStatus::Interrupted),
+            ]
+        );
+        let logs = load_task_run_logs(&state, "running", false, None, LogPage::default())
+            .await
+            .unwrap()
+            .unwrap()
+            .logs;
+        assert_eq!(
+            logs[0].line,
+            "Interrupted: the server stopped while this run was running"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stops_or_adopts_process_groups_a_previous_server_left_running() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let state = test_state().await;
+        let mut killed = Command::new("sleep")
+            .arg("30")
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        let mut adopted = Command::new("sleep")
+            .arg("30")
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        let started_at = chrono::Utc::now().timestamp_millis();
+        for (id, child) in [("killed", &killed), ("adopted", &adopted)] {
+            task_run::ActiveModel {
+                pid: Set(child.id().map(|pid| pid as i32)),
+                started_at: Set(Some(started_at)),
+                ..new_run(id, "dev", TaskRunStatus::Running)
+            }
+            .insert(&state.db)
+            .await
+            .unwrap();
+        }
+
+        // Reconciled one at a time, as a server only ever does one or the other.
+        update_task_run_status(&state, "adopted", TaskRunStatus::Success, None)
+            .await
+            .unwrap();
+        let summary = reconcile_orphaned_task_runs(&state, false).await.unwrap();
+        assert_eq!((summary.interrupted, summary.adopted), (1, 0));
+        assert_eq!(killed.wait().await.unwrap().signal(), Some(libc::SIGTERM));
+        let run = task_run::Entity::find_by_id("killed")
+            .one(&state.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(run.status, TaskRunStatus::Interrupted);
+
+        update_task_run_status(&state, "adopted", TaskRunStatus::Running, None)
+            .await
+            .unwrap();
+        let summary = reconcile_orphaned_task_runs(&state, true).await.unwrap();
+        assert_eq!((summary.interrupted, summary.adopted), (0, 1));
+        assert!(state.running_processes.lock().await.contains_key("adopted"));
+        let run = task_run::Entity::find_by_id("adopted")
+            .one(&state.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(run.status, TaskRunStatus::Running);
+
+        // Exiting on its own finishes the run, as nothing knows how it went.
+        adopted.kill().await.unwrap();
+        let runs = wait_for_runs(&state.db, |runs| {
+            runs.iter().all(|run| run.status != TaskRunStatus::Running)
+        })
+        .await;
+        assert!(
+            runs.iter()
+                .all(|run| run.status == TaskRunStatus::Interrupted)
+        );
+        assert!(!state.running_processes.lock().await.contains_key("adopted"));
+    }
+
+    #[tokio::test]
+    async fn a_restart_keeps_the_optional_subtasks_the_run_included() {
+        let project = TestProject::new(
+            r#"{"tasks":{"dev":{"tasks":{"api":{},"storybook":{"optional":true}}}}}"#,
+        );
+        let state = test_state().await;
+        spawn_task_completion_listener(state.clone());
+        let root_run_id = start_including(
+            &state,
+            &project.cwd,
+            "dev",
+            Some(vec!["dev:storybook".to_string()]),
+        )
+        .await;
+        wait_for_runs(&state.db, |runs| {
+            find(runs, "dev:storybook").is_some_and(|run| run.status == TaskRunStatus::Success)
+        })
+        .await;
+        assert_eq!(
+            load_include_tasks(&state.db, &root_run_id).await.unwrap(),
+            HashSet::from(["dev:storybook".to_string()])
+        );
+
+        let (status, _) = restart_task(
+            State(state.clone()),
+            Json(RestartTaskRequest {
+                run_id: root_run_id.clone(),
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let runs = wait_for_runs(&state.db, |runs| {
+            find(runs, "dev:storybook")
+                .is_some_and(|run| run.attempt == 2 && run.status == TaskRunStatus::Success)
+        })
+        .await;
+        assert_eq!(
+            runs.iter()
+                .filter(|run| run.task == "dev:storybook")
+                .count(),
+            1
+        );
+    }
+
+    async fn include(state: &AppState, run_id: &str, task: &str) -> StatusCode {
+        let (status, _) = include_task(
+            State(state.clone()),
+            Path(run_id.to_string()),
+            Json(IncludeTaskRequest {
+                task: task.to_string(),
+            }),
+        )
+        .await;
+        status
+    }
+
+    async fn exclude(state: &AppState, run_id: &str, task: &str) -> StatusCode {
+        let (status, _) = exclude_task(
+            State(state.clone()),
+            Path(run_id.to_string()),
+            Json(ExcludeTaskRequest {
+                task: task.to_string(),
+            }),
+        )
+        .await;
+        status
+    }
+
+    #[tokio::test]
+    async fn includes_and_excludes_an_optional_subtask_of_a_finished_run() {
+        let project = TestProject::new(
+            r#"{"tasks":{"dev":{"tasks":{"api":{},"storybook":{"optional":true}}}}}"#,
+        );
+        let state = test_state().await;
+        spawn_task_completion_listener(state.clone());
+        let root_run_id = start(&state, &project.cwd, "dev").await;
+        wait_for_runs(&state.db, |runs| {
+            find(runs, "dev:api").is_some_and(|run| run.status == TaskRunStatus::Success)
+        })
+        .await;
+
+        assert_eq!(
+            include(&state, &root_run_id, "dev:storybook").await,
+            StatusCode::OK
+        );
+        wait_for_runs(&state.db, |runs| {
+            find(runs, "dev:storybook").is_some_and(|run| run.status == TaskRunStatus::Success)
+        })
+        .await;
+
+        assert_eq!(
+            exclude(&state, &root_run_id, "dev:storybook").await,
+            StatusCode::OK
+        );
+        assert!(
+            load_include_tasks(&state.db, &root_run_id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let (status, _) = restart_task(
+            State(state.clone()),
+            Json(RestartTaskRequest {
+                run_id: root_run_id.clone(),
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let runs = wait_for_runs(&state.db, |runs| {
+            find(runs, "dev:api")
+                .is_some_and(|run| run.attempt == 2 && run.status == TaskRunStatus::Success)
+        })
+        .await;
+        let storybook = find(&runs, "dev:storybook").unwrap();
+        assert_eq!(storybook.status, TaskRunStatus::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn only_includes_or_excludes_optional_subtasks_of_the_run() {
+        let project = TestProject::new(
+            r#"{"tasks":{"lint":{},"dev":{"tasks":{"api":{},"storybook":{"optional":true}}}}}"#,
+        );
+        let state = test_state().await;
+        let root_run_id = start(&state, &project.cwd, "dev").await;
+
+        assert_eq!(
+            include(&state, "missing", "dev:storybook").await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            include(&state, &root_run_id, "dev:api").await,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            exclude(&state, &root_run_id, "lint").await,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            exclude(&state, &root_run_id, "dev:docs").await,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn deletes_a_finished_run_with_its_subtree() {
+        let state = test_state().await;
+        insert_run(&state.db, "dev-1", "dev", TaskRunStatus::Success).await;
+        let mut api = new_run("dev-1-api", "dev:api", TaskRunStatus::Failed);
+        api.parent_run_id = Set(Some("dev-1".to_string()));
+        api.insert(&state.db).await.unwrap();
+        insert_run(&state.db, "build-1", "build", TaskRunStatus::Success).await;
+
+        let (status, _) = delete_task_run(State(state.clone()), Path("missing".to_string())).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, Json(response)) =
+            delete_task_run(State(state.clone()), Path("dev-1".to_string())).await;
+        assert_eq!(status, StatusCode::OK);
+        let DeleteTaskRunResponse::Success(body) = response else {
+            panic!("delete failed");
+        };
+        let mut deleted_run_ids = body.deleted_run_ids;
+        deleted_run_ids.sort();
+        assert_eq!(deleted_run_ids, ["dev-1", "dev-1-api"]);
+        let remaining = task_run::Entity::find().all(&state.db).await.unwrap();
+        assert_eq!(
+            remaining
+                .iter()
+                .map(|run| run.id.as_str())
+                .collect::<Vec<_>>(),
+            ["build-1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn refuses_to_delete_a_run_with_anything_still_going() {
+        let state = test_state().await;
+        insert_run(&state.db, "dev-1", "dev", TaskRunStatus::Success).await;
+        let mut api = new_run("dev-1-api", "dev:api", TaskRunStatus::Running);
+        api.parent_run_id = Set(Some("dev-1".to_string()));
+        api.insert(&state.db).await.unwrap();
+
+        let (status, _) = delete_task_run(State(state.clone()), Path("dev-1".to_string())).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(
+            task_run::Entity::find().all(&state.db).await.unwrap().len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn a_resumed_log_stream_sends_only_the_lines_after_its_sequence() {
+        let state = test_state().await;
+        insert_run(&state.db, "dev-1", "dev", TaskRunStatus::Running).await;
+        for line in ["one", "two", "three"] {
+            append_task_log_line(
+                &state,
+                "dev-1".to_string(),
+                "dev".to_string(),
+                line.to_string(),
+                false,
+            )
+            .await;
+        }
+        state.log_writer.flush().await;
+        let first = task_run_log::Entity::find()
+            .order_by_asc(task_run_log::Column::Id)
+            .one(&state.db)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let addr = serve(state.clone()).await;
+        let url = format!("ws://{addr}/api/tasks/dev-1/logs?fromSequence={}", first.id);
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        let mut next_line = async || {
+            let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+                .await
+                .expect("no message came")
+                .unwrap()
+                .unwrap();
+            match serde_json::from_str(message.to_text().unwrap()).unwrap() {
+                TaskRunLogsStreamMessage::Log { log } => log.line,
+                other => panic!("expected a log message, got {other:?}"),
+            }
+        };
+        assert_eq!(next_line().await, "two");
+        assert_eq!(next_line().await, "three");
+
+        append_task_log_line(
+            &state,
+            "dev-1".to_string(),
+            "dev".to_string(),
+            "four".to_string(),
+            false,
+        )
+        .await;
+        assert_eq!(next_line().await, "four");
+    }
+
+    #[tokio::test]
+    async fn only_a_task_with_stdin_waits_for_input() {
+        let project = TestProject::new(
+            r#"{"tasks":{"cat":{"command":"cat"},
+                "prompt":{"command":"head -n1","stdin":true}}}"#,
+        );
+        let state = test_state().await;
+        start(&state, &project.cwd, "cat").await;
+        wait_for_runs(&state.db, |runs| {
+            find(runs, "cat").is_some_and(|run| run.status == TaskRunStatus::Success)
+        })
+        .await;
+
+        let run_id = start(&state, &project.cwd, "prompt").await;
+        let send = async {
+            loop {
+                let (status, _) = send_task_run_input(
+                    State(state.clone()),
+                    Path(run_id.clone()),
+                    Json(SendTaskRunInputRequest {
+                        data: "hello\n".to_string(),
+                    }),
+                )
+                .await;
+                if status == StatusCode::OK {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), send)
+            .await
+            .expect("the task never took input");
+        wait_for_runs(&state.db, |runs| {
+            find(runs, "prompt").is_some_and(|run| run.status == TaskRunStatus::Success)
+        })
+        .await;
+        state.log_writer.flush().await;
+        let lines = task_run_log::Entity::find()
+            .filter(task_run_log::Column::RunId.eq(run_id))
+            .all(&state.db)
+            .await
+            .unwrap();
+        assert!(lines.iter().any(|log| log.line == "hello"));
+    }
+
+    #[tokio::test]
+    async fn a_crashing_task_restarts_until_cancelled_during_its_backoff() {
+        let project = TestProject::new(
+            r#"{"tasks":{"crash":{"command":"exit 1",
+                "restart":{"on":"failure","backoffMs":300}}}}"#,
+        );
+        let state = test_state().await;
+        spawn_task_completion_listener(state.clone());
+        let run_id = start(&state, &project.cwd, "crash").await;
+
+        // Waits for the second exit, so there has been a restart and the run
+        // is now waiting out the backoff before the next one.
+        let lines = async {
+            loop {
+                state.log_writer.flush().await;
+                let lines = task_run_log::Entity::find()
+                    .filter(task_run_log::Column::RunId.eq(run_id.clone()))
+                    .order_by_asc(task_run_log::Column::Id)
+                    .all(&state.db)
+                    .await
+                    .unwrap();
+                let waiting = lines
+                    .iter()
+                    .filter(|log| log.line.contains("restarting in"));
+                if waiting.count() == 2 {
+                    return lines;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        };
+        let lines = tokio::time::timeout(Duration::from_secs(10), lines)
+            .await
+            .expect("the task never exited twice");
+        let restart = lines
+            .iter()
+            .find(|log| log.line == "↻ Restart 1")
+            .expect("no restart line");
+        assert_eq!(restart.attempt, 2);
+
+        let run = task_run::Entity::find_by_id(run_id.clone())
+            .one(&state.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(run.status, TaskRunStatus::Running);
+        assert_eq!((run.attempt, run.restarts), (2, 1));
+
+        let (status, _) = cancel_task(
+            State(state.clone()),
+            Json(CancelTaskRequest {
+                run_id: run_id.clone(),
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        // Past the 600ms the second restart would have waited.
+        tokio::time::sleep(Duration::from_millis(1000)).await;
+        let run = task_run::Entity::find_by_id(run_id)
+            .one(&state.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(run.status, TaskRunStatus::Cancelled);
+        assert_eq!((run.attempt, run.restarts), (2, 1));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn a_task_timeout_stops_its_process_and_fails_the_run() {
+        let project =
+            TestProject::new(r#"{"tasks":{"slow":{"command":"sleep 30","timeoutMs":300}}}"#);
+        let state = test_state().await;
+        start(&state, &project.cwd, "slow").await;
+
+        let runs = wait_for_runs(&state.db, |runs| {
+            find(runs, "slow").is_some_and(|run| run.status == TaskRunStatus::Failed)
+        })
+        .await;
+        let run = find(&runs, "slow").unwrap();
+        assert_eq!(run.timed_out_after_ms, Some(300));
+        let pgid = run.pid.expect("no pid recorded");
+        let stopped = async {
+            // Signal 0 only checks whether anything in the group is left.
+            while unsafe { libc::kill(-pgid, 0) } == 0 {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), stopped)
+            .await
+            .expect("the process outlived its timeout");
+    }
+
+    #[tokio::test]
+    async fn a_tree_timeout_stops_waiting_once_the_tree_settles() {
+        let project = TestProject::new(r#"{"tasks":{"build":{"command":"true"}}}"#);
+        let state = test_state().await;
+        let (status, _) = run_task(
+            State(state.clone()),
+            Json(StartTaskRequest {
+                task: "build".to_string(),
+                cwd: project.cwd.clone(),
+                include_tasks: None,
+                env: None,
+                timeout_ms: Some(600_000),
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(state.task_events.receiver_count(), 1);
+
+        wait_for_runs(&state.db, |runs| {
+            find(runs, "build").is_some_and(|run| run.status == TaskRunStatus::Success)
+        })
+        .await;
+        let unsubscribed = async {
+            while state.task_events.receiver_count() > 0 {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), unsubscribed)
+            .await
+            .expect("the timeout kept waiting on a settled tree");
+    }
+
+    #[tokio::test]
+    async fn a_tree_timeout_reaches_subtasks_started_after_the_root_succeeds() {
+        let project =
+            TestProject::new(r#"{"tasks":{"check":{"tasks":{"test":{"command":"sleep 30"}}}}}"#);
+        let state = test_state().await;
+        spawn_task_completion_listener(state.clone());
+        let (status, _) = run_task(
+            State(state.clone()),
+            Json(StartTaskRequest {
+                task: "check".to_string(),
+                cwd: project.cwd.clone(),
+                include_tasks: None,
+                env: None,
+                timeout_ms: Some(1000),
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let runs = wait_for_runs(&state.db, |runs| {
+            find(runs, "check:test").is_some_and(|run| run.status == TaskRunStatus::Failed)
+        })
+        .await;
+        assert_eq!(find(&runs, "check").unwrap().status, TaskRunStatus::Success);
+        assert_eq!(
+            find(&runs, "check:test").unwrap().timed_out_after_ms,
+            Some(1000)
+        );
+    }
+}
@@ -0,0 +1,91 @@
+//! Fixtures shared by the API tests.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, DatabaseConnection, EntityTrait};
+
+use crate::api::{AppState, create_app_state, create_router};
+use crate::db::entities::task_run::{self, TaskRunStatus};
+use crate::db::{connect_sqlite, run_migrations};
+
+/// App state over a fresh in-memory database.
+pub async fn test_state() -> AppState {
+    let db = connect_sqlite("sqlite::memory:").await.unwrap();
+    run_migrations(&db).await.unwrap();
+    create_app_state(db)
+}
+
+/// A first attempt of `task` in `/repo`, for a test to set more fields on
+/// before inserting it.
+pub fn new_run(id: &str, task: &str, status: TaskRunStatus) -> task_run::ActiveModel {
+    task_run::ActiveModel {
+        id: Set(id.to_string()),
+        task: Set(task.to_string()),
+        cwd: Set("/repo".to_string()),
+        status: Set(status),
+        updated_at: Set(0),
+        attempt: Set(1),
+        ..Default::default()
+    }
+}
+
+pub async fn insert_run(
+    db: &DatabaseConnection,
+    id: &str,
+    task: &str,
+    status: TaskRunStatus,
+) -> task_run::Model {
+    new_run(id, task, status).insert(db).await.unwrap()
+}
+
+/// A project directory holding the given `task.config.json`, removed again
+/// when dropped.
+pub struct TestProject {
+    pub cwd: String,
+}
+
+impl TestProject {
+    pub fn new(config: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("bizi-test-{}", nanoid::nanoid!()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cwd = dir.to_string_lossy().to_string();
+        std::fs::write(crate::config::config_path(&cwd), config).unwrap();
+        Self { cwd }
+    }
+}
+
+impl Drop for TestProject {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.cwd);
+    }
+}
+
+/// Polls every run until `done` holds for them, failing the test if that takes
+/// longer than ten seconds.
+pub async fn wait_for_runs(
+    db: &DatabaseConnection,
+    done: impl Fn(&[task_run::Model]) -> bool,
+) -> Vec<task_run::Model> {
+    let poll = async {
+        loop {
+            let runs = task_run::Entity::find().all(db).await.unwrap();
+            if done(&runs) {
+                return runs;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(10), poll)
+        .await
+        .expect("runs never got there")
+}
+
+/// Serves the API over `state` on a free local port, for tests that need a
+/// real websocket.
+pub async fn serve(state: AppState) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(axum::serve(listener, create_router(state)).into_future());
+    addr
+}
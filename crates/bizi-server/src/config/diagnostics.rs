@@ -0,0 +1,596 @@
+//! Problems in a `task.config.json` reported with where they are, rather than
+//! the single error [`Config::load`] stops at.
+//!
+//! `serde_json::Value` forgets where each value came from and quietly keeps the
+//! last of any duplicated key, so once the document is known to be valid JSON
+//! it is walked again with the small parser below, which keeps both.
+
+use std::collections::{HashMap, HashSet};
+
+pub use bizi_api::{ConfigDiagnostic, DiagnosticSeverity};
+
+use super::{Config, ConfigError, Task};
+use crate::signal::Signal;
+
+/// Keys allowed at the top of the file besides `tasks`.
+const TOP_LEVEL_KEYS: [&str; 3] = ["$schema", "tasks", "retention"];
+
+/// Checks a config file's contents, returning every problem in document order.
+pub fn diagnose_config(source: &str) -> Vec<ConfigDiagnostic> {
+    if let Err(err) = serde_json::from_str::<serde_json::Value>(source) {
+        return vec![diagnostic(
+            DiagnosticSeverity::Error,
+            "$".to_string(),
+            Position {
+                line: err.line(),
+                column: err.column(),
+            },
+            format!("Invalid JSON: {}", strip_position(&err)),
+        )];
+    }
+
+    let Some(root) = Parser::new(source).parse() else {
+        return Vec::new();
+    };
+
+    let mut checker = Checker::default();
+    checker.check_duplicates(&root, "$");
+    checker.check_root(&root);
+
+    // The checks above look at shape; anything serde still refuses is a value of
+    // the wrong type somewhere.
+    match serde_json::from_str::<Config>(source) {
+        Ok(config) => checker.check_dependencies(&config),
+        // Already reported with a better location by `check_duplicates`.
+        Err(err) if err.to_string().starts_with("duplicate field") => {}
+        Err(err) => checker.push(
+            DiagnosticSeverity::Error,
+            "$".to_string(),
+            Position {
+                line: err.line(),
+                column: err.column(),
+            },
+            strip_position(&err),
+        ),
+    }
+
+    checker.diagnostics.sort_by_key(|d| (d.line, d.column));
+    checker.diagnostics
+}
+
+#[derive(Default)]
+struct Checker<'a> {
+    diagnostics: Vec<ConfigDiagnostic>,
+    /// The `dependsOn` entries of each task, for pointing at a bad dependency.
+    depends_on: HashMap<String, (String, &'a Node)>,
+}
+
+impl<'a> Checker<'a> {
+    fn push(
+        &mut self,
+        severity: DiagnosticSeverity,
+        path: String,
+        position: Position,
+        message: String,
+    ) {
+        self.diagnostics
+            .push(diagnostic(severity, path, position, message));
+    }
+
+    fn check_duplicates(&mut self, node: &Node, path: &str) {
+        match &node.value {
+            NodeValue::Object(entries) => {
+                let mut seen = HashSet::new();
+                for entry in entries {
+                    let entry_path = join_path(path, &entry.key);
+                    if !seen.insert(entry.key.as_str()) {
+                        self.push(
+                            DiagnosticSeverity::Error,
+                            entry_path.clone(),
+                            entry.key_position,
+                            format!("Duplicate key '{}'; only the last one is used", entry.key),
+                        );
+                    }
+                    self.check_duplicates(&entry.value, &entry_path);
+                }
+            }
+            NodeValue::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.check_duplicates(item, &format!("{path}[{index}]"));
+                }
+            }
+            NodeValue::Scalar(_) => {}
+        }
+    }
+
+    fn check_root(&mut self, root: &'a Node) {
+        let NodeValue::Object(entries) = &root.value else {
+            return;
+        };
+
+        for entry in entries {
+            if !TOP_LEVEL_KEYS.contains(&entry.key.as_str()) {
+                self.unknown_field(&join_path("$", &entry.key), entry);
+            }
+        }
+        if let Some(tasks) = root.get("tasks") {
+            self.check_tasks(tasks, "$.tasks", None);
+        }
+    }
+
+    fn check_tasks(&mut self, tasks: &'a Node, path: &str, parent_key: Option<&str>) {
+        let NodeValue::Object(entries) = &tasks.value else {
+            return;
+        };
+
+        for entry in entries {
+            let task_path = join_path(path, &entry.key);
+            if entry.key.contains(':') {
+                self.push(
+                    DiagnosticSeverity::Error,
+                    task_path.clone(),
+                    entry.key_position,
+                    format!(
+                        "Task key '{}' contains ':', which separates subtask keys, so it can never be run",
+                        entry.key
+                    ),
+                );
+            }
+
+            let task_key = match parent_key {
+                Some(parent_key) => format!("{parent_key}:{}", entry.key),
+                None => entry.key.clone(),
+            };
+            self.check_task(&entry.value, &task_path, &task_key);
+        }
+    }
+
+    fn check_task(&mut self, task: &'a Node, path: &str, task_key: &str) {
+        let NodeValue::Object(entries) = &task.value else {
+            return;
+        };
+
+        let task_fields = task_fields();
+        for entry in entries {
+            if !task_fields.contains(&entry.key) {
+                self.unknown_field(&join_path(path, &entry.key), entry);
+            }
+        }
+
+        if let Some(command) = task.get("command")
+            && let NodeValue::Scalar(serde_json::Value::String(command_text)) = &command.value
+            && command_text.trim().is_empty()
+        {
+            self.push(
+                DiagnosticSeverity::Error,
+                join_path(path, "command"),
+                command.position,
+                "Command is empty; leave it out for a task that only groups subtasks".to_string(),
+            );
+        }
+
+        if let Some(pattern) = task.get("ready").and_then(|ready| ready.get("log"))
+            && let NodeValue::Scalar(serde_json::Value::String(pattern_text)) = &pattern.value
+            && let Err(err) = regex::Regex::new(pattern_text)
+        {
+            self.push(
+                DiagnosticSeverity::Error,
+                join_path(&join_path(path, "ready"), "log"),
+                pattern.position,
+                format!("Invalid regular expression: {}", err),
+            );
+        }
+
+        if let Some(signal) = task.get("stopSignal")
+            && let NodeValue::Scalar(serde_json::Value::String(signal_name)) = &signal.value
+            && Signal::parse(signal_name).is_none()
+        {
+            self.push(
+                DiagnosticSeverity::Error,
+                join_path(path, "stopSignal"),
+                signal.position,
+                format!("Unknown signal '{}'", signal_name),
+            );
+        }
+
+        if let Some(depends_on) = task.get("dependsOn") {
+            self.depends_on.insert(
+                task_key.to_string(),
+                (join_path(path, "dependsOn"), depends_on),
+            );
+        }
+
+        if let Some(tasks) = task.get("tasks") {
+            self.check_tasks(tasks, &join_path(path, "tasks"), Some(task_key));
+        }
+    }
+
+    fn unknown_field(&mut self, path: &str, entry: &Entry) {
+        self.push(
+            DiagnosticSeverity::Warning,
+            path.to_string(),
+            entry.key_position,
+            format!("Unknown field '{}' is ignored", entry.key),
+        );
+    }
+
+    fn check_dependencies(&mut self, config: &Config) {
+        let err = match config.validate_dependencies() {
+            Ok(()) => return,
+            Err(err) => err,
+        };
+
+        let (task_key, dependency) = match &err {
+            ConfigError::UnknownDependency { task, dependency } => {
+                (task.as_str(), Some(dependency))
+            }
+            ConfigError::CircularDependency(cycle) => (cycle[0].as_str(), cycle.get(1)),
+            _ => return,
+        };
+
+        // Point at the `dependsOn` entry that starts the problem. A cycle through
+        // a subtask's parent has no such entry, so fall back to the whole list or
+        // the top of the file.
+        let (path, position) = match self.depends_on.get(task_key) {
+            Some((path, depends_on)) => match depends_on.array_item(dependency) {
+                Some((index, item)) => (format!("{path}[{index}]"), item.position),
+                None => (path.clone(), depends_on.position),
+            },
+            None => ("$".to_string(), Position { line: 1, column: 1 }),
+        };
+        self.push(DiagnosticSeverity::Error, path, position, err.to_string());
+    }
+}
+
+/// The fields a task may set, as they are spelled in `task.config.json`.
+fn task_fields() -> HashSet<String> {
+    let serde_json::Value::Object(fields) =
+        serde_json::to_value(Task::default()).unwrap_or_default()
+    else {
+        return HashSet::new();
+    };
+    // Filled in by the server when it hands tasks out, never read from the file.
+    fields
+        .into_iter()
+        .map(|(key, _)| key)
+        .filter(|key| key != "dependsOnTasks")
+        .collect()
+}
+
+fn diagnostic(
+    severity: DiagnosticSeverity,
+    path: String,
+    position: Position,
+    message: String,
+) -> ConfigDiagnostic {
+    ConfigDiagnostic {
+        severity,
+        path,
+        line: position.line,
+        column: position.column,
+        message,
+    }
+}
+
+/// serde_json appends " at line X column Y", which the diagnostic already says.
+fn strip_position(err: &serde_json::Error) -> String {
+    let message = err.to_string();
+    match message.rfind(" at line ") {
+        Some(index) => message[..index].to_string(),
+        None => message,
+    }
+}
+
+/// `$.tasks.dev` for plain keys, `$.tasks["dev:api"]` for anything else.
+fn join_path(path: &str, key: &str) -> String {
+    let is_plain = !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_plain {
+        format!("{path}.{key}")
+    } else {
+        format!("{path}[{}]", serde_json::Value::String(key.to_string()))
+    }
+}
+
+// ------------------------------------------------------------------- parser
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+#[derive(Debug)]
+struct Node {
+    position: Position,
+    value: NodeValue,
+}
+
+#[derive(Debug)]
+enum NodeValue {
+    /// Entries in document order, duplicates included.
+    Object(Vec<Entry>),
+    Array(Vec<Node>),
+    Scalar(serde_json::Value),
+}
+
+#[derive(Debug)]
+struct Entry {
+    key: String,
+    key_position: Position,
+    value: Node,
+}
+
+impl Node {
+    /// The last value for `key`, which is the one serde keeps.
+    fn get(&self, key: &str) -> Option<&Node> {
+        let NodeValue::Object(entries) = &self.value else {
+            return None;
+        };
+        entries
+            .iter()
+            .rev()
+            .find(|entry| entry.key == key)
+            .map(|entry| &entry.value)
+    }
+
+    /// Finds a `dependsOn` entry by task key, whether it is written as the key
+    /// itself or as an object with a `task` field.
+    fn array_item(&self, value: Option<&String>) -> Option<(usize, &Node)> {
+        let (NodeValue::Array(items), Some(value)) = (&self.value, value) else {
+            return None;
+        };
+        let is_key = |node: &Node| matches!(&node.value, NodeValue::Scalar(serde_json::Value::String(s)) if s == value);
+        items
+            .iter()
+            .enumerate()
+            .find(|(_, item)| is_key(item) || item.get("task").is_some_and(is_key))
+    }
+}
+
+/// Only ever run on text serde_json has already accepted, so it does not try
+/// to explain malformed input; it just gives up on it.
+struct Parser<'a> {
+    source: &'a str,
+    offset: usize,
+    position: Position,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            offset: 0,
+            position: Position { line: 1, column: 1 },
+        }
+    }
+
+    fn parse(mut self) -> Option<Node> {
+        self.skip_whitespace();
+        self.parse_value()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.offset..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let character = self.peek()?;
+        self.offset += character.len_utf8();
+        if character == '\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+        Some(character)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.bump();
+        }
+    }
+
+    fn parse_value(&mut self) -> Option<Node> {
+        let position = self.position;
+        let value = match self.peek()? {
+            '{' => NodeValue::Object(self.parse_object()?),
+            '[' => NodeValue::Array(self.parse_array()?),
+            '"' => NodeValue::Scalar(serde_json::Value::String(self.parse_string()?)),
+            _ => NodeValue::Scalar(self.parse_literal()?),
+        };
+        Some(Node { position, value })
+    }
+
+    fn parse_object(&mut self) -> Option<Vec<Entry>> {
+        self.bump();
+        let mut entries = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek()? {
+                '}' => {
+                    self.bump();
+                    return Some(entries);
+                }
+                ',' => {
+                    self.bump();
+                    continue;
+                }
+                _ => {}
+            }
+
+            let key_position = self.position;
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            if self.bump()? != ':' {
+                return None;
+            }
+            self.skip_whitespace();
+            let value = self.parse_value()?;
+            entries.push(Entry {
+                key,
+                key_position,
+                value,
+            });
+        }
+    }
+
+    fn parse_array(&mut self) -> Option<Vec<Node>> {
+        self.bump();
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek()? {
+                ']' => {
+                    self.bump();
+                    return Some(items);
+                }
+                ',' => {
+                    self.bump();
+                }
+                _ => items.push(self.parse_value()?),
+            }
+        }
+    }
+
+    /// Finds the closing quote and lets serde_json decode the escapes.
+    fn parse_string(&mut self) -> Option<String> {
+        let start = self.offset;
+        if self.bump()? != '"' {
+            return None;
+        }
+        loop {
+            match self.bump()? {
+                '"' => break,
+                '\\' => {
+                    self.bump()?;
+                }
+                _ => {}
+            }
+        }
+        serde_json::from_str(&self.source[start..self.offset]).ok()
+    }
+
+    fn parse_literal(&mut self) -> Option<serde_json::Value> {
+        let start = self.offset;
+        while self
+            .peek()
+            .is_some_and(|c| !matches!(c, ',' | ']' | '}') && !c.is_ascii_whitespace())
+        {
+            self.bump();
+        }
+        serde_json::from_str(&self.source[start..self.offset]).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summarize(source: &str) -> Vec<(DiagnosticSeverity, String, usize, usize)> {
+        diagnose_config(source)
+            .into_iter()
+            .map(|d| (d.severity, d.path, d.line, d.column))
+            .collect()
+    }
+
+    #[test]
+    fn reports_where_the_json_stops_parsing() {
+        let diagnostics = diagnose_config("{\n  \"tasks\": {\n    \"dev\": {,\n}");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Error);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (3, 13));
+        assert!(!diagnostics[0].message.contains("at line"));
+    }
+
+    #[test]
+    fn finds_shape_problems_in_document_order() {
+        let source = r#"{
+  "$schema": "https://getbizi.dev/schemas/task.config.json",
+  "tasks": {
+    "dev": {
+      "comand": "pnpm dev",
+      "tasks": {
+        "api": { "command": "  ", "ready": { "log": "(" } },
+        "web:app": { "command": "pnpm dev", "stopSignal": "TREM" }
+      }
+    },
+    "dev": { "command": "pnpm dev" }
+  }
+}"#;
+
+        assert_eq!(
+            summarize(source),
+            vec![
+                (
+                    DiagnosticSeverity::Warning,
+                    "$.tasks.dev.comand".to_string(),
+                    5,
+                    7
+                ),
+                (
+                    DiagnosticSeverity::Error,
+                    "$.tasks.dev.tasks.api.command".to_string(),
+                    7,
+                    29
+                ),
+                (
+                    DiagnosticSeverity::Error,
+                    "$.tasks.dev.tasks.api.ready.log".to_string(),
+                    7,
+                    53
+                ),
+                (
+                    DiagnosticSeverity::Error,
+                    r#"$.tasks.dev.tasks["web:app"]"#.to_string(),
+                    8,
+                    9
+                ),
+                (
+                    DiagnosticSeverity::Error,
+                    r#"$.tasks.dev.tasks["web:app"].stopSignal"#.to_string(),
+                    8,
+                    59
+                ),
+                (DiagnosticSeverity::Error, "$.tasks.dev".to_string(), 11, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn points_at_the_dependency_that_causes_a_problem() {
+        let source = "{\"tasks\": {\n  \"lint\": {},\n  \"test\": {\"dependsOn\": [\"lint\", \"biuld\"]}\n}}";
+
+        let diagnostics = diagnose_config(source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "$.tasks.test.dependsOn[1]");
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (3, 34));
+        assert_eq!(
+            diagnostics[0].message,
+            "Task 'test' depends on unknown task 'biuld'"
+        );
+    }
+
+    #[test]
+    fn reports_values_of_the_wrong_type() {
+        let diagnostics = diagnose_config(r#"{"tasks": {"dev": {"optional": "yes"}}}"#);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Error);
+        assert!(diagnostics[0].message.starts_with("invalid type"));
+    }
+
+    #[test]
+    fn a_valid_config_has_no_diagnostics() {
+        assert!(
+            diagnose_config(
+                r#"{"tasks": {"build": {"command": "cargo build", "env": {"A": "\"b\""}},
+                    "test": {"dependsOn": ["build"], "tasks": {"unit": {"command": "cargo test"}}}}}"#
+            )
+            .is_empty()
+        );
+    }
+}
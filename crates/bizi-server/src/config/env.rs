@@ -0,0 +1,226 @@
+//! The environment a task's command runs with, built from the task's `envFile`
+//! and `env` settings plus any variables the run was started with.
+
+use indexmap::IndexMap;
+use std::path::Path;
+
+use super::EnvFile;
+
+/// One level's own `envFile` and `env`, as its subtasks inherit them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvLayer {
+    pub env_file: Option<EnvFile>,
+    pub env: Option<IndexMap<String, String>>,
+}
+
+/// Builds the variables to set on a task's process. Later sources win: each of
+/// `layers`, from the outermost ancestor down to the task itself, applies its
+/// `envFile` and then its `env`, and `run_env` comes last.
+///
+/// Values are interpolated as they are applied. `${VAR}` expands to a variable
+/// set earlier in that order, falling back to the server's own environment, and
+/// to nothing when neither has it.
+pub async fn resolve_task_env(
+    project_cwd: &str,
+    layers: &[EnvLayer],
+    run_env: &IndexMap<String, String>,
+) -> Result<IndexMap<String, String>, String> {
+    let mut resolved = IndexMap::new();
+
+    for layer in layers {
+        for env_file in layer.env_file.iter().flat_map(|env_file| env_file.paths()) {
+            let path = Path::new(project_cwd).join(env_file);
+            let contents = tokio::fs::read_to_string(&path)
+                .await
+                .map_err(|err| format!("Failed to read env file {}: {}", path.display(), err))?;
+            let entries = parse_dotenv(&contents)
+                .map_err(|err| format!("Failed to parse env file {}: {}", path.display(), err))?;
+            for (key, value) in entries {
+                apply(&mut resolved, key, &value);
+            }
+        }
+
+        for (key, value) in layer.env.iter().flatten() {
+            apply(&mut resolved, key.clone(), value);
+        }
+    }
+
+    for (key, value) in run_env {
+        apply(&mut resolved, key.clone(), value);
+    }
+
+    Ok(resolved)
+}
+
+fn apply(resolved: &mut IndexMap<String, String>, key: String, value: &str) {
+    let value = interpolate(value, |name| {
+        resolved
+            .get(name)
+            .cloned()
+            .or_else(|| std::env::var(name).ok())
+    });
+    resolved.insert(key, value);
+}
+
+/// Expands every `${VAR}` in `value`. An unterminated `${` is left as-is.
+fn interpolate(value: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            output.push_str(&rest[start..]);
+            return output;
+        };
+        output.push_str(&lookup(&after[..end]).unwrap_or_default());
+        rest = &after[end + 1..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Parses the subset of the dotenv format tools generally agree on: `KEY=VALUE`
+/// lines, an optional `export` prefix, `#` comments, and single or double
+/// quoted values. Double quotes understand `\n`, `\t`, `\"` and `\\`.
+fn parse_dotenv(contents: &str) -> Result<Vec<(String, String)>, String> {
+    let mut entries = Vec::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line).trim_start();
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("line {}: expected KEY=VALUE", index + 1));
+        };
+
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(format!("line {}: invalid key '{}'", index + 1, key));
+        }
+
+        let value = parse_dotenv_value(value.trim())
+            .ok_or_else(|| format!("line {}: unterminated quoted value", index + 1))?;
+        entries.push((key.to_string(), value));
+    }
+
+    Ok(entries)
+}
+
+fn parse_dotenv_value(value: &str) -> Option<String> {
+    if let Some(quoted) = value.strip_prefix('\'') {
+        let end = quoted.find('\'')?;
+        return Some(quoted[..end].to_string());
+    }
+
+    if let Some(quoted) = value.strip_prefix('"') {
+        let mut output = String::new();
+        let mut chars = quoted.chars();
+        while let Some(character) = chars.next() {
+            match character {
+                '"' => return Some(output),
+                '\\' => match chars.next()? {
+                    'n' => output.push('\n'),
+                    't' => output.push('\t'),
+                    other => output.push(other),
+                },
+                other => output.push(other),
+            }
+        }
+        return None;
+    }
+
+    // Unquoted values end at an inline comment.
+    let value = match value.find(" #") {
+        Some(comment) => &value[..comment],
+        None => value,
+    };
+    Some(value.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dotenv_lines() {
+        let entries = parse_dotenv(
+            "# comment\n\
+             PORT=3000\n\
+             export DATABASE_URL = postgres://localhost/app # local db\n\
+             GREETING=\"hello\\nworld\"\n\
+             RAW='${NOT_EXPANDED}'\n\
+             EMPTY=\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                ("PORT".to_string(), "3000".to_string()),
+                (
+                    "DATABASE_URL".to_string(),
+                    "postgres://localhost/app".to_string()
+                ),
+                ("GREETING".to_string(), "hello\nworld".to_string()),
+                ("RAW".to_string(), "${NOT_EXPANDED}".to_string()),
+                ("EMPTY".to_string(), String::new()),
+            ]
+        );
+        assert!(parse_dotenv("NOPE").is_err());
+        assert!(parse_dotenv("OPEN=\"never closed").is_err());
+    }
+
+    #[test]
+    fn interpolates_braced_variables() {
+        let lookup = |name: &str| (name == "PORT").then(|| "3000".to_string());
+        assert_eq!(
+            interpolate("http://localhost:${PORT}/api", lookup),
+            "http://localhost:3000/api"
+        );
+        assert_eq!(interpolate("${MISSING}-$PORT", lookup), "-$PORT");
+        assert_eq!(interpolate("broken ${PORT", lookup), "broken ${PORT");
+    }
+
+    #[tokio::test]
+    async fn later_sources_override_and_interpolate_earlier_ones() {
+        let layer = EnvLayer {
+            env: Some(IndexMap::from([
+                ("PORT".to_string(), "4000".to_string()),
+                ("URL".to_string(), "http://localhost:${PORT}".to_string()),
+            ])),
+            ..EnvLayer::default()
+        };
+        let run_env = IndexMap::from([("PORT".to_string(), "5000".to_string())]);
+
+        let env = resolve_task_env("/nonexistent", &[layer], &run_env)
+            .await
+            .unwrap();
+        assert_eq!(env["URL"], "http://localhost:4000");
+        assert_eq!(env["PORT"], "5000");
+    }
+
+    #[tokio::test]
+    async fn a_subtask_env_file_overrides_its_parent_env() {
+        let project = std::env::temp_dir().join(format!("bizi-env-{}", std::process::id()));
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(project.join("api.env"), "PORT=4000\n").unwrap();
+        let config: crate::config::Config = serde_json::from_str(
+            r#"{"tasks":{"dev":{"env":{"PORT":"3000","HOST":"localhost"},
+                "tasks":{"api":{"envFile":"api.env"}}}}}"#,
+        )
+        .unwrap();
+        let layers = config.get_task_env_layers("dev:api");
+
+        let env = resolve_task_env(project.to_str().unwrap(), &layers, &IndexMap::new()).await;
+        std::fs::remove_dir_all(&project).unwrap();
+        let env = env.unwrap();
+        assert_eq!(env["PORT"], "4000");
+        assert_eq!(env["HOST"], "localhost");
+    }
+}
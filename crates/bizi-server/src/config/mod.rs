@@ -1,12 +1,51 @@
+mod diagnostics;
+mod env;
+
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
-use std::{io::ErrorKind, path::Path};
+use std::{
+    collections::HashSet,
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+};
 
-pub use bizi_api::Task;
+pub use bizi_api::{
+    Dependency, DetailedDependency, EnvFile, ReadyProbe, RestartOn, RestartPolicy,
+    SatisfactionPolicy, Task,
+};
+pub use diagnostics::diagnose_config;
+pub use env::{EnvLayer, resolve_task_env};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub tasks: IndexMap<String, Task>,
+    /// Overrides the server's retention limits for this project's runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention: Option<Retention>,
+}
+
+/// How much run history to keep. A limit left unset falls back to the
+/// server's, and `0` turns it off.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Retention {
+    /// Runs to keep per task, newest first. A run counts with its subtasks.
+    pub keep_runs: Option<u64>,
+    /// Log lines to keep per run, newest first.
+    pub max_log_lines: Option<u64>,
+    /// Days since a run last changed before it is deleted.
+    pub max_age_days: Option<u64>,
+}
+
+impl Retention {
+    /// `self`'s limits, with `defaults` filling in the ones it leaves unset.
+    pub fn or(self, defaults: Retention) -> Retention {
+        Retention {
+            keep_runs: self.keep_runs.or(defaults.keep_runs),
+            max_log_lines: self.max_log_lines.or(defaults.max_log_lines),
+            max_age_days: self.max_age_days.or(defaults.max_age_days),
+        }
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -15,36 +54,89 @@ pub enum ConfigError {
     Io(#[from] std::io::Error),
     #[error("JSON parse error: {0}")]
     Json(#[from] serde_json::Error),
+    #[error("Task '{task}' depends on unknown task '{dependency}'")]
+    UnknownDependency { task: String, dependency: String },
+    #[error("Circular task dependency: {}", .0.join(" -> "))]
+    CircularDependency(Vec<String>),
 }
 
 impl ConfigError {
     pub fn is_not_found(&self) -> bool {
         matches!(self, ConfigError::Io(e) if e.kind() == ErrorKind::NotFound)
     }
+
+    /// Whether the config file was read but cannot be used as written, which is
+    /// the user's to fix rather than a server failure.
+    pub fn is_invalid(&self) -> bool {
+        matches!(
+            self,
+            ConfigError::Json(_)
+                | ConfigError::UnknownDependency { .. }
+                | ConfigError::CircularDependency(_)
+        )
+    }
 }
 
 impl Config {
     pub async fn load(path: &str) -> Result<Self, ConfigError> {
-        let path = Path::new(&path);
-        let config = tokio::fs::read_to_string(path.join("task.config.json")).await?;
+        let config = tokio::fs::read_to_string(config_path(path)).await?;
+
+        let config: Config = serde_json::from_str(&config)?;
+        config.validate_dependencies()?;
+        Ok(config)
+    }
+
+    /// Checks that every `dependsOn` names a task that exists and that no task
+    /// ends up waiting on itself. A subtask only starts once its parent succeeds,
+    /// so it waits on its parent just like on a `dependsOn` entry.
+    pub fn validate_dependencies(&self) -> Result<(), ConfigError> {
+        let all_tasks = self.get_all_tasks();
+        let mut edges: IndexMap<&str, Vec<&str>> = IndexMap::new();
+        for (key, task) in &all_tasks {
+            let waits_on = edges.entry(key.as_str()).or_default();
+            if let Some((parent, _)) = key.rsplit_once(':') {
+                waits_on.push(parent);
+            }
+            for dependency in task.depends_on.iter().flatten() {
+                let Some((dependency, _)) = all_tasks.get_key_value(dependency.task()) else {
+                    return Err(ConfigError::UnknownDependency {
+                        task: key.clone(),
+                        dependency: dependency.task().to_string(),
+                    });
+                };
+                waits_on.push(dependency.as_str());
+            }
+        }
 
-        Ok(serde_json::from_str(&config)?)
+        let mut finished = HashSet::new();
+        for key in edges.keys() {
+            let mut path = Vec::new();
+            if let Some(cycle) = find_cycle(&edges, key, &mut path, &mut finished) {
+                return Err(ConfigError::CircularDependency(cycle));
+            }
+        }
+        Ok(())
     }
 
-    /// TODO: handle circular task references (this will just be an error as it's a user mistake and we can't fix it)
+    /// The returned task carries the `cwd` and `autoStartDependencies` it
+    /// inherits from the closest ancestor that sets them. Its `env` and
+    /// `envFile` stay its own; `get_task_env_layers` has the inherited ones.
     pub fn get_task(&self, task_key: String) -> Option<Task> {
-        let mut task = if let Some(task) = get_task(&self.tasks, task_key) {
-            task.clone()
-        } else {
-            return None;
-        };
+        let lineage = get_task_lineage(&self.tasks, task_key)?;
+        let mut task = Task::clone(lineage.last()?);
+        task.cwd = lineage.iter().rev().find_map(|task| task.cwd.clone());
+        task.auto_start_dependencies = lineage
+            .iter()
+            .rev()
+            .find_map(|task| task.auto_start_dependencies);
 
         if let Some(depends_on) = &task.depends_on {
             let mut depends_on_tasks = IndexMap::new();
-            for key in depends_on.iter() {
+            for dependency in depends_on.iter() {
+                let key = dependency.task().to_string();
                 let depends_on_task = get_task(&self.tasks, key.clone());
                 if let Some(depends_on_task) = depends_on_task {
-                    depends_on_tasks.insert(key.clone(), depends_on_task.clone());
+                    depends_on_tasks.insert(key, depends_on_task.clone());
                 }
             }
             task.depends_on_tasks = Some(depends_on_tasks);
@@ -53,28 +145,107 @@ impl Config {
         Some(task)
     }
 
+    /// The `envFile` and `env` of every level from the outermost ancestor down
+    /// to the task itself, in the order `resolve_task_env` applies them.
+    pub fn get_task_env_layers(&self, task_key: &str) -> Vec<EnvLayer> {
+        get_task_lineage(&self.tasks, task_key.to_string())
+            .into_iter()
+            .flatten()
+            .map(|task| EnvLayer {
+                env_file: task.env_file.clone(),
+                env: task.env.clone(),
+            })
+            .collect()
+    }
+
     pub fn get_all_tasks(&self) -> IndexMap<String, Task> {
         get_all_tasks(&self.tasks, None)
     }
 }
 
+/// Where the config for a project `cwd` lives.
+pub fn config_path(cwd: &str) -> PathBuf {
+    Path::new(cwd).join("task.config.json")
+}
+
+/// Resolves the directory a task's command runs in. A task's `cwd` is relative
+/// to the directory holding `task.config.json`, which is the project `cwd`.
+pub fn resolve_task_working_dir(project_cwd: &str, task: &Task) -> PathBuf {
+    let project_cwd = Path::new(project_cwd);
+    let Some(task_cwd) = task.cwd.as_deref().filter(|cwd| !cwd.trim().is_empty()) else {
+        return project_cwd.to_path_buf();
+    };
+
+    // Joined lexically rather than canonicalized so a directory that does not
+    // exist yet still resolves to something the run can report.
+    let mut resolved = PathBuf::new();
+    for component in project_cwd.join(task_cwd).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    resolved.push(component);
+                }
+            }
+            _ => resolved.push(component),
+        }
+    }
+    resolved
+}
+
+/// Depth-first walk from `key`, returning the first cycle it runs into as the
+/// keys along it with the repeated key at both ends.
+fn find_cycle<'a>(
+    edges: &IndexMap<&'a str, Vec<&'a str>>,
+    key: &'a str,
+    path: &mut Vec<&'a str>,
+    finished: &mut HashSet<&'a str>,
+) -> Option<Vec<String>> {
+    if let Some(start) = path.iter().position(|visiting| *visiting == key) {
+        let mut cycle: Vec<String> = path[start..].iter().map(|key| key.to_string()).collect();
+        cycle.push(key.to_string());
+        return Some(cycle);
+    }
+    if finished.contains(key) {
+        return None;
+    }
+
+    path.push(key);
+    for next in edges.get(key).into_iter().flatten() {
+        if let Some(cycle) = find_cycle(edges, next, path, finished) {
+            return Some(cycle);
+        }
+    }
+    path.pop();
+    finished.insert(key);
+    None
+}
+
 /// Handles getting nested tasks like `dev:packages` or `dev:server`.
 fn get_task(tasks: &IndexMap<String, Task>, task_key: String) -> Option<&Task> {
+    get_task_lineage(tasks, task_key)?.pop()
+}
+
+/// Like [`get_task`], but returns every task along the key, outermost first, so
+/// settings that subtasks inherit can be looked up on their ancestors.
+fn get_task_lineage(tasks: &IndexMap<String, Task>, task_key: String) -> Option<Vec<&Task>> {
     let task_key_segments = task_key.split(":").collect::<Vec<&str>>();
-    if task_key_segments.len() == 0 {
+    if task_key_segments.is_empty() {
         return None;
     }
 
     let task = tasks.get(task_key_segments[0])?;
     if task_key_segments.len() == 1 {
-        return Some(task); // only looking for one segment so just return the task
+        return Some(vec![task]); // only looking for one segment so just return the task
     }
 
     if let Some(tasks) = &task.tasks {
-        return get_task(tasks, task_key_segments[1..].join(":"));
+        let mut lineage = get_task_lineage(tasks, task_key_segments[1..].join(":"))?;
+        lineage.insert(0, task);
+        return Some(lineage);
     }
 
-    Some(task)
+    Some(vec![task])
 }
 
 fn get_all_tasks(
@@ -86,8 +257,134 @@ fn get_all_tasks(
     for (key, task) in tasks.iter() {
         task_keys.insert(format!("{}{}", &base, key), task.clone());
         if let Some(tasks) = &task.tasks {
-            task_keys.extend(get_all_tasks(&tasks, Some(format!("{}{}", &base, key))));
+            task_keys.extend(get_all_tasks(tasks, Some(format!("{}{}", &base, key))));
         }
     }
     task_keys
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(json: &str) -> Config {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn subtasks_inherit_the_closest_cwd() {
+        let config = config(
+            r#"{"tasks":{"dev":{"cwd":"./apps","tasks":{
+                "api":{"command":"cargo run"},
+                "site":{"cwd":"./apps/site","command":"pnpm dev"}
+            }}}}"#,
+        );
+
+        assert_eq!(
+            config
+                .get_task("dev:api".to_string())
+                .unwrap()
+                .cwd
+                .as_deref(),
+            Some("./apps")
+        );
+        assert_eq!(
+            config
+                .get_task("dev:site".to_string())
+                .unwrap()
+                .cwd
+                .as_deref(),
+            Some("./apps/site")
+        );
+        assert!(config.get_task("dev:ghost".to_string()).is_none());
+    }
+
+    #[test]
+    fn subtasks_layer_their_env_over_their_ancestors() {
+        let config = config(
+            r#"{"tasks":{"dev":{"env":{"NODE_ENV":"development","PORT":"3000"},"envFile":".env",
+                "tasks":{"api":{"env":{"PORT":"4000"},"envFile":["apps/api/.env"]}}}}}"#,
+        );
+
+        let layers = config.get_task_env_layers("dev:api");
+        assert_eq!(layers.len(), 2);
+        assert_eq!(layers[0].env.as_ref().unwrap()["PORT"], "3000");
+        assert_eq!(layers[0].env_file.as_ref().unwrap().paths(), [".env"]);
+        assert_eq!(layers[1].env.as_ref().unwrap()["PORT"], "4000");
+        assert_eq!(
+            layers[1].env_file.as_ref().unwrap().paths(),
+            ["apps/api/.env"]
+        );
+
+        let task = config.get_task("dev:api".to_string()).unwrap();
+        assert_eq!(task.env.unwrap().len(), 1);
+        assert!(config.get_task_env_layers("dev:ghost").is_empty());
+    }
+
+    #[test]
+    fn reports_the_full_dependency_cycle() {
+        let config = config(
+            r#"{"tasks":{"build":{"dependsOn":["dev:api"]},
+                "dev":{"tasks":{"api":{"dependsOn":["build"]}}}}}"#,
+        );
+
+        let err = config.validate_dependencies().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Circular task dependency: build -> dev:api -> build"
+        );
+        assert!(err.is_invalid());
+    }
+
+    #[test]
+    fn parents_cannot_depend_on_their_own_subtasks() {
+        let config = config(r#"{"tasks":{"dev":{"dependsOn":["dev:api"],"tasks":{"api":{}}}}}"#);
+
+        assert_eq!(
+            config.validate_dependencies().unwrap_err().to_string(),
+            "Circular task dependency: dev -> dev:api -> dev"
+        );
+    }
+
+    #[test]
+    fn reports_unknown_dependencies() {
+        let config = config(
+            r#"{"tasks":{"lint":{},"dev":{"tasks":{"api":{"dependsOn":["lint","biuld"]}}}}}"#,
+        );
+
+        assert_eq!(
+            config.validate_dependencies().unwrap_err().to_string(),
+            "Task 'dev:api' depends on unknown task 'biuld'"
+        );
+        assert!(
+            self::config(r#"{"tasks":{"lint":{},"test":{"dependsOn":["lint"]}}}"#)
+                .validate_dependencies()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn resolves_working_dirs_against_the_project_root() {
+        let task = |cwd: Option<&str>| Task {
+            cwd: cwd.map(str::to_string),
+            ..Task::default()
+        };
+
+        assert_eq!(
+            resolve_task_working_dir("/repo", &task(None)),
+            PathBuf::from("/repo")
+        );
+        assert_eq!(
+            resolve_task_working_dir("/repo", &task(Some("./apps/api"))),
+            PathBuf::from("/repo/apps/api")
+        );
+        assert_eq!(
+            resolve_task_working_dir("/repo/tools", &task(Some("../apps/./site"))),
+            PathBuf::from("/repo/apps/site")
+        );
+        assert_eq!(
+            resolve_task_working_dir("/repo", &task(Some("/srv/api"))),
+            PathBuf::from("/srv/api")
+        );
+    }
+}
@@ -1,2 +1,3 @@
 pub mod task_run;
+pub mod task_run_include_task;
 pub mod task_run_log;
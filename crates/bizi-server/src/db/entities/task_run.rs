@@ -15,9 +15,40 @@ pub struct Model {
     pub task: String,
     pub cwd: String,
     pub parent_run_id: Option<String>,
+    /// The run whose `dependsOn` this run was started to satisfy.
+    pub started_by_run_id: Option<String>,
     pub status: TaskRunStatus,
     pub updated_at: i64,
     pub waiting_on: Option<String>,
+    /// The task a `Blocked` run was waiting on when it could no longer succeed.
+    pub blocked_by: Option<String>,
+    pub working_dir: Option<String>,
+    /// Variables the run was started with, as a JSON object. Child runs copy
+    /// their parent's.
+    pub env: Option<String>,
+    /// When the task's `ready` probe passed. Cleared each time the run starts.
+    pub ready_at: Option<i64>,
+    /// How the process exited. A process killed by a signal is given the
+    /// shell's `128 + n`, with the signal's name in `signal`.
+    pub exit_code: Option<i32>,
+    pub signal: Option<String>,
+    /// The shell the command runs in, which leads a process group of its own,
+    /// so this is the group's ID too.
+    pub pid: Option<i32>,
+    /// When the process started and exited. Both are reset on every start.
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+    /// Starts at 1 and goes up each time the run is restarted. Log lines keep
+    /// the attempt they were written in, so earlier output survives a restart.
+    #[sea_orm(default_value = 1)]
+    pub attempt: i32,
+    /// How many of those attempts the task's `restart` policy started. Reset
+    /// when the run is restarted by hand.
+    #[sea_orm(default_value = 0)]
+    pub restarts: i32,
+    /// Set when the run was stopped for running past a time limit, to that
+    /// limit. Cleared each time the run starts.
+    pub timed_out_after_ms: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -13,6 +13,9 @@ pub struct Model {
     pub line: String,
     pub is_stderr: bool,
     pub timestamp: i64,
+    /// The run's `attempt` when the line was written.
+    #[sea_orm(default_value = 1)]
+    pub attempt: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+use crate::db::entities::task_run;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The first migration creates `task_runs` from the current entity, so a
+        // fresh database already has this column.
+        if manager.has_column("task_runs", "ready_at").await? {
+            return Ok(());
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(task_run::Entity)
+                    .add_column(
+                        ColumnDef::new(task_run::Column::ReadyAt)
+                            .big_integer()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(task_run::Entity)
+                    .drop_column(task_run::Column::ReadyAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+
+use crate::db::entities::task_run;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The first migration creates `task_runs` from the current entity, so a
+        // fresh database already has these columns.
+        if manager.has_column("task_runs", "exit_code").await? {
+            return Ok(());
+        }
+
+        // SQLite only takes one column per `ALTER TABLE`.
+        let columns = [
+            ColumnDef::new(task_run::Column::ExitCode)
+                .integer()
+                .null()
+                .to_owned(),
+            ColumnDef::new(task_run::Column::Signal)
+                .string()
+                .null()
+                .to_owned(),
+            ColumnDef::new(task_run::Column::Pid)
+                .integer()
+                .null()
+                .to_owned(),
+            ColumnDef::new(task_run::Column::StartedAt)
+                .big_integer()
+                .null()
+                .to_owned(),
+            ColumnDef::new(task_run::Column::FinishedAt)
+                .big_integer()
+                .null()
+                .to_owned(),
+        ];
+        for mut column in columns {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(task_run::Entity)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            task_run::Column::ExitCode,
+            task_run::Column::Signal,
+            task_run::Column::Pid,
+            task_run::Column::StartedAt,
+            task_run::Column::FinishedAt,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(task_run::Entity)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
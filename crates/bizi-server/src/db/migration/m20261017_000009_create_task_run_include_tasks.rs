@@ -0,0 +1,34 @@
+use sea_orm::Schema;
+use sea_orm_migration::prelude::*;
+
+use crate::db::entities::task_run_include_task;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let schema = Schema::new(manager.get_database_backend());
+        // The composite primary key on (root_run_id, task) doubles as the index
+        // for looking a root run's set up.
+        manager
+            .create_table(
+                schema
+                    .create_table_from_entity(task_run_include_task::Entity)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(task_run_include_task::Entity)
+                    .to_owned(),
+            )
+            .await
+    }
+}
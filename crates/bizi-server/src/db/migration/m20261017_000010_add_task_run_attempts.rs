@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+
+use crate::db::entities::{task_run, task_run_log};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Both tables are first created from the current entities, so a fresh
+        // database already has these columns. Existing rows are all from a
+        // run's first attempt, as restarts used to delete the logs.
+        if !manager.has_column("task_runs", "attempt").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(task_run::Entity)
+                        .add_column(
+                            ColumnDef::new(task_run::Column::Attempt)
+                                .integer()
+                                .not_null()
+                                .default(1),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+        if !manager.has_column("task_run_logs", "attempt").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(task_run_log::Entity)
+                        .add_column(
+                            ColumnDef::new(task_run_log::Column::Attempt)
+                                .integer()
+                                .not_null()
+                                .default(1),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(task_run_log::Entity)
+                    .drop_column(task_run_log::Column::Attempt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(task_run::Entity)
+                    .drop_column(task_run::Column::Attempt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// An external-content index over `task_run_logs.line`, kept in step by
+// triggers so every way of writing or deleting logs updates it. The trigram
+// tokenizer matches any substring, the way `grep` would, rather than only
+// whole words.
+const UP: &[&str] = &[
+    "CREATE VIRTUAL TABLE IF NOT EXISTS task_run_logs_fts USING fts5(
+        line,
+        content = 'task_run_logs',
+        content_rowid = 'id',
+        tokenize = 'trigram'
+    )",
+    "CREATE TRIGGER IF NOT EXISTS task_run_logs_fts_insert AFTER INSERT ON task_run_logs BEGIN
+        INSERT INTO task_run_logs_fts (rowid, line) VALUES (new.id, new.line);
+    END",
+    "CREATE TRIGGER IF NOT EXISTS task_run_logs_fts_delete AFTER DELETE ON task_run_logs BEGIN
+        INSERT INTO task_run_logs_fts (task_run_logs_fts, rowid, line)
+            VALUES ('delete', old.id, old.line);
+    END",
+    "CREATE TRIGGER IF NOT EXISTS task_run_logs_fts_update AFTER UPDATE OF line ON task_run_logs BEGIN
+        INSERT INTO task_run_logs_fts (task_run_logs_fts, rowid, line)
+            VALUES ('delete', old.id, old.line);
+        INSERT INTO task_run_logs_fts (rowid, line) VALUES (new.id, new.line);
+    END",
+    // Indexes the lines written before the table existed.
+    "INSERT INTO task_run_logs_fts (task_run_logs_fts) VALUES ('rebuild')",
+];
+
+const DOWN: &[&str] = &[
+    "DROP TRIGGER IF EXISTS task_run_logs_fts_update",
+    "DROP TRIGGER IF EXISTS task_run_logs_fts_delete",
+    "DROP TRIGGER IF EXISTS task_run_logs_fts_insert",
+    "DROP TABLE IF EXISTS task_run_logs_fts",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for statement in UP {
+            manager
+                .get_connection()
+                .execute_unprepared(statement)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for statement in DOWN {
+            manager
+                .get_connection()
+                .execute_unprepared(statement)
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,16 @@
 mod m20260212_000001_create_task_runs;
 mod m20260212_000002_create_task_run_logs;
+mod m20261017_000003_add_task_run_working_dir;
+mod m20261017_000004_add_task_run_env;
+mod m20261017_000005_add_task_run_ready_at;
+mod m20261017_000006_add_task_run_started_by_run_id;
+mod m20261017_000007_add_task_run_blocked_by;
+mod m20261017_000008_add_task_run_exit_details;
+mod m20261017_000009_create_task_run_include_tasks;
+mod m20261017_000010_add_task_run_attempts;
+mod m20261017_000011_create_task_run_logs_fts;
+mod m20261017_000012_add_task_run_restarts;
+mod m20261017_000013_add_task_run_timed_out_after_ms;
 
 use sea_orm_migration::prelude::*;
 
@@ -11,6 +22,17 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20260212_000001_create_task_runs::Migration),
             Box::new(m20260212_000002_create_task_run_logs::Migration),
+            Box::new(m20261017_000003_add_task_run_working_dir::Migration),
+            Box::new(m20261017_000004_add_task_run_env::Migration),
+            Box::new(m20261017_000005_add_task_run_ready_at::Migration),
+            Box::new(m20261017_000006_add_task_run_started_by_run_id::Migration),
+            Box::new(m20261017_000007_add_task_run_blocked_by::Migration),
+            Box::new(m20261017_000008_add_task_run_exit_details::Migration),
+            Box::new(m20261017_000009_create_task_run_include_tasks::Migration),
+            Box::new(m20261017_000010_add_task_run_attempts::Migration),
+            Box::new(m20261017_000011_create_task_run_logs_fts::Migration),
+            Box::new(m20261017_000012_add_task_run_restarts::Migration),
+            Box::new(m20261017_000013_add_task_run_timed_out_after_ms::Migration),
         ]
     }
 }
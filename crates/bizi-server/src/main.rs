@@ -1,10 +1,23 @@
-use bizi_server::api::{create_app_state, create_router, tasks};
+use bizi_server::api::tasks;
+use bizi_server::api::{create_app_state, create_router, retention};
+use bizi_server::config::Retention;
 use bizi_server::db::{connect_sqlite, run_migrations};
 use clap::Parser;
 use tokio::net::TcpListener;
 
 const DATABASE_URL: &str = "sqlite://bizi.db?mode=rwc";
 
+/// What to do with the process groups of runs a previous server left running.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+enum OrphanedProcesses {
+    /// Stop them the way cancelling their runs would.
+    #[default]
+    Kill,
+    /// Keep their runs going until their process groups exit. Output they
+    /// write from then on is not captured.
+    Adopt,
+}
+
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 struct Args {
@@ -12,6 +25,19 @@ struct Args {
     address: String,
     #[arg(long, default_value_t = 7436)]
     port: u16,
+    /// What to do with task processes a previous server left running.
+    #[arg(long, value_enum, default_value_t = OrphanedProcesses::Kill)]
+    orphaned_processes: OrphanedProcesses,
+    /// Runs to keep per task, unless a project sets its own. 0 keeps them all.
+    #[arg(long, default_value_t = 50)]
+    keep_runs: u64,
+    /// Log lines to keep per run, unless a project sets its own. 0 keeps them all.
+    #[arg(long, default_value_t = 50_000)]
+    max_log_lines: u64,
+    /// Days to keep a run after it last changed, unless a project sets its own.
+    /// 0 keeps runs regardless of age.
+    #[arg(long, default_value_t = 30)]
+    max_age_days: u64,
 }
 
 #[tokio::main]
@@ -20,6 +46,31 @@ async fn main() -> anyhow::Result<()> {
     let db = connect_sqlite(DATABASE_URL).await?;
     run_migrations(&db).await?;
     let state = create_app_state(db);
+    let orphaned = tasks::reconcile_orphaned_task_runs(
+        &state,
+        args.orphaned_processes == OrphanedProcesses::Adopt,
+    )
+    .await?;
+    if orphaned.interrupted > 0 {
+        println!(
+            "Marked {} task runs left over from a previous server as interrupted",
+            orphaned.interrupted
+        );
+    }
+    if orphaned.adopted > 0 {
+        println!(
+            "Adopted {} task runs still running from a previous server",
+            orphaned.adopted
+        );
+    }
+    retention::spawn_pruning(
+        state.clone(),
+        Retention {
+            keep_runs: Some(args.keep_runs),
+            max_log_lines: Some(args.max_log_lines),
+            max_age_days: Some(args.max_age_days),
+        },
+    );
     let app = create_router(state.clone());
 
     let address = format!("{}:{}", args.address, args.port);
@@ -32,8 +83,9 @@ async fn main() -> anyhow::Result<()> {
         .with_graceful_shutdown(async move {
             let _ = tokio::signal::ctrl_c().await;
             tasks::cancel_all_running_processes(&state).await;
-            // Give task runners a brief moment to propagate kills.
+            // The processes have exited; give task runners a brief moment to record it.
             tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            state.log_writer.flush().await;
         })
         .await?;
     Ok(())
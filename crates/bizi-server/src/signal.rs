@@ -0,0 +1,95 @@
+//! The signals a task can be stopped or signalled with, by name.
+
+use std::fmt;
+
+const SIGNAL_NAMES: &[&str] = &[
+    "SIGHUP", "SIGINT", "SIGQUIT", "SIGILL", "SIGTRAP", "SIGABRT", "SIGBUS", "SIGFPE", "SIGKILL",
+    "SIGUSR1", "SIGSEGV", "SIGUSR2", "SIGPIPE", "SIGALRM", "SIGTERM", "SIGCONT", "SIGSTOP",
+    "SIGTSTP", "SIGWINCH",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signal(&'static str);
+
+impl Signal {
+    pub const TERM: Signal = Signal("SIGTERM");
+    pub const KILL: Signal = Signal("SIGKILL");
+
+    /// Accepts names with or without the `SIG` prefix, in any case, so
+    /// `SIGINT`, `INT` and `int` are all the same signal.
+    pub fn parse(name: &str) -> Option<Signal> {
+        let name = name.trim().to_ascii_uppercase();
+        let name = name.strip_prefix("SIG").unwrap_or(&name);
+        SIGNAL_NAMES
+            .iter()
+            .find(|known| &known[3..] == name)
+            .map(|known| Signal(known))
+    }
+
+    /// The signal with this number on the current platform, if it is one
+    /// bizi knows by name.
+    #[cfg(unix)]
+    pub fn from_number(number: libc::c_int) -> Option<Signal> {
+        SIGNAL_NAMES
+            .iter()
+            .map(|name| Signal(name))
+            .find(|signal| signal.number() == number)
+    }
+
+    pub fn name(self) -> &'static str {
+        self.0
+    }
+
+    #[cfg(unix)]
+    pub fn number(self) -> libc::c_int {
+        match self.0 {
+            "SIGHUP" => libc::SIGHUP,
+            "SIGINT" => libc::SIGINT,
+            "SIGQUIT" => libc::SIGQUIT,
+            "SIGILL" => libc::SIGILL,
+            "SIGTRAP" => libc::SIGTRAP,
+            "SIGABRT" => libc::SIGABRT,
+            "SIGBUS" => libc::SIGBUS,
+            "SIGFPE" => libc::SIGFPE,
+            "SIGKILL" => libc::SIGKILL,
+            "SIGUSR1" => libc::SIGUSR1,
+            "SIGSEGV" => libc::SIGSEGV,
+            "SIGUSR2" => libc::SIGUSR2,
+            "SIGPIPE" => libc::SIGPIPE,
+            "SIGALRM" => libc::SIGALRM,
+            "SIGCONT" => libc::SIGCONT,
+            "SIGSTOP" => libc::SIGSTOP,
+            "SIGTSTP" => libc::SIGTSTP,
+            "SIGWINCH" => libc::SIGWINCH,
+            _ => libc::SIGTERM,
+        }
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_names_with_or_without_the_prefix() {
+        assert_eq!(Signal::parse("SIGINT"), Some(Signal("SIGINT")));
+        assert_eq!(Signal::parse("int"), Some(Signal("SIGINT")));
+        assert_eq!(Signal::parse(" sigterm "), Some(Signal::TERM));
+        assert_eq!(Signal::parse("SIGNOPE"), None);
+        assert_eq!(Signal::parse("SIG"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn maps_numbers_back_to_names() {
+        assert_eq!(Signal::from_number(libc::SIGKILL), Some(Signal::KILL));
+        assert_eq!(Signal::from_number(libc::SIGTERM), Some(Signal::TERM));
+        assert_eq!(Signal::from_number(0), None);
+    }
+}
@@ -1,5 +1,9 @@
 //! HTTP + WebSocket client for the bizi server. Mirrors `@getbizi/client`.
 
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
 use anyhow::{Context, Result, bail};
 use futures_util::{SinkExt, StreamExt};
 use tokio::sync::mpsc;
@@ -7,15 +11,21 @@ use tokio_tungstenite::tungstenite::Message;
 
 // Request and response shapes come from the crate the server defines its API
 // with, so this client cannot disagree with the server about the wire format.
-pub use bizi_api::TaskRunLogsStreamMessage;
 use bizi_api::{
-    CancelTaskRequest, GetTaskRunLogsResponse, GetTaskRunResponse, ListTaskRunsResponse,
-    ListTasksResponse, RestartTaskRequest, StartTaskRequest, StartTaskResponse, TaskMap,
-    TaskRunLogLine, TaskRunTreeNode,
+    CancelTaskRequest, ConfigDiagnostic, ExcludeTaskRequest, GetTaskRunLogsResponse,
+    GetTaskRunResponse, IncludeTaskRequest, ListTaskRunsResponse, ListTasksResponse,
+    RestartTaskRequest, SearchLogsResponse, SignalTaskRunRequest, SignalTaskRunResponse,
+    StartTaskRequest, StartTaskResponse, TaskMap, TaskRunInputStreamMessage, TaskRunTreeNode,
+    ValidateConfigResponse,
+};
+pub use bizi_api::{
+    GetTaskRunLogsRequest, GetTaskRunLogsResponseBody, LogSearchMatch, SearchLogsRequest,
+    SearchLogsResponseBody, TaskRunLogsStreamMessage,
 };
 
 pub const BIZI_API_PORT: u16 = 7436;
 pub const BIZI_API_HOST: &str = "localhost";
+const LOG_STREAM_RECONNECT_DELAY: Duration = Duration::from_secs(1);
 
 #[derive(Clone)]
 pub struct BiziApi {
@@ -71,6 +81,21 @@ impl BiziApi {
         Ok(body.tasks)
     }
 
+    pub async fn validate_config(&self, cwd: &str) -> Result<Vec<ConfigDiagnostic>> {
+        let response = self
+            .client
+            .get(self.url("/api/config/validate"))
+            .query(&[("cwd", cwd)])
+            .send()
+            .await
+            .context("failed to reach the bizi server")?;
+        let body = unwrap_response!(
+            read_json::<ValidateConfigResponse>(response).await?,
+            ValidateConfigResponse
+        );
+        Ok(body.diagnostics)
+    }
+
     pub async fn list_task_runs(&self, cwd: &str) -> Result<Vec<TaskRunTreeNode>> {
         let response = self
             .client
@@ -103,12 +128,12 @@ impl BiziApi {
     pub async fn get_task_run_logs(
         &self,
         run_id: &str,
-        include_children: bool,
-    ) -> Result<Vec<TaskRunLogLine>> {
+        request: &GetTaskRunLogsRequest,
+    ) -> Result<GetTaskRunLogsResponseBody> {
         let response = self
             .client
             .get(self.url(&format!("/api/tasks/{}/logs", encode_path(run_id))))
-            .query(&[("includeChildren", include_children.to_string())])
+            .query(request)
             .send()
             .await
             .context("failed to reach the bizi server")?;
@@ -116,7 +141,22 @@ impl BiziApi {
             read_json::<GetTaskRunLogsResponse>(response).await?,
             GetTaskRunLogsResponse
         );
-        Ok(body.logs)
+        Ok(body)
+    }
+
+    pub async fn search_logs(&self, request: &SearchLogsRequest) -> Result<SearchLogsResponseBody> {
+        let response = self
+            .client
+            .get(self.url("/api/logs/search"))
+            .query(request)
+            .send()
+            .await
+            .context("failed to reach the bizi server")?;
+        let body = unwrap_response!(
+            read_json::<SearchLogsResponse>(response).await?,
+            SearchLogsResponse
+        );
+        Ok(body)
     }
 
     pub async fn run_task(
@@ -124,6 +164,7 @@ impl BiziApi {
         task: &str,
         cwd: &str,
         include_tasks: Option<Vec<String>>,
+        timeout: Option<Duration>,
     ) -> Result<String> {
         let response = self
             .client
@@ -132,6 +173,8 @@ impl BiziApi {
                 task: task.to_string(),
                 cwd: cwd.to_string(),
                 include_tasks,
+                env: None,
+                timeout_ms: timeout.map(|timeout| timeout.as_millis() as u64),
             })
             .send()
             .await
@@ -156,6 +199,24 @@ impl BiziApi {
         ensure_ok(response).await
     }
 
+    /// Sends `signal` to the run's process group, returning its full name.
+    pub async fn signal_task_run(&self, run_id: &str, signal: &str) -> Result<String> {
+        let response = self
+            .client
+            .post(self.url(&format!("/api/tasks/{}/signal", encode_path(run_id))))
+            .json(&SignalTaskRunRequest {
+                signal: signal.to_string(),
+            })
+            .send()
+            .await
+            .context("failed to reach the bizi server")?;
+        let body = unwrap_response!(
+            read_json::<SignalTaskRunResponse>(response).await?,
+            SignalTaskRunResponse
+        );
+        Ok(body.signal)
+    }
+
     pub async fn restart_task(&self, run_id: &str) -> Result<()> {
         let response = self
             .client
@@ -169,6 +230,34 @@ impl BiziApi {
         ensure_ok(response).await
     }
 
+    /// Starts the optional subtask `task` under the run `run_id`, its parent.
+    pub async fn include_task(&self, run_id: &str, task: &str) -> Result<()> {
+        let response = self
+            .client
+            .post(self.url(&format!("/api/tasks/{}/include", encode_path(run_id))))
+            .json(&IncludeTaskRequest {
+                task: task.to_string(),
+            })
+            .send()
+            .await
+            .context("failed to reach the bizi server")?;
+        ensure_ok(response).await
+    }
+
+    /// Cancels the optional subtask `task` under the run `run_id`, its parent.
+    pub async fn exclude_task(&self, run_id: &str, task: &str) -> Result<()> {
+        let response = self
+            .client
+            .post(self.url(&format!("/api/tasks/{}/exclude", encode_path(run_id))))
+            .json(&ExcludeTaskRequest {
+                task: task.to_string(),
+            })
+            .send()
+            .await
+            .context("failed to reach the bizi server")?;
+        ensure_ok(response).await
+    }
+
     /// Streams task run updates until the socket closes or the receiver goes
     /// away. Callers own the lifetime by spawning (and aborting) this future.
     pub async fn stream_task_run<T, F>(&self, run_id: &str, sender: mpsc::Sender<T>, wrap: F)
@@ -186,29 +275,120 @@ impl BiziApi {
         .await;
     }
 
-    /// Streams log snapshots and appended lines for a run.
+    /// Streams log snapshots and appended lines for a run. With `tail`, the
+    /// snapshot holds only that many of the newest lines. A dropped connection
+    /// is re-opened from the last line received, so nothing is missed or sent
+    /// twice; only an error from the server ends the stream.
     pub async fn stream_task_logs<T, F>(
         &self,
         run_id: &str,
         include_children: bool,
+        tail: Option<u64>,
         sender: mpsc::Sender<T>,
         wrap: F,
     ) where
         T: Send + 'static,
-        F: Fn(TaskRunLogsStreamMessage) -> T + Send + 'static,
+        F: Fn(TaskRunLogsStreamMessage) -> T + Send + Sync + 'static,
     {
-        let query = if include_children {
-            "?includeChildren=true"
-        } else {
-            ""
-        };
-        let url = self.ws_url(&format!("/api/tasks/{}/logs{query}", encode_path(run_id)));
-        stream_json(url, sender, move |text| {
-            serde_json::from_str::<TaskRunLogsStreamMessage>(&text)
-                .ok()
-                .map(&wrap)
-        })
-        .await;
+        let wrap = Arc::new(wrap);
+        // Sequences start at 1, so 0 means no line has arrived yet.
+        let last_sequence = Arc::new(AtomicU64::new(0));
+        let ended = Arc::new(AtomicBool::new(false));
+
+        loop {
+            let mut query = Vec::new();
+            if include_children {
+                query.push("includeChildren=true".to_string());
+            }
+            match last_sequence.load(Ordering::Relaxed) {
+                0 => {
+                    if let Some(tail) = tail {
+                        query.push(format!("tail={tail}"));
+                    }
+                }
+                last_sequence => query.push(format!("fromSequence={last_sequence}")),
+            }
+            let query = if query.is_empty() {
+                String::new()
+            } else {
+                format!("?{}", query.join("&"))
+            };
+            let url = self.ws_url(&format!("/api/tasks/{}/logs{query}", encode_path(run_id)));
+
+            stream_json(url, sender.clone(), {
+                let wrap = wrap.clone();
+                let last_sequence = last_sequence.clone();
+                let ended = ended.clone();
+                move |text| {
+                    let message = serde_json::from_str::<TaskRunLogsStreamMessage>(&text).ok()?;
+                    match &message {
+                        TaskRunLogsStreamMessage::Snapshot { logs, .. } => {
+                            if let Some(log) = logs.last() {
+                                last_sequence.store(log.sequence, Ordering::Relaxed);
+                            }
+                        }
+                        TaskRunLogsStreamMessage::Log { log } => {
+                            last_sequence.store(log.sequence, Ordering::Relaxed);
+                        }
+                        TaskRunLogsStreamMessage::Error { .. } => {
+                            ended.store(true, Ordering::Relaxed)
+                        }
+                    }
+                    Some(wrap(message))
+                }
+            })
+            .await;
+
+            if sender.is_closed() || ended.load(Ordering::Relaxed) {
+                break;
+            }
+            tokio::time::sleep(LOG_STREAM_RECONNECT_DELAY).await;
+        }
+    }
+
+    /// Writes each string from `input` to the run's stdin, or types it at its
+    /// terminal, in order over one websocket, until `input` closes. Fails with
+    /// the server's message once it stops taking input.
+    pub async fn stream_task_run_input(
+        &self,
+        run_id: &str,
+        mut input: mpsc::UnboundedReceiver<String>,
+    ) -> Result<()> {
+        let url = self.ws_url(&format!("/api/tasks/{}/input", encode_path(run_id)));
+        let (mut socket, _) = tokio_tungstenite::connect_async(&url)
+            .await
+            .context("failed to reach the bizi server")?;
+
+        loop {
+            tokio::select! {
+                data = input.recv() => {
+                    let Some(data) = data else {
+                        let _ = socket.close(None).await;
+                        return Ok(());
+                    };
+                    socket
+                        .send(Message::Text(data))
+                        .await
+                        .context("lost the connection to the bizi server")?;
+                }
+                message = socket.next() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        if let Ok(TaskRunInputStreamMessage::Error { message }) =
+                            serde_json::from_str(&text)
+                        {
+                            bail!("{message}");
+                        }
+                    }
+                    Some(Ok(Message::Ping(payload))) => {
+                        let _ = socket.send(Message::Pong(payload)).await;
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
+                        bail!("the task stopped taking input");
+                    }
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
     }
 }
 
@@ -315,7 +495,7 @@ mod tests {
         let legacy_snapshot: TaskRunLogsStreamMessage =
             serde_json::from_str(r#"{"type":"snapshot","run_id":"r1","logs":[]}"#).unwrap();
         match legacy_snapshot {
-            TaskRunLogsStreamMessage::Snapshot { run_id, logs } => {
+            TaskRunLogsStreamMessage::Snapshot { run_id, logs, .. } => {
                 assert_eq!(run_id, "r1");
                 assert!(logs.is_empty());
             }
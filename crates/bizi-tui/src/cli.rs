@@ -1,9 +1,13 @@
 //! Argument parsing. Mirrors `lib/args.ts` and `commands/cli.ts`, including the
 //! implicit `bizi <task>` → `bizi run <task>` rewrite.
 
+use std::time::Duration;
+
 use clap::{Parser, Subcommand};
 
-const RESERVED_SUBCOMMANDS: [&str; 4] = ["run", "cancel", "stat", "init"];
+const RESERVED_SUBCOMMANDS: [&str; 7] = [
+    "run", "cancel", "stat", "init", "validate", "search", "signal",
+];
 
 #[derive(Debug, Clone)]
 pub struct CliOptions {
@@ -33,9 +37,18 @@ pub enum CliCommand {
         /// Disable prompts and only cancel on exit when this session started the run
         #[arg(long = "non-interactive", default_value_t = false)]
         non_interactive: bool,
+        /// Fail whatever is still running after this long, like 30s, 15m or 2h
+        #[arg(long, value_parser = parse_duration)]
+        timeout: Option<Duration>,
     },
     /// Cancel a task
     Cancel { task: String },
+    /// Send a signal to a running task and its subtasks
+    Signal {
+        task: String,
+        /// The signal to send, like SIGHUP, HUP or usr1
+        signal: String,
+    },
     /// Show task status
     Stat {
         task: String,
@@ -45,6 +58,25 @@ pub enum CliCommand {
     },
     /// Create a starter task.config.json
     Init,
+    /// Check task.config.json for mistakes
+    Validate,
+    /// Search the logs of every run in the project
+    Search {
+        /// Text to find anywhere in a line, ignoring case
+        query: String,
+        /// Only search lines written by this task
+        #[arg(long)]
+        task: Option<String>,
+        /// Only search lines written within this long, like 30s, 15m, 2h or 7d
+        #[arg(long, value_parser = parse_duration)]
+        since: Option<Duration>,
+        /// Show at most this many matches, the newest ones
+        #[arg(long)]
+        limit: Option<u64>,
+        /// Print machine-readable JSON output
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
 }
 
 pub enum CliMode {
@@ -76,6 +108,25 @@ pub fn resolve_cli_mode(argv: &[String]) -> Result<CliMode, clap::Error> {
     }
 }
 
+/// Parses a count followed by `s`, `m`, `h` or `d`.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let split_at = value
+        .find(|character: char| !character.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (count, unit) = value.split_at(split_at);
+    let count: u64 = count
+        .parse()
+        .map_err(|_| format!("expected a number followed by s, m, h or d, got \"{value}\""))?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(format!("unknown unit \"{unit}\", expected s, m, h or d")),
+    };
+    Ok(Duration::from_secs(count.saturating_mul(seconds)))
+}
+
 fn normalize_implicit_run_command(argv: &[String]) -> (Vec<String>, bool) {
     let Some(first_positional_index) = find_first_positional_token_index(argv) else {
         return (argv.to_vec(), false);
@@ -163,6 +214,10 @@ mod tests {
             normalize_implicit_run_command(&argv(&["-C", "/tmp", "dev:api"]));
         assert_eq!(normalized, argv(&["-C", "/tmp", "run", "dev:api"]));
         assert!(implicit);
+
+        let (normalized, implicit) = normalize_implicit_run_command(&argv(&["check"]));
+        assert_eq!(normalized, argv(&["run", "check"]));
+        assert!(implicit);
     }
 
     #[test]
@@ -191,6 +246,25 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn parses_search_durations() {
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("7d"), Ok(Duration::from_secs(604_800)));
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("10").is_err());
+        assert!(parse_duration("10w").is_err());
+
+        match resolve_cli_mode(&argv(&["search", "EADDRINUSE", "--since", "1h"])).unwrap() {
+            CliMode::Command(CliCommand::Search { query, since, .. }, _, implicit) => {
+                assert_eq!(query, "EADDRINUSE");
+                assert_eq!(since, Some(Duration::from_secs(3600)));
+                assert!(!implicit);
+            }
+            _ => panic!("expected a search command"),
+        }
+    }
+
     #[test]
     fn rejects_unknown_options() {
         assert!(resolve_cli_mode(&argv(&["--nope"])).is_err());
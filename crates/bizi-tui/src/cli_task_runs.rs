@@ -18,7 +18,7 @@ fn visit<'a>(node: &'a TaskRunTreeNode, flattened: &mut Vec<&'a TaskRunTreeNode>
 }
 
 fn newest_first(mut runs: Vec<&TaskRunTreeNode>) -> Vec<&TaskRunTreeNode> {
-    runs.sort_by(|left, right| right.updated_at.cmp(&left.updated_at));
+    runs.sort_by_key(|run| std::cmp::Reverse(run.updated_at));
     runs
 }
 
@@ -101,10 +101,23 @@ mod tests {
             id: id.to_string(),
             task: task.to_string(),
             cwd: "/tmp".to_string(),
+            working_dir: None,
             parent_run_id: None,
+            started_by_run_id: None,
             status,
+            ready: false,
             updated_at,
             waiting_on: None,
+            blocked_by: None,
+            exit_code: None,
+            signal: None,
+            pid: None,
+            started_at: None,
+            finished_at: None,
+            include_tasks: Vec::new(),
+            attempt: 1,
+            restarts: 0,
+            timed_out_after_ms: None,
             children: Vec::new(),
         }
     }
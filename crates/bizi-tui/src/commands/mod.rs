@@ -1,4 +1,7 @@
 pub mod cancel;
 pub mod init;
 pub mod run;
+pub mod search;
+pub mod signal;
 pub mod stat;
+pub mod validate;
@@ -10,12 +10,14 @@ use anyhow::{Result, anyhow};
 use tokio::sync::mpsc;
 use tokio::time::{Duration, sleep};
 
-use crate::api::{BiziApi, TaskRunLogsStreamMessage};
+use crate::api::{BiziApi, GetTaskRunLogsRequest, TaskRunLogsStreamMessage};
 use crate::cli_task_runs::find_active_run_by_task_key;
-use crate::model::{TaskRunLogLine, TaskRunTreeNode};
+use crate::model::{TaskRunLogLine, TaskRunStatus, TaskRunTreeNode};
 use crate::prompt::{self, PromptResult};
 
 const SIGNAL_EXIT_CODE: i32 = 130;
+// What `timeout(1)` exits with, which CI scripts already check for.
+const TIMEOUT_EXIT_CODE: i32 = 124;
 const CANCEL_FALLBACK_MS: u64 = 3000;
 const STATUS_POLL_MS: u64 = 500;
 const SETTLE_ATTEMPTS: usize = 4;
@@ -23,7 +25,8 @@ const SETTLE_DELAY_MS: u64 = 180;
 
 enum RunEvent {
     Log(TaskRunLogsStreamMessage),
-    RunUpdate(TaskRunTreeNode),
+    // Boxed because a snapshot dwarfs every other event.
+    RunUpdate(Box<TaskRunTreeNode>),
     PollTick,
     Signal,
     CancelFallback,
@@ -41,6 +44,7 @@ pub async fn run_command(
     cwd: &str,
     task: &str,
     non_interactive: bool,
+    timeout: Option<Duration>,
 ) -> Result<i32> {
     let interactive = prompt::is_interactive() && !non_interactive;
 
@@ -49,10 +53,13 @@ pub async fn run_command(
         find_active_run_by_task_key(&before_task_runs, task).map(|run| run.id.clone());
 
     let run_id = api
-        .run_task(task, cwd, None)
+        .run_task(task, cwd, None, timeout)
         .await
         .map_err(|_| anyhow!("failed to start task \"{task}\""))?;
     let started_by_session = active_before_run_id.as_deref() != Some(run_id.as_str());
+    if timeout.is_some() && !started_by_session {
+        eprintln!("Task \"{task}\" was already running, so --timeout does not apply to it.");
+    }
 
     let (tx, mut rx) = mpsc::channel::<RunEvent>(1024);
     let mut tasks = Vec::new();
@@ -62,7 +69,8 @@ pub async fn run_command(
         let run_id = run_id.clone();
         let tx = tx.clone();
         async move {
-            api.stream_task_logs(&run_id, true, tx, RunEvent::Log).await;
+            api.stream_task_logs(&run_id, true, None, tx, RunEvent::Log)
+                .await;
         }
     }));
 
@@ -71,7 +79,10 @@ pub async fn run_command(
         let run_id = run_id.clone();
         let tx = tx.clone();
         async move {
-            api.stream_task_run(&run_id, tx, RunEvent::RunUpdate).await;
+            api.stream_task_run(&run_id, tx, |snapshot| {
+                RunEvent::RunUpdate(Box::new(snapshot))
+            })
+            .await;
         }
     }));
 
@@ -113,7 +124,7 @@ pub async fn run_command(
                 TaskRunLogsStreamMessage::Error { message } => eprintln!("{message}"),
             },
             RunEvent::RunUpdate(snapshot) => {
-                if let Some(code) = session.finalize_when_task_tree_settles(snapshot).await {
+                if let Some(code) = session.finalize_when_task_tree_settles(*snapshot).await {
                     exit_code = Some(code);
                     break;
                 }
@@ -176,7 +187,7 @@ impl RunSession<'_> {
         self.flush_run_logs_with_retries().await;
         Some(
             self.signal_exit_code
-                .unwrap_or_else(|| settled_snapshot.status.exit_code()),
+                .unwrap_or_else(|| task_tree_exit_code(&settled_snapshot)),
         )
     }
 
@@ -210,8 +221,12 @@ impl RunSession<'_> {
     async fn flush_run_logs_with_retries(&mut self) {
         // Give logs a brief chance to catch up in storage after the task tree settles.
         for attempt in 0..SETTLE_ATTEMPTS {
-            if let Ok(logs) = self.api.get_task_run_logs(&self.run_id, true).await {
-                for log in logs {
+            let request = GetTaskRunLogsRequest {
+                include_children: Some(true),
+                ..GetTaskRunLogsRequest::default()
+            };
+            if let Ok(body) = self.api.get_task_run_logs(&self.run_id, &request).await {
+                for log in body.logs {
                     self.emit_log(&log);
                 }
             }
@@ -252,6 +267,30 @@ impl RunSession<'_> {
     }
 }
 
+/// The root's own outcome, unless a run below it failed, was blocked or was
+/// interrupted. A cancelled child is not a failure on its own: optional
+/// subtasks that were not included start out cancelled. A run anywhere in the
+/// tree that timed out makes it 124.
+fn task_tree_exit_code(root: &TaskRunTreeNode) -> i32 {
+    if has_timed_out_runs_in_task_tree(root) {
+        return TIMEOUT_EXIT_CODE;
+    }
+
+    fn has_failed_descendant(run: &TaskRunTreeNode) -> bool {
+        run.children.iter().any(|child| {
+            matches!(
+                child.status,
+                TaskRunStatus::Failed | TaskRunStatus::Blocked | TaskRunStatus::Interrupted
+            ) || has_failed_descendant(child)
+        })
+    }
+
+    if has_failed_descendant(root) {
+        return 1;
+    }
+    root.status.exit_code()
+}
+
 fn spawn_cancel_fallback(tx: mpsc::Sender<RunEvent>) {
     tokio::spawn(async move {
         sleep(Duration::from_millis(CANCEL_FALLBACK_MS)).await;
@@ -293,6 +332,10 @@ fn has_active_runs_in_task_tree(run: &TaskRunTreeNode) -> bool {
     run.children.iter().any(has_active_runs_in_task_tree)
 }
 
+fn has_timed_out_runs_in_task_tree(run: &TaskRunTreeNode) -> bool {
+    run.timed_out_after_ms.is_some() || run.children.iter().any(has_timed_out_runs_in_task_tree)
+}
+
 #[cfg(unix)]
 async fn forward_signals(tx: mpsc::Sender<RunEvent>) {
     use tokio::signal::unix::{SignalKind, signal};
@@ -330,17 +373,29 @@ async fn forward_signals(tx: mpsc::Sender<RunEvent>) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::TaskRunStatus;
 
     fn run(status: TaskRunStatus, children: Vec<TaskRunTreeNode>) -> TaskRunTreeNode {
         TaskRunTreeNode {
             id: "run".to_string(),
             task: "dev".to_string(),
             cwd: "/tmp".to_string(),
+            working_dir: None,
             parent_run_id: None,
+            started_by_run_id: None,
             status,
+            ready: false,
             updated_at: 0,
             waiting_on: None,
+            blocked_by: None,
+            exit_code: None,
+            signal: None,
+            pid: None,
+            started_at: None,
+            finished_at: None,
+            include_tasks: Vec::new(),
+            attempt: 1,
+            restarts: 0,
+            timed_out_after_ms: None,
             children,
         }
     }
@@ -365,5 +420,29 @@ mod tests {
         assert_eq!(TaskRunStatus::Success.exit_code(), 0);
         assert_eq!(TaskRunStatus::Failed.exit_code(), 1);
         assert_eq!(TaskRunStatus::Cancelled.exit_code(), 1);
+        assert_eq!(TaskRunStatus::Blocked.exit_code(), 1);
+    }
+
+    #[test]
+    fn fails_when_a_child_failed_or_was_blocked_but_not_when_one_was_skipped() {
+        let blocked = run(
+            TaskRunStatus::Success,
+            vec![
+                run(TaskRunStatus::Success, Vec::new()),
+                run(TaskRunStatus::Blocked, Vec::new()),
+            ],
+        );
+        assert_eq!(task_tree_exit_code(&blocked), 1);
+
+        let optional_skipped = run(
+            TaskRunStatus::Success,
+            vec![run(TaskRunStatus::Cancelled, Vec::new())],
+        );
+        assert_eq!(task_tree_exit_code(&optional_skipped), 0);
+
+        let mut timed_out_child = run(TaskRunStatus::Failed, Vec::new());
+        timed_out_child.timed_out_after_ms = Some(600_000);
+        let timed_out = run(TaskRunStatus::Success, vec![timed_out_child]);
+        assert_eq!(task_tree_exit_code(&timed_out), TIMEOUT_EXIT_CODE);
     }
 }
@@ -0,0 +1,77 @@
+//! `bizi search`: finds log lines across every run of the project.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{Local, TimeZone};
+
+use crate::api::{BiziApi, LogSearchMatch, SearchLogsRequest};
+use crate::prompt::{blue, dim, paint};
+
+pub struct SearchOptions {
+    pub query: String,
+    pub task: Option<String>,
+    pub since: Option<Duration>,
+    pub limit: Option<u64>,
+    pub json: bool,
+}
+
+/// Prints matches oldest first, so the newest ends up next to the prompt.
+/// Like `grep`, exits with 1 when nothing matched.
+pub async fn search_command(api: &BiziApi, cwd: &str, options: SearchOptions) -> Result<i32> {
+    let since = options
+        .since
+        .map(|since| chrono::Utc::now().timestamp_millis() - since.as_millis() as i64);
+    let request = SearchLogsRequest {
+        cwd: cwd.to_string(),
+        q: options.query,
+        task: options.task,
+        since,
+        limit: options.limit,
+    };
+    let body = api.search_logs(&request).await?;
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&body)?);
+    } else {
+        for found in body.matches.iter().rev() {
+            println!("{}", format_match(found));
+        }
+        if body.has_more {
+            println!(
+                "{}",
+                dim("Older matches were left out; narrow the search or raise --limit.")
+            );
+        }
+    }
+    Ok(if body.matches.is_empty() { 1 } else { 0 })
+}
+
+fn format_match(found: &LogSearchMatch) -> String {
+    let timestamp = match Local.timestamp_millis_opt(found.timestamp).single() {
+        Some(date) => date.format("%Y-%m-%d %H:%M:%S").to_string(),
+        None => "---------- --:--:--".to_string(),
+    };
+    format!(
+        "{} {} {} {}",
+        dim(&timestamp),
+        blue(&found.task),
+        highlight_snippet(&found.snippet),
+        dim(&format!("({} #{})", found.run_id, found.sequence))
+    )
+}
+
+/// Renders the server's `**match**` markers in bold.
+fn highlight_snippet(snippet: &str) -> String {
+    snippet
+        .split("**")
+        .enumerate()
+        .map(|(index, part)| {
+            if index % 2 == 1 {
+                paint("1", part)
+            } else {
+                part.to_string()
+            }
+        })
+        .collect()
+}
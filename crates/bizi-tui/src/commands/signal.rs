@@ -0,0 +1,55 @@
+//! `bizi signal`: sends a signal to every process running for a task and its
+//! subtasks.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use futures_util::future::join_all;
+
+use crate::api::BiziApi;
+use crate::cli_task_runs::find_active_runs_in_task_subtree;
+use crate::model::TaskRunStatus;
+
+pub async fn signal_command(api: &BiziApi, cwd: &str, task: &str, signal: &str) -> Result<i32> {
+    let task_runs = api.list_task_runs(cwd).await?;
+
+    // Queued runs and parents without a command have no process to signal.
+    let mut seen_run_ids = HashSet::new();
+    let running_runs: Vec<_> = find_active_runs_in_task_subtree(&task_runs, task)
+        .into_iter()
+        .filter(|run| run.status == TaskRunStatus::Running && run.pid.is_some())
+        .filter(|run| seen_run_ids.insert(run.id.clone()))
+        .collect();
+
+    if running_runs.is_empty() {
+        eprintln!("No running processes found for task \"{task}\" or its subtasks.");
+        return Ok(1);
+    }
+
+    let outcomes =
+        join_all(running_runs.iter().map(|run| async move {
+            (run.task.clone(), api.signal_task_run(&run.id, signal).await)
+        }))
+        .await;
+
+    let mut sent_as = None;
+    let mut failed = 0;
+    for (run_task, outcome) in &outcomes {
+        match outcome {
+            Ok(name) => sent_as = Some(name.clone()),
+            Err(err) => {
+                eprintln!("Failed to signal \"{run_task}\": {err}");
+                failed += 1;
+            }
+        }
+    }
+
+    if let Some(name) = sent_as {
+        println!(
+            "Sent {name} to {}/{} run(s) for \"{task}\" and its subtasks.",
+            outcomes.len() - failed,
+            outcomes.len()
+        );
+    }
+    Ok(if failed > 0 { 1 } else { 0 })
+}
@@ -7,7 +7,7 @@ use crate::api::BiziApi;
 use crate::cli_task_runs::{find_active_run_in_task_subtree, find_latest_run_in_task_subtree};
 use crate::model::{TaskMap, TaskRunTreeNode};
 use crate::prompt::{blue, dim, green, red, yellow};
-use crate::status::task_status_display;
+use crate::status::{describe_run_outcome, task_status_display};
 use crate::task_runs::{
     DisplayStatusByTaskKey, build_display_status_by_task_key, index_runs_by_task_key,
 };
@@ -19,6 +19,14 @@ struct RunSummary {
     task: String,
     status: String,
     updated_at: i64,
+    exit_code: Option<i32>,
+    signal: Option<String>,
+    started_at: Option<i64>,
+    finished_at: Option<i64>,
+    /// How many times the task's `restart` policy has restarted the run.
+    restarts: u32,
+    /// Like `exited 137 (SIGKILL) after 4m 12s`, once the process has exited.
+    outcome: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -79,6 +87,12 @@ fn summarize_run(run: &TaskRunTreeNode) -> RunSummary {
         task: run.task.clone(),
         status: run.status.as_str().to_string(),
         updated_at: run.updated_at,
+        exit_code: run.exit_code,
+        signal: run.signal.clone(),
+        started_at: run.started_at,
+        finished_at: run.finished_at,
+        restarts: run.restarts,
+        outcome: describe_run_outcome(run),
     }
 }
 
@@ -178,7 +192,7 @@ fn colorize_status_icon(status: Option<&str>, icon: &str) -> String {
         Some("Queued") | Some("Indeterminate") => yellow(icon),
         Some("Running") => green(icon),
         Some("Success") => blue(icon),
-        Some("Failed") => red(icon),
+        Some("Failed") | Some("Blocked") | Some("Interrupted") => red(icon),
         Some(_) => icon.to_string(),
     }
 }
@@ -0,0 +1,81 @@
+//! `bizi validate`: prints the server's diagnostics for `task.config.json` and
+//! fails when any of them is an error, so it can gate a pre-commit hook.
+
+use anyhow::Result;
+use bizi_api::{ConfigDiagnostic, DiagnosticSeverity};
+
+use crate::api::BiziApi;
+use crate::prompt::{dim, green, red, yellow};
+
+pub async fn validate_command(api: &BiziApi, cwd: &str) -> Result<i32> {
+    let diagnostics = api.validate_config(cwd).await?;
+
+    if diagnostics.is_empty() {
+        println!("{} task.config.json is valid.", green("✓"));
+        return Ok(0);
+    }
+
+    for diagnostic in &diagnostics {
+        println!("{}", format_diagnostic(diagnostic));
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+    println!();
+    println!("{}", summarize(errors, warnings));
+
+    Ok(if errors > 0 { 1 } else { 0 })
+}
+
+fn format_diagnostic(diagnostic: &ConfigDiagnostic) -> String {
+    let severity = match diagnostic.severity {
+        DiagnosticSeverity::Error => red("error"),
+        DiagnosticSeverity::Warning => yellow("warning"),
+    };
+    format!(
+        "task.config.json:{}:{}: {severity}: {} {}",
+        diagnostic.line,
+        diagnostic.column,
+        diagnostic.message,
+        dim(&format!("({})", diagnostic.path))
+    )
+}
+
+fn summarize(errors: usize, warnings: usize) -> String {
+    let plural = |count: usize, noun: &str| {
+        if count == 1 {
+            format!("1 {noun}")
+        } else {
+            format!("{count} {noun}s")
+        }
+    };
+    format!(
+        "{}, {}",
+        plural(errors, "error"),
+        plural(warnings, "warning")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_diagnostics_like_a_compiler() {
+        let line = format_diagnostic(&ConfigDiagnostic {
+            severity: DiagnosticSeverity::Error,
+            path: "$.tasks.dev.command".to_string(),
+            line: 4,
+            column: 18,
+            message: "Command is empty".to_string(),
+        });
+        assert_eq!(
+            line,
+            "task.config.json:4:18: error: Command is empty ($.tasks.dev.command)"
+        );
+        assert_eq!(summarize(1, 2), "1 error, 2 warnings");
+    }
+}
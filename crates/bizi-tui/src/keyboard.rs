@@ -29,6 +29,49 @@ pub fn is_jump_parents_backward_shortcut(key: &KeyEvent, is_macos: bool) -> bool
     has_jump_modifier(key, is_macos) && is_up_key(key)
 }
 
+/// Ctrl+], which leaves input mode for a task under a terminal, where Esc has
+/// to reach the task. Terminals without enhanced key reporting send it as the
+/// same byte as Ctrl+5, and crossterm reports it that way.
+pub fn is_leave_terminal_input_shortcut(key: &KeyEvent) -> bool {
+    key.modifiers.contains(KeyModifiers::CONTROL)
+        && matches!(key.code, KeyCode::Char(']') | KeyCode::Char('5'))
+}
+
+/// What a terminal sends for `key`, for typing into a task that runs under
+/// one. `None` for keys that send nothing.
+pub fn terminal_input(key: &KeyEvent) -> Option<String> {
+    let sequence = match key.code {
+        KeyCode::Char(character) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            if !character.is_ascii_alphabetic() {
+                return None;
+            }
+            // Ctrl+A is 0x01 through Ctrl+Z at 0x1a.
+            (((character.to_ascii_lowercase() as u8) & 0x1f) as char).to_string()
+        }
+        KeyCode::Char(character) => character.to_string(),
+        KeyCode::Enter => "\r".to_string(),
+        KeyCode::Tab => "\t".to_string(),
+        KeyCode::BackTab => "\x1b[Z".to_string(),
+        KeyCode::Backspace => "\x7f".to_string(),
+        KeyCode::Esc => "\x1b".to_string(),
+        KeyCode::Up => "\x1b[A".to_string(),
+        KeyCode::Down => "\x1b[B".to_string(),
+        KeyCode::Right => "\x1b[C".to_string(),
+        KeyCode::Left => "\x1b[D".to_string(),
+        KeyCode::Home => "\x1b[H".to_string(),
+        KeyCode::End => "\x1b[F".to_string(),
+        KeyCode::Delete => "\x1b[3~".to_string(),
+        KeyCode::PageUp => "\x1b[5~".to_string(),
+        KeyCode::PageDown => "\x1b[6~".to_string(),
+        _ => return None,
+    };
+    // Alt is sent as a leading escape.
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        return Some(format!("\x1b{sequence}"));
+    }
+    Some(sequence)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,4 +104,50 @@ mod tests {
         assert!(!is_jump_parents_forward_shortcut(&plain, true));
         assert!(!is_jump_parents_forward_shortcut(&plain, false));
     }
+
+    #[test]
+    fn translates_keys_to_what_a_terminal_sends() {
+        let input = |code, modifiers| terminal_input(&key(code, modifiers));
+        assert_eq!(
+            input(KeyCode::Char('h'), KeyModifiers::NONE).as_deref(),
+            Some("h")
+        );
+        assert_eq!(
+            input(KeyCode::Char('C'), KeyModifiers::CONTROL).as_deref(),
+            Some("\x03")
+        );
+        assert_eq!(
+            input(KeyCode::Enter, KeyModifiers::NONE).as_deref(),
+            Some("\r")
+        );
+        assert_eq!(
+            input(KeyCode::Up, KeyModifiers::NONE).as_deref(),
+            Some("\x1b[A")
+        );
+        assert_eq!(
+            input(KeyCode::Char('b'), KeyModifiers::ALT).as_deref(),
+            Some("\x1bb")
+        );
+        assert_eq!(input(KeyCode::F(1), KeyModifiers::NONE), None);
+    }
+
+    #[test]
+    fn ctrl_bracket_leaves_terminal_input_but_esc_does_not() {
+        assert!(is_leave_terminal_input_shortcut(&key(
+            KeyCode::Char(']'),
+            KeyModifiers::CONTROL
+        )));
+        assert!(is_leave_terminal_input_shortcut(&key(
+            KeyCode::Char('5'),
+            KeyModifiers::CONTROL
+        )));
+        assert!(!is_leave_terminal_input_shortcut(&key(
+            KeyCode::Char(']'),
+            KeyModifiers::NONE
+        )));
+        assert!(!is_leave_terminal_input_shortcut(&key(
+            KeyCode::Esc,
+            KeyModifiers::NONE
+        )));
+    }
 }
@@ -58,14 +58,40 @@ async fn dispatch(mode: CliMode) -> i32 {
                 CliCommand::Run {
                     task,
                     non_interactive,
-                } => commands::run::run_command(&api, &options.cwd, &task, non_interactive).await,
+                    timeout,
+                } => {
+                    commands::run::run_command(&api, &options.cwd, &task, non_interactive, timeout)
+                        .await
+                }
                 CliCommand::Cancel { task } => {
                     commands::cancel::cancel_command(&api, &options.cwd, &task).await
                 }
+                CliCommand::Signal { task, signal } => {
+                    commands::signal::signal_command(&api, &options.cwd, &task, &signal).await
+                }
                 CliCommand::Stat { task, json } => {
                     commands::stat::stat_command(&api, &options.cwd, &task, json).await
                 }
                 CliCommand::Init => commands::init::init_command(&options.cwd).await,
+                CliCommand::Validate => {
+                    commands::validate::validate_command(&api, &options.cwd).await
+                }
+                CliCommand::Search {
+                    query,
+                    task,
+                    since,
+                    limit,
+                    json,
+                } => {
+                    let request = commands::search::SearchOptions {
+                        query,
+                        task,
+                        since,
+                        limit,
+                        json,
+                    };
+                    commands::search::search_command(&api, &options.cwd, request).await
+                }
             };
 
             match result {
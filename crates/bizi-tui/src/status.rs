@@ -1,6 +1,7 @@
 use ratatui::style::Color;
 
-use crate::model::{DisplayTaskStatus, TaskRunStatus};
+use crate::logs::format_elapsed_duration;
+use crate::model::{DisplayTaskStatus, TaskRunStatus, TaskRunTreeNode};
 
 pub struct TaskStatusDisplay {
     pub color: Color,
@@ -34,6 +35,14 @@ pub fn task_status_display(status: Option<DisplayTaskStatus>) -> TaskStatusDispl
             color: hex_color(0xff, 0x3b, 0x30),
             icon: "✖",
         },
+        Some(DisplayTaskStatus::Run(TaskRunStatus::Blocked)) => TaskStatusDisplay {
+            color: hex_color(0xff, 0x9f, 0x0a),
+            icon: "⊘",
+        },
+        Some(DisplayTaskStatus::Run(TaskRunStatus::Interrupted)) => TaskStatusDisplay {
+            color: hex_color(0xff, 0x9f, 0x0a),
+            icon: "↯",
+        },
         Some(DisplayTaskStatus::Run(TaskRunStatus::Cancelled)) => TaskStatusDisplay {
             color: hex_color(0x77, 0x77, 0x77),
             icon: "■",
@@ -77,6 +86,43 @@ pub fn parse_color(value: &str) -> Option<Color> {
     }
 }
 
+/// How the run's process exited, like `137 (SIGKILL)`, once it has.
+pub fn exit_detail(run: &TaskRunTreeNode) -> Option<String> {
+    let exit_code = run.exit_code?;
+    Some(match run.signal.as_deref() {
+        Some(signal) => format!("{exit_code} ({signal})"),
+        None => exit_code.to_string(),
+    })
+}
+
+/// How long the run's latest process ran for, once it has exited.
+pub fn process_duration_ms(run: &TaskRunTreeNode) -> Option<i64> {
+    Some(run.finished_at? - run.started_at?)
+}
+
+/// A finished run's outcome in words, like `exited 137 (SIGKILL) after 4m 12s`,
+/// or `timed out after 10m 0s, exited 143 (SIGTERM)`.
+pub fn describe_run_outcome(run: &TaskRunTreeNode) -> Option<String> {
+    if let Some(timeout_ms) = run.timed_out_after_ms {
+        let timed_out = format!(
+            "timed out after {}",
+            format_elapsed_duration(timeout_ms as i64)
+        );
+        return Some(match exit_detail(run) {
+            Some(exit) => format!("{timed_out}, exited {exit}"),
+            None => timed_out,
+        });
+    }
+    let exit = exit_detail(run)?;
+    Some(match process_duration_ms(run) {
+        Some(duration_ms) => format!(
+            "exited {exit} after {}",
+            format_elapsed_duration(duration_ms)
+        ),
+        None => format!("exited {exit}"),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -101,4 +147,34 @@ mod tests {
             "◐"
         );
     }
+
+    #[test]
+    fn describes_how_a_run_exited() {
+        let mut run: TaskRunTreeNode = serde_json::from_value(serde_json::json!({
+            "id": "r1", "task": "dev", "cwd": "/repo", "status": "Failed",
+            "ready": false, "updatedAt": 0, "includeTasks": [], "attempt": 1, "restarts": 0,
+            "children": [],
+        }))
+        .unwrap();
+        assert_eq!(describe_run_outcome(&run), None);
+
+        run.exit_code = Some(137);
+        run.signal = Some("SIGKILL".to_string());
+        run.started_at = Some(1_000);
+        run.finished_at = Some(253_000);
+        assert_eq!(
+            describe_run_outcome(&run).as_deref(),
+            Some("exited 137 (SIGKILL) after 4m 12s")
+        );
+
+        run.signal = None;
+        run.finished_at = None;
+        assert_eq!(describe_run_outcome(&run).as_deref(), Some("exited 137"));
+
+        run.timed_out_after_ms = Some(600_000);
+        assert_eq!(
+            describe_run_outcome(&run).as_deref(),
+            Some("timed out after 10m 0s, exited 137")
+        );
+    }
 }
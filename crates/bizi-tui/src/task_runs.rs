@@ -52,7 +52,7 @@ pub fn upsert_run_tree_node(roots: &mut Vec<TaskRunTreeNode>, updated_run: TaskR
         roots.push(updated_run);
     }
 
-    roots.sort_by(|left, right| right.updated_at.cmp(&left.updated_at));
+    roots.sort_by_key(|root| std::cmp::Reverse(root.updated_at));
 }
 
 fn replace_run_tree_node(node: &mut TaskRunTreeNode, updated_run: &TaskRunTreeNode) -> bool {
@@ -125,7 +125,38 @@ pub fn can_cancel_run(run: &TaskRunTreeNode) -> bool {
         return true;
     }
 
-    run.status != TaskRunStatus::Success && run.status != TaskRunStatus::Failed
+    !matches!(
+        run.status,
+        TaskRunStatus::Success
+            | TaskRunStatus::Failed
+            | TaskRunStatus::Blocked
+            | TaskRunStatus::Interrupted
+    )
+}
+
+/// How toggling an optional subtask would go: on the latest run of its parent,
+/// including it unless that run's invocation already does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptionalTaskToggle {
+    pub parent_run_id: String,
+    pub is_included: bool,
+}
+
+/// `None` unless `task_key` is an optional subtask whose parent has a run.
+pub fn optional_task_toggle(
+    tasks: &TaskMap,
+    run_by_task_key: &RunByTaskKey,
+    task_key: &str,
+) -> Option<OptionalTaskToggle> {
+    if !tasks.get(task_key)?.optional.unwrap_or(false) {
+        return None;
+    }
+    let (parent_key, _) = task_key.rsplit_once(':')?;
+    let parent_run = run_by_task_key.get(parent_key)?;
+    Some(OptionalTaskToggle {
+        parent_run_id: parent_run.id.clone(),
+        is_included: parent_run.include_tasks.iter().any(|key| key == task_key),
+    })
 }
 
 #[cfg(test)]
@@ -139,10 +170,23 @@ mod tests {
             id: id.to_string(),
             task: task.to_string(),
             cwd: "/tmp".to_string(),
+            working_dir: None,
             parent_run_id: None,
+            started_by_run_id: None,
             status,
+            ready: false,
             updated_at,
             waiting_on: None,
+            blocked_by: None,
+            exit_code: None,
+            signal: None,
+            pid: None,
+            started_at: None,
+            finished_at: None,
+            include_tasks: Vec::new(),
+            attempt: 1,
+            restarts: 0,
+            timed_out_after_ms: None,
             children: Vec::new(),
         }
     }
@@ -241,4 +285,48 @@ mod tests {
         };
         assert!(can_cancel_run(&parent), "parents stay cancellable");
     }
+
+    #[test]
+    fn toggles_optional_subtasks_on_the_parent_run() {
+        let mut tasks: TaskMap = IndexMap::new();
+        tasks.insert("dev".to_string(), Task::default());
+        tasks.insert("dev:api".to_string(), Task::default());
+        tasks.insert(
+            "dev:storybook".to_string(),
+            Task {
+                optional: Some(true),
+                ..Task::default()
+            },
+        );
+
+        let mut run_by_task_key: RunByTaskKey = HashMap::new();
+        assert_eq!(
+            optional_task_toggle(&tasks, &run_by_task_key, "dev:storybook"),
+            None,
+            "nothing to toggle until the parent runs"
+        );
+
+        run_by_task_key.insert(
+            "dev".to_string(),
+            run("root", "dev", TaskRunStatus::Running, 1),
+        );
+        assert_eq!(
+            optional_task_toggle(&tasks, &run_by_task_key, "dev:api"),
+            None
+        );
+        assert_eq!(
+            optional_task_toggle(&tasks, &run_by_task_key, "dev:storybook"),
+            Some(OptionalTaskToggle {
+                parent_run_id: "root".to_string(),
+                is_included: false,
+            })
+        );
+
+        run_by_task_key.get_mut("dev").unwrap().include_tasks = vec!["dev:storybook".to_string()];
+        assert!(
+            optional_task_toggle(&tasks, &run_by_task_key, "dev:storybook")
+                .unwrap()
+                .is_included
+        );
+    }
 }
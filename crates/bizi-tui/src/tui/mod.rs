@@ -24,10 +24,13 @@ use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
 use tokio::time::{Duration, interval};
 
-use crate::api::{BiziApi, TaskRunLogsStreamMessage};
+use crate::api::{
+    BiziApi, GetTaskRunLogsRequest, GetTaskRunLogsResponseBody, TaskRunLogsStreamMessage,
+};
 use crate::cli::CliOptions;
 use crate::keyboard::{
     IS_MACOS, is_jump_parents_backward_shortcut, is_jump_parents_forward_shortcut,
+    is_leave_terminal_input_shortcut, terminal_input,
 };
 use crate::logs::{count_log_line_rows, resolve_task_log_color};
 use crate::model::{
@@ -35,8 +38,8 @@ use crate::model::{
     TaskTreeNode,
 };
 use crate::task_runs::{
-    DisplayStatusByTaskKey, RunByTaskKey, build_display_status_by_task_key, can_cancel_run,
-    index_runs_by_task_key, upsert_run_tree_node,
+    DisplayStatusByTaskKey, OptionalTaskToggle, RunByTaskKey, build_display_status_by_task_key,
+    can_cancel_run, index_runs_by_task_key, optional_task_toggle, upsert_run_tree_node,
 };
 use crate::task_structure::{
     build_task_tree, find_next_parent_task_index, find_previous_parent_task_index,
@@ -53,6 +56,9 @@ const COPY_TOAST_MS: u64 = 2000;
 /// scrollbox, so these are the amounts to match.
 const LOG_SCROLL_LINE_DIVISOR: usize = 5;
 const LOG_SCROLL_PAGE_DIVISOR: usize = 2;
+/// Log lines fetched at a time: the newest on selecting a run, then older
+/// pages as the view scrolls up to them.
+const LOG_PAGE_SIZE: u64 = 2000;
 
 type Backend = CrosstermBackend<Stdout>;
 
@@ -91,6 +97,11 @@ const QUIT_ACTIONS: [(&str, QuitAction); 2] = [
     ),
 ];
 
+/// The signals offered by the `s` menu, most commonly wanted first.
+const SIGNAL_MENU: [&str; 7] = [
+    "SIGHUP", "SIGINT", "SIGTERM", "SIGUSR1", "SIGUSR2", "SIGQUIT", "SIGKILL",
+];
+
 pub struct RunningTaskRow {
     pub key: String,
     pub status: TaskRunStatus,
@@ -128,15 +139,33 @@ impl LogLayout {
     }
 }
 
+/// Input mode, where keys go to the selected run rather than to the UI.
+struct TaskInput {
+    run_id: String,
+    /// With `pty: true`, every key goes straight to the task's terminal.
+    /// Otherwise, for `stdin: true`, a line is edited here and sent to its
+    /// stdin on Enter.
+    is_terminal: bool,
+    line: String,
+    sender: mpsc::UnboundedSender<String>,
+}
+
 pub enum AppEvent {
     Term(Event),
     ClockTick,
     RefreshTick,
     TasksLoaded(Option<TaskMap>),
     RunsLoaded(Option<Vec<TaskRunTreeNode>>),
-    RootRunUpdated(TaskRunTreeNode),
+    RootRunUpdated(Box<TaskRunTreeNode>),
     SelectedRunPing,
     Logs(u64, TaskRunLogsStreamMessage),
+    OlderLogs(u64, Result<GetTaskRunLogsResponseBody, String>),
+    /// The run given input stopped taking it, with the server's reason.
+    InputClosed(String, String),
+    /// What came of sending a signal from the `s` menu.
+    SignalSent(Result<String, String>),
+    /// Including or excluding an optional subtask failed, with the reason.
+    OptionalTaskToggleFailed(String),
     ToastExpired(u64),
     Quit,
 }
@@ -159,12 +188,22 @@ pub struct App {
     log_longest_task_name: usize,
     log_layout: LogLayout,
     log_mode: LogMode,
+    /// Whether the server has lines older than the first in `logs`.
+    log_has_older: bool,
+    is_loading_older_logs: bool,
+    /// Rows between the top of the log view and the end of the buffer, kept
+    /// across prepending older lines so the view stays where it was.
+    log_rows_from_scroll_to_end: Option<usize>,
     focused_pane: Pane,
 
     task_search_query: String,
     is_task_search_focused: bool,
     show_task_search_error: bool,
 
+    task_input: Option<TaskInput>,
+    /// The highlighted entry of `SIGNAL_MENU` while the menu is open.
+    signal_menu_index: Option<usize>,
+
     show_quit_confirmation: bool,
     quit_action_index: usize,
     is_cancelling_before_exit: bool,
@@ -190,6 +229,9 @@ pub struct App {
     root_runs_key: String,
     root_run_handles: Vec<JoinHandle<()>>,
     log_subscription_key: Option<String>,
+    /// An earlier attempt of a run whose logs are shown instead of its latest.
+    /// Only applies while that run is selected.
+    log_attempt: Option<(String, u32)>,
     log_generation: u64,
     log_handles: Vec<JoinHandle<()>>,
 
@@ -214,10 +256,15 @@ impl App {
             log_longest_task_name: 0,
             log_layout: LogLayout::default(),
             log_mode: LogMode::Aggregate,
+            log_has_older: false,
+            is_loading_older_logs: false,
+            log_rows_from_scroll_to_end: None,
             focused_pane: Pane::Tasks,
             task_search_query: String::new(),
             is_task_search_focused: false,
             show_task_search_error: false,
+            task_input: None,
+            signal_menu_index: None,
             show_quit_confirmation: false,
             quit_action_index: 0,
             is_cancelling_before_exit: false,
@@ -235,6 +282,7 @@ impl App {
             root_runs_key: String::new(),
             root_run_handles: Vec::new(),
             log_subscription_key: None,
+            log_attempt: None,
             log_generation: 0,
             log_handles: Vec::new(),
             should_quit: false,
@@ -255,6 +303,7 @@ impl App {
         } else if self.selected_index >= self.task_rows.len() {
             self.selected_index = self.task_rows.len() - 1;
         }
+        self.end_stale_task_input();
     }
 
     fn selected_row(&self) -> Option<&TaskRow> {
@@ -305,7 +354,9 @@ impl App {
         }
         match self.selected_display_status() {
             Some(DisplayTaskStatus::Run(TaskRunStatus::Success))
-            | Some(DisplayTaskStatus::Run(TaskRunStatus::Failed)) => RunAction::Run,
+            | Some(DisplayTaskStatus::Run(TaskRunStatus::Failed))
+            | Some(DisplayTaskStatus::Run(TaskRunStatus::Blocked))
+            | Some(DisplayTaskStatus::Run(TaskRunStatus::Interrupted)) => RunAction::Run,
             _ => RunAction::Restart,
         }
     }
@@ -334,10 +385,32 @@ impl App {
         self.log_mode == LogMode::Aggregate
     }
 
+    fn selected_optional_task_toggle(&self) -> Option<OptionalTaskToggle> {
+        let row = self.selected_row()?;
+        optional_task_toggle(&self.tasks, &self.run_by_task_key, &row.key)
+    }
+
     fn can_cancel_selected(&self) -> bool {
         self.selected_run().map(can_cancel_run).unwrap_or(false)
     }
 
+    fn can_send_input_to_selected(&self) -> bool {
+        let takes_input = self
+            .selected_row()
+            .and_then(|row| self.tasks.get(&row.key))
+            .is_some_and(|task| task.pty.unwrap_or(false) || task.stdin.unwrap_or(false));
+        self.selected_has_command()
+            && takes_input
+            && self
+                .selected_run()
+                .is_some_and(|run| run.status == TaskRunStatus::Running)
+    }
+
+    fn can_signal_selected(&self) -> bool {
+        self.selected_run()
+            .is_some_and(|run| run.status == TaskRunStatus::Running && run.pid.is_some())
+    }
+
     fn running_task_rows(&self) -> Vec<RunningTaskRow> {
         self.task_rows
             .iter()
@@ -387,6 +460,20 @@ impl App {
         self.log_revision = self.log_revision.wrapping_add(1);
     }
 
+    /// Puts a page of older lines in front of the buffer without moving what
+    /// is on screen.
+    fn prepend_logs(&mut self, mut older: Vec<TaskRunLogLine>) {
+        if older.is_empty() {
+            return;
+        }
+        if !self.log_follow {
+            self.log_rows_from_scroll_to_end =
+                Some(self.log_layout.total_rows().saturating_sub(self.log_scroll));
+        }
+        older.append(&mut self.logs);
+        self.replace_logs(older);
+    }
+
     /// Brings `log_layout` up to date for the given pane geometry. Appends are
     /// O(1) per new line; only a width change or a replaced buffer costs a full
     /// pass over the logs.
@@ -407,6 +494,10 @@ impl App {
             self.log_layout.row_offsets.push(self.log_layout.total_rows);
             self.log_layout.total_rows += count_log_line_rows(&line.line, content_width);
         }
+
+        if let Some(rows_to_end) = self.log_rows_from_scroll_to_end.take() {
+            self.log_scroll = self.log_layout.total_rows.saturating_sub(rows_to_end);
+        }
     }
 
     /// Columns available to the log message itself, after the timestamp and task
@@ -450,7 +541,7 @@ impl App {
         let cwd = self.cwd.clone();
         let events = self.events.clone();
         tokio::spawn(async move {
-            let _ = api.run_task(&task_key, &cwd, None).await;
+            let _ = api.run_task(&task_key, &cwd, None, None).await;
             let result = api.list_task_runs(&cwd).await.ok();
             let _ = events.send(AppEvent::RunsLoaded(result)).await;
         });
@@ -486,6 +577,34 @@ impl App {
         });
     }
 
+    fn toggle_selected_optional_task(&self) {
+        let Some(task_key) = self.selected_row().map(|row| row.key.clone()) else {
+            return;
+        };
+        let Some(toggle) = self.selected_optional_task_toggle() else {
+            return;
+        };
+        let api = self.api.clone();
+        let cwd = self.cwd.clone();
+        let events = self.events.clone();
+        tokio::spawn(async move {
+            let result = if toggle.is_included {
+                api.exclude_task(&toggle.parent_run_id, &task_key).await
+            } else {
+                api.include_task(&toggle.parent_run_id, &task_key).await
+            };
+            // Reloading runs clears the error, so a failed toggle skips it.
+            if let Err(err) = result {
+                let _ = events
+                    .send(AppEvent::OptionalTaskToggleFailed(err.to_string()))
+                    .await;
+                return;
+            }
+            let result = api.list_task_runs(&cwd).await.ok();
+            let _ = events.send(AppEvent::RunsLoaded(result)).await;
+        });
+    }
+
     fn cancel_running_tasks_before_exit(&mut self) {
         if self.is_cancelling_before_exit {
             return;
@@ -542,8 +661,10 @@ impl App {
             let api = self.api.clone();
             let events = self.events.clone();
             self.root_run_handles.push(tokio::spawn(async move {
-                api.stream_task_run(&run_id, events, AppEvent::RootRunUpdated)
-                    .await;
+                api.stream_task_run(&run_id, events, |run| {
+                    AppEvent::RootRunUpdated(Box::new(run))
+                })
+                .await;
             }));
         }
     }
@@ -557,14 +678,39 @@ impl App {
     fn selected_stream_key(&self) -> Option<String> {
         let run = self.selected_run()?;
         Some(format!(
-            "{}:{}:{}:{}",
+            "{}:{}:{}:{}:{:?}",
             run.id,
             run.updated_at,
             run.status.as_str(),
-            self.selected_uses_aggregate_logs()
+            self.selected_uses_aggregate_logs(),
+            self.viewed_log_attempt()
         ))
     }
 
+    /// The earlier attempt of the selected run being viewed, if any.
+    fn viewed_log_attempt(&self) -> Option<u32> {
+        let run = self.selected_run()?;
+        match &self.log_attempt {
+            Some((run_id, attempt)) if *run_id == run.id && *attempt < run.attempt => {
+                Some(*attempt)
+            }
+            _ => None,
+        }
+    }
+
+    /// Steps back through the selected run's earlier attempts, returning to
+    /// its latest after the first.
+    fn cycle_log_attempt(&mut self) {
+        let Some(run) = self.selected_run() else {
+            return;
+        };
+        if run.attempt <= 1 {
+            return;
+        }
+        let viewed = self.viewed_log_attempt().unwrap_or(run.attempt);
+        self.log_attempt = (viewed > 1).then(|| (run.id.clone(), viewed - 1));
+    }
+
     fn sync_log_subscription(&mut self) {
         let next_key = self.selected_stream_key();
         if next_key == self.log_subscription_key {
@@ -590,16 +736,46 @@ impl App {
         // jumps back to the bottom for the new selection.
         self.log_scroll = 0;
         self.log_follow = true;
+        self.log_has_older = false;
+        self.is_loading_older_logs = false;
+        self.log_rows_from_scroll_to_end = None;
 
         let api = self.api.clone();
         let events = self.events.clone();
         let logs_run_id = run_id.clone();
-        self.log_handles.push(tokio::spawn(async move {
-            api.stream_task_logs(&logs_run_id, include_children, events, move |message| {
-                AppEvent::Logs(generation, message)
-            })
-            .await;
-        }));
+        if let Some(attempt) = self.viewed_log_attempt() {
+            // An earlier attempt is over, so one fetch covers it.
+            self.log_handles.push(tokio::spawn(async move {
+                let request = GetTaskRunLogsRequest {
+                    include_children: Some(include_children),
+                    attempt: Some(attempt),
+                    tail: Some(LOG_PAGE_SIZE),
+                    ..GetTaskRunLogsRequest::default()
+                };
+                let message = match api.get_task_run_logs(&logs_run_id, &request).await {
+                    Ok(body) => TaskRunLogsStreamMessage::Snapshot {
+                        run_id: logs_run_id,
+                        logs: body.logs,
+                        has_more: body.has_more,
+                    },
+                    Err(err) => TaskRunLogsStreamMessage::Error {
+                        message: err.to_string(),
+                    },
+                };
+                let _ = events.send(AppEvent::Logs(generation, message)).await;
+            }));
+        } else {
+            self.log_handles.push(tokio::spawn(async move {
+                api.stream_task_logs(
+                    &logs_run_id,
+                    include_children,
+                    Some(LOG_PAGE_SIZE),
+                    events,
+                    move |message| AppEvent::Logs(generation, message),
+                )
+                .await;
+            }));
+        }
 
         let api = self.api.clone();
         let events = self.events.clone();
@@ -640,7 +816,7 @@ impl App {
                 None => self.error_message = Some("failed to load task runs".to_string()),
             },
             AppEvent::RootRunUpdated(run) => {
-                upsert_run_tree_node(&mut self.task_runs, run);
+                upsert_run_tree_node(&mut self.task_runs, *run);
                 self.rebuild_task_indexes();
             }
             AppEvent::SelectedRunPing => self.spawn_refresh_runs(),
@@ -649,13 +825,44 @@ impl App {
                     return;
                 }
                 match message {
-                    TaskRunLogsStreamMessage::Snapshot { logs, .. } => self.replace_logs(logs),
+                    TaskRunLogsStreamMessage::Snapshot { logs, has_more, .. } => {
+                        self.log_has_older = has_more;
+                        self.replace_logs(logs);
+                    }
                     TaskRunLogsStreamMessage::Log { log } => self.append_log(log),
                     TaskRunLogsStreamMessage::Error { message } => {
                         self.error_message = Some(message)
                     }
                 }
             }
+            AppEvent::OlderLogs(generation, result) => {
+                if generation != self.log_generation {
+                    return;
+                }
+                self.is_loading_older_logs = false;
+                match result {
+                    Ok(body) => {
+                        self.log_has_older = body.has_more;
+                        self.prepend_logs(body.logs);
+                    }
+                    Err(message) => self.error_message = Some(message),
+                }
+            }
+            AppEvent::InputClosed(run_id, message) => {
+                if self
+                    .task_input
+                    .as_ref()
+                    .is_some_and(|input| input.run_id == run_id)
+                {
+                    self.task_input = None;
+                    self.error_message = Some(message);
+                }
+            }
+            AppEvent::SignalSent(result) => match result {
+                Ok(message) => self.show_copy_toast(message),
+                Err(message) => self.error_message = Some(message),
+            },
+            AppEvent::OptionalTaskToggleFailed(message) => self.error_message = Some(message),
             AppEvent::ToastExpired(generation) => {
                 if generation == self.copy_toast_generation {
                     self.copy_toast_message = None;
@@ -676,6 +883,15 @@ impl App {
             self.handle_quit_confirmation_keys(key);
             return;
         }
+        if self.signal_menu_index.is_some() {
+            self.handle_signal_menu_keys(key);
+            return;
+        }
+        // Ahead of every shortcut, so Ctrl+C and `q` reach the task too.
+        if self.task_input.is_some() {
+            self.handle_task_input_keys(key);
+            return;
+        }
         if self.handle_copy_selection_key(key) {
             return;
         }
@@ -702,6 +918,133 @@ impl App {
         self.handle_action_keys(key);
     }
 
+    fn handle_signal_menu_keys(&mut self, key: KeyEvent) {
+        let Some(index) = self.signal_menu_index else {
+            return;
+        };
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.signal_menu_index =
+                    Some(index.checked_sub(1).unwrap_or(SIGNAL_MENU.len() - 1));
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.signal_menu_index = Some((index + 1) % SIGNAL_MENU.len());
+            }
+            KeyCode::Enter => {
+                self.signal_menu_index = None;
+                self.signal_selected_run(SIGNAL_MENU[index]);
+            }
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('s') => {
+                self.signal_menu_index = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn signal_selected_run(&self, signal: &'static str) {
+        let Some(run) = self.selected_run() else {
+            return;
+        };
+        let (run_id, task) = (run.id.clone(), run.task.clone());
+        let api = self.api.clone();
+        let events = self.events.clone();
+        tokio::spawn(async move {
+            let result = api
+                .signal_task_run(&run_id, signal)
+                .await
+                .map(|name| format!("Sent {name} to {task}"))
+                .map_err(|err| err.to_string());
+            let _ = events.send(AppEvent::SignalSent(result)).await;
+        });
+    }
+
+    fn start_task_input(&mut self) {
+        if !self.can_send_input_to_selected() {
+            return;
+        }
+        let (Some(row), Some(run)) = (self.selected_row(), self.selected_run()) else {
+            return;
+        };
+        let is_terminal = self
+            .tasks
+            .get(&row.key)
+            .and_then(|task| task.pty)
+            .unwrap_or(false);
+        let run_id = run.id.clone();
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let api = self.api.clone();
+        let events = self.events.clone();
+        tokio::spawn({
+            let run_id = run_id.clone();
+            async move {
+                if let Err(err) = api.stream_task_run_input(&run_id, receiver).await {
+                    let _ = events
+                        .send(AppEvent::InputClosed(run_id, err.to_string()))
+                        .await;
+                }
+            }
+        });
+        self.task_input = Some(TaskInput {
+            run_id,
+            is_terminal,
+            line: String::new(),
+            sender,
+        });
+    }
+
+    /// A task under a terminal gets every key but Ctrl+], which leaves input
+    /// mode, Esc included. Any other task gets one line at a time, and Esc
+    /// leaves.
+    fn handle_task_input_keys(&mut self, key: KeyEvent) {
+        let Some(input) = self.task_input.as_mut() else {
+            return;
+        };
+        let leaves = if input.is_terminal {
+            is_leave_terminal_input_shortcut(&key)
+        } else {
+            key.code == KeyCode::Esc
+        };
+        if leaves {
+            self.task_input = None;
+            return;
+        }
+
+        let data = if input.is_terminal {
+            terminal_input(&key)
+        } else {
+            match key.code {
+                KeyCode::Enter => Some(format!("{}\n", std::mem::take(&mut input.line))),
+                KeyCode::Backspace => {
+                    input.line.pop();
+                    None
+                }
+                KeyCode::Char(character) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    input.line.push(character);
+                    None
+                }
+                _ => None,
+            }
+        };
+        if let Some(data) = data {
+            let _ = input.sender.send(data);
+        }
+    }
+
+    /// Leaves input mode once the run it writes to is no longer the selected
+    /// one or has stopped.
+    fn end_stale_task_input(&mut self) {
+        let Some(input) = &self.task_input else {
+            return;
+        };
+        let is_current = self
+            .selected_run()
+            .is_some_and(|run| run.id == input.run_id && run.status == TaskRunStatus::Running);
+        if !is_current {
+            self.task_input = None;
+        }
+    }
+
     fn handle_quit_confirmation_keys(&mut self, key: KeyEvent) {
         if self.is_cancelling_before_exit {
             return;
@@ -811,14 +1154,12 @@ impl App {
         match key.code {
             // Matches the TypeScript TUI: down (and the vim-style `j`) leaves
             // the search box and drops into the task list.
-            KeyCode::Down | KeyCode::Char('j') => {
-                if !self.task_rows.is_empty() {
-                    self.selected_index = 0;
-                    self.is_task_search_focused = false;
-                    self.focused_pane = Pane::Tasks;
-                }
+            KeyCode::Down | KeyCode::Char('j') if !self.task_rows.is_empty() => {
+                self.selected_index = 0;
+                self.is_task_search_focused = false;
+                self.focused_pane = Pane::Tasks;
             }
-            KeyCode::Char('/') => {}
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('/') => {}
             KeyCode::Esc => self.clear_task_search(),
             KeyCode::Enter => self.handle_task_search_submit(),
             KeyCode::Backspace => {
@@ -891,7 +1232,9 @@ impl App {
             .flatten()
         {
             Some(DisplayTaskStatus::Run(TaskRunStatus::Success))
-            | Some(DisplayTaskStatus::Run(TaskRunStatus::Failed)) => RunAction::Run,
+            | Some(DisplayTaskStatus::Run(TaskRunStatus::Failed))
+            | Some(DisplayTaskStatus::Run(TaskRunStatus::Blocked))
+            | Some(DisplayTaskStatus::Run(TaskRunStatus::Interrupted)) => RunAction::Run,
             _ => RunAction::Restart,
         }
     }
@@ -970,6 +1313,7 @@ impl App {
             KeyCode::Home => {
                 self.log_follow = false;
                 self.log_scroll = 0;
+                self.load_older_logs();
                 true
             }
             KeyCode::End => {
@@ -1000,6 +1344,40 @@ impl App {
         let next = (current as isize + delta).clamp(0, max_scroll as isize) as usize;
         self.log_scroll = next;
         self.log_follow = next >= max_scroll;
+        if next == 0 && delta < 0 {
+            self.load_older_logs();
+        }
+    }
+
+    /// Fetches the page of lines before the oldest one shown, once the view
+    /// has scrolled up to it.
+    fn load_older_logs(&mut self) {
+        if !self.log_has_older || self.is_loading_older_logs {
+            return;
+        }
+        let (Some(run), Some(oldest)) = (self.selected_run(), self.logs.first()) else {
+            return;
+        };
+        let run_id = run.id.clone();
+        let request = GetTaskRunLogsRequest {
+            include_children: Some(self.selected_uses_aggregate_logs()),
+            attempt: self.viewed_log_attempt(),
+            before_sequence: Some(oldest.sequence),
+            tail: Some(LOG_PAGE_SIZE),
+            ..GetTaskRunLogsRequest::default()
+        };
+        self.is_loading_older_logs = true;
+
+        let api = self.api.clone();
+        let events = self.events.clone();
+        let generation = self.log_generation;
+        self.log_handles.push(tokio::spawn(async move {
+            let result = api
+                .get_task_run_logs(&run_id, &request)
+                .await
+                .map_err(|err| err.to_string());
+            let _ = events.send(AppEvent::OlderLogs(generation, result)).await;
+        }));
     }
 
     fn handle_action_keys(&mut self, key: KeyEvent) -> bool {
@@ -1027,6 +1405,24 @@ impl App {
                 self.cancel_selected_run();
                 true
             }
+            KeyCode::Char('o') => {
+                self.toggle_selected_optional_task();
+                true
+            }
+            KeyCode::Char('a') => {
+                self.cycle_log_attempt();
+                true
+            }
+            KeyCode::Char('i') => {
+                self.start_task_input();
+                true
+            }
+            KeyCode::Char('s') => {
+                if self.can_signal_selected() {
+                    self.signal_menu_index = Some(0);
+                }
+                true
+            }
             _ => false,
         }
     }
@@ -8,18 +8,20 @@ use ratatui::layout::{Position, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use unicode_width::UnicodeWidthStr;
 
+use crate::cli_task_runs::flatten_task_runs;
 use crate::logs::{
     format_elapsed_duration, format_log_timestamp, format_task_tag_for_log, wrap_log_line,
 };
 use crate::model::{DisplayTaskStatus, TaskRunStatus, TaskTreeNode};
-use crate::status::{parse_color, task_status_display};
+use crate::status::{exit_detail, parse_color, task_status_display};
 
-use super::{App, Pane, QUIT_ACTIONS};
+use super::{App, Pane, QUIT_ACTIONS, SIGNAL_MENU, TaskInput};
 
 const GREY: Color = Color::Rgb(0x66, 0x66, 0x66);
 const BRIGHT: Color = Color::Rgb(0xe6, 0xe6, 0xe6);
 const SEARCH_ERROR: Color = Color::Rgb(0xff, 0x55, 0x55);
 const TOAST: Color = Color::Rgb(0x7d, 0xdc, 0x8e);
+const RESTARTS: Color = Color::Rgb(0xff, 0x9f, 0x0a);
 const SELECTION_BG: Color = Color::Rgb(0x3a, 0x4a, 0x78);
 const SELECTION_FG: Color = Color::Rgb(0xff, 0xff, 0xff);
 const DIALOG_WIDTH: u16 = 84;
@@ -78,6 +80,14 @@ pub fn render(frame: &mut Frame, app: &mut App) {
 
     if app.show_quit_confirmation {
         draw_quit_confirmation(buffer, area, app);
+    } else if let Some(index) = app.signal_menu_index {
+        draw_signal_menu(buffer, area, app, index);
+    } else if let Some(input) = app.task_input.as_ref().filter(|input| !input.is_terminal) {
+        let cursor_x = 2 + ("esc stop input | > ".width() + input.line.width()) as u16;
+        frame.set_cursor_position(Position::new(
+            cursor_x.min(area.width.saturating_sub(2)),
+            layout.footer_text_y,
+        ));
     } else if app.is_task_search_focused {
         let cursor_x = layout.search_x
             + (app.task_search_query.width() as u16).min(layout.search_width.saturating_sub(1));
@@ -331,7 +341,11 @@ fn push_task_node(
         .flatten();
 
     lines.push(card_edge(width, border, true));
-    lines.push(card_header(&node.row.key, status, width, border));
+    let restarts = app
+        .run_by_task_key
+        .get(&node.row.key)
+        .map_or(0, |run| run.restarts);
+    lines.push(card_header(&node.row.key, status, restarts, width, border));
 
     if !node.children.is_empty() {
         lines.push(vec![
@@ -383,22 +397,31 @@ fn card_edge(width: u16, border: Style, is_top: bool) -> Runs {
     )]
 }
 
+/// `restarts` shows as `↻3` next to the status icon once a run's `restart`
+/// policy has restarted it.
 fn card_header(
     task_key: &str,
     status: Option<DisplayTaskStatus>,
+    restarts: u32,
     width: u16,
     border: Style,
 ) -> Runs {
     let display = task_status_display(status);
+    let badge = if restarts > 0 {
+        format!("↻{restarts} ")
+    } else {
+        String::new()
+    };
     let field_width = (width - 4) as usize;
-    let key_width = field_width.saturating_sub(2);
+    let key_width = field_width.saturating_sub(2 + badge.width());
     let key = truncate_to_width(task_key, key_width);
-    let padding = field_width.saturating_sub(key.width() + 1);
+    let padding = field_width.saturating_sub(key.width() + badge.width() + 1);
 
     vec![
         ("│ ".to_string(), border),
         (key, Style::default()),
         (" ".repeat(padding), Style::default()),
+        (badge, Style::default().fg(RESTARTS)),
         (display.icon.to_string(), Style::default().fg(display.color)),
         (" │".to_string(), border),
     ]
@@ -540,10 +563,21 @@ fn draw_run_status(buffer: &mut Buffer, layout: &FrameLayout, app: &App) {
         let display = task_status_display(footer_status.map(DisplayTaskStatus::Run));
         runs.push((display.icon.to_string(), Style::default().fg(display.color)));
         runs.push((format!(" {}", run_status_text(app)), Style::default()));
+        if let Some(started_for) = started_for_task(app) {
+            runs.push((
+                format!(" · started for {started_for}"),
+                Style::default().fg(GREY),
+            ));
+        }
     }
 
     let left_width: usize = runs.iter().map(|(text, _)| text.width()).sum();
-    let cwd = truncate_to_width(&app.cwd, width as usize);
+    // A task with its own `cwd` shows where it actually runs.
+    let cwd = app
+        .selected_run()
+        .and_then(|run| run.working_dir.as_deref())
+        .unwrap_or(&app.cwd);
+    let cwd = truncate_to_width(cwd, width as usize);
     let gap = (width as usize)
         .saturating_sub(left_width)
         .saturating_sub(cwd.width());
@@ -553,6 +587,15 @@ fn draw_run_status(buffer: &mut Buffer, layout: &FrameLayout, app: &App) {
     draw_runs(buffer, x, layout.right_status_y, width, &runs);
 }
 
+/// The task whose `dependsOn` caused the selected run to be started, if any.
+fn started_for_task(app: &App) -> Option<&str> {
+    let started_by_run_id = app.selected_run()?.started_by_run_id.as_deref()?;
+    flatten_task_runs(&app.task_runs)
+        .into_iter()
+        .find(|run| run.id == started_by_run_id)
+        .map(|run| run.task.as_str())
+}
+
 /// Port of `RunDetailsPanel`'s `footerStatusText` memo.
 fn run_status_text(app: &App) -> String {
     let now_ms = chrono::Local::now().timestamp_millis();
@@ -561,15 +604,22 @@ fn run_status_text(app: &App) -> String {
     let last_log_timestamp = app.logs.last().map(|line| line.timestamp);
     let footer_status = app.selected_footer_status();
 
-    let run_start = first_log_timestamp
+    let selected_run = app.selected_run();
+    let run_start = selected_run
+        .and_then(|run| run.started_at)
+        .or(first_log_timestamp)
         .or(selected_run_updated_at)
         .unwrap_or(now_ms);
     let run_end = match footer_status {
         Some(TaskRunStatus::Running) | Some(TaskRunStatus::Queued) => now_ms,
-        _ => last_log_timestamp
+        _ => selected_run
+            .and_then(|run| run.finished_at)
+            .or(last_log_timestamp)
             .or(selected_run_updated_at)
             .unwrap_or(now_ms),
     };
+    let exit = selected_run.and_then(exit_detail);
+    let timed_out_after_ms = selected_run.and_then(|run| run.timed_out_after_ms);
     let run_duration_ms = run_end - run_start;
 
     let waiting_on = app
@@ -589,21 +639,42 @@ fn run_status_text(app: &App) -> String {
         Some(TaskRunStatus::Running) => {
             format!("Running for {}", format_elapsed_duration(run_duration_ms))
         }
-        Some(TaskRunStatus::Cancelled) => {
-            format!(
+        Some(TaskRunStatus::Cancelled) => match exit {
+            Some(exit) => format!(
+                "Canceled after {}, exited {exit}",
+                format_elapsed_duration(run_duration_ms)
+            ),
+            None => format!(
                 "Canceled after {}",
                 format_elapsed_duration(run_duration_ms)
-            )
-        }
+            ),
+        },
         Some(TaskRunStatus::Success) => {
             format!("Succeeded in {}", format_elapsed_duration(run_duration_ms))
         }
-        Some(TaskRunStatus::Failed) => {
-            format!("Failed after {}", format_elapsed_duration(run_duration_ms))
-        }
+        Some(TaskRunStatus::Failed) => match (timed_out_after_ms, exit) {
+            (Some(timeout_ms), _) => {
+                format!(
+                    "Timed out after {}",
+                    format_elapsed_duration(timeout_ms as i64)
+                )
+            }
+            (None, Some(exit)) => format!(
+                "Exited {exit} after {}",
+                format_elapsed_duration(run_duration_ms)
+            ),
+            (None, None) => format!("Failed after {}", format_elapsed_duration(run_duration_ms)),
+        },
         Some(TaskRunStatus::Queued) => {
             format!("Queued for {}", format_elapsed_duration(run_duration_ms))
         }
+        Some(TaskRunStatus::Blocked) => {
+            match app.selected_run().and_then(|run| run.blocked_by.as_deref()) {
+                Some(blocked_by) => format!("Blocked by {}", collapse_whitespace(blocked_by)),
+                None => "Blocked".to_string(),
+            }
+        }
+        Some(TaskRunStatus::Interrupted) => "Interrupted when the server stopped".to_string(),
         None => collapse_whitespace(
             app.selected_display_status()
                 .map(|status| status.label())
@@ -613,6 +684,11 @@ fn run_status_text(app: &App) -> String {
 }
 
 fn draw_footer(buffer: &mut Buffer, layout: &FrameLayout, app: &App) {
+    if let Some(input) = &app.task_input {
+        draw_task_input_footer(buffer, layout, app, input);
+        return;
+    }
+
     let mut actions: Vec<(&str, String)> = vec![("/", "find/run".to_string())];
     if app.selected_row().is_some() {
         actions.push(("r", app.selected_run_action().label().to_string()));
@@ -620,6 +696,26 @@ fn draw_footer(buffer: &mut Buffer, layout: &FrameLayout, app: &App) {
     if app.can_cancel_selected() {
         actions.push(("c", "cancel".to_string()));
     }
+    if let Some(toggle) = app.selected_optional_task_toggle() {
+        let label = if toggle.is_included {
+            "exclude"
+        } else {
+            "include"
+        };
+        actions.push(("o", label.to_string()));
+    }
+    if let Some(run) = app.selected_run()
+        && run.attempt > 1
+    {
+        let viewed = app.viewed_log_attempt().unwrap_or(run.attempt);
+        actions.push(("a", format!("attempt {viewed}/{}", run.attempt)));
+    }
+    if app.can_send_input_to_selected() {
+        actions.push(("i", "input".to_string()));
+    }
+    if app.can_signal_selected() {
+        actions.push(("s", "signal".to_string()));
+    }
     if app.can_toggle_log_mode() {
         actions.push(("m", format!("logs: {}", app.log_mode.label())));
     }
@@ -647,6 +743,30 @@ fn draw_footer(buffer: &mut Buffer, layout: &FrameLayout, app: &App) {
     draw_runs(buffer, 2, layout.footer_text_y, width, &runs);
 }
 
+/// Replaces the shortcuts while in input mode: the line being typed, or a
+/// note that keys go to the task's terminal.
+fn draw_task_input_footer(buffer: &mut Buffer, layout: &FrameLayout, app: &App, input: &TaskInput) {
+    let leave_key = if input.is_terminal { "ctrl+] " } else { "esc " };
+    let mut runs: Runs = vec![(leave_key.to_string(), Style::default())];
+    runs.push(("stop input".to_string(), Style::default().fg(GREY)));
+    runs.push((" | ".to_string(), Style::default().fg(GREY)));
+    if input.is_terminal {
+        runs.push((
+            "keys go to the task's terminal".to_string(),
+            Style::default().fg(TOAST),
+        ));
+    } else {
+        runs.push(("> ".to_string(), Style::default().fg(TOAST)));
+        runs.push((input.line.clone(), Style::default()));
+    }
+    if let Some(message) = &app.error_message {
+        runs.push((format!(" | error: {message}"), Style::default()));
+    }
+
+    let width = layout.area.width.saturating_sub(3);
+    draw_runs(buffer, 2, layout.footer_text_y, width, &runs);
+}
+
 // ----------------------------------------------------------- quit dialog
 
 fn draw_quit_confirmation(buffer: &mut Buffer, area: Rect, app: &App) {
@@ -709,6 +829,7 @@ fn draw_quit_confirmation(buffer: &mut Buffer, area: Rect, app: &App) {
             &card_header(
                 &task.key,
                 Some(DisplayTaskStatus::Run(task.status)),
+                0,
                 card_width,
                 border,
             )
@@ -769,6 +890,58 @@ fn draw_quit_confirmation(buffer: &mut Buffer, area: Rect, app: &App) {
     }
 }
 
+// ----------------------------------------------------------- signal menu
+
+fn draw_signal_menu(buffer: &mut Buffer, area: Rect, app: &App, selected_index: usize) {
+    let task = app.selected_row().map(|row| row.key.as_str()).unwrap_or("");
+    let title = format!("Send a signal to {task}");
+    let width = ((title.width() + 4) as u16)
+        .max(30)
+        .min(area.width.saturating_sub(2));
+    let height = (SIGNAL_MENU.len() as u16 + 6).min(area.height);
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    blank_area(buffer, Rect::new(x, y, width, height));
+
+    let border = Style::default().fg(GREY).bg(Color::Rgb(0, 0, 0));
+    let text_style = Style::default().bg(Color::Rgb(0, 0, 0));
+    draw_box(buffer, x, y, width, height, border);
+
+    let inner_x = x + 2;
+    let inner_width = width.saturating_sub(4);
+    draw_runs(buffer, inner_x, y + 1, inner_width, &[(title, text_style)]);
+    for (index, signal) in SIGNAL_MENU.iter().enumerate() {
+        let style = if index == selected_index {
+            Style::default()
+                .fg(Color::Rgb(0, 0, 0))
+                .bg(Color::Rgb(0xff, 0xff, 0xff))
+        } else {
+            text_style
+        };
+        let label = format!(
+            " {signal:<width$}",
+            width = inner_width.saturating_sub(1) as usize
+        );
+        draw_runs(
+            buffer,
+            inner_x,
+            y + 3 + index as u16,
+            inner_width,
+            &[(label, style)],
+        );
+    }
+    draw_runs(
+        buffer,
+        inner_x,
+        y + height - 2,
+        inner_width,
+        &[(
+            "enter send | esc close".to_string(),
+            Style::default().fg(GREY).bg(Color::Rgb(0, 0, 0)),
+        )],
+    );
+}
+
 /// Blacks out `area` so an overlay can be drawn on top of it.
 ///
 /// Resets each cell rather than just recolouring it. Setting only the symbol and
@@ -974,9 +1147,14 @@ mod tests {
         assert_eq!(rendered.width(), 20);
         assert!(rendered.starts_with('╭') && rendered.ends_with('╮'));
 
-        let header = card_header("dev:api", None, 20, border);
+        let header = card_header("dev:api", None, 0, 20, border);
         let rendered: String = header.iter().map(|(text, _)| text.as_str()).collect();
         assert_eq!(rendered.width(), 20);
         assert!(rendered.contains("dev:api"));
+
+        let header = card_header("dev:api", None, 3, 20, border);
+        let rendered: String = header.iter().map(|(text, _)| text.as_str()).collect();
+        assert_eq!(rendered.width(), 20);
+        assert!(rendered.contains("dev:api") && rendered.contains("↻3 ○"));
     }
 }
@@ -4,6 +4,38 @@
  */
 
 export interface paths {
+	"/api/config/validate": {
+		parameters: {
+			query?: never;
+			header?: never;
+			path?: never;
+			cookie?: never;
+		};
+		get: operations["validate_config"];
+		put?: never;
+		post?: never;
+		delete?: never;
+		options?: never;
+		head?: never;
+		patch?: never;
+		trace?: never;
+	};
+	"/api/logs/search": {
+		parameters: {
+			query?: never;
+			header?: never;
+			path?: never;
+			cookie?: never;
+		};
+		get: operations["search_logs"];
+		put?: never;
+		post?: never;
+		delete?: never;
+		options?: never;
+		head?: never;
+		patch?: never;
+		trace?: never;
+	};
 	"/api/tasks": {
 		parameters: {
 			query?: never;
@@ -94,6 +126,54 @@ export interface paths {
 		get: operations["get_task_run"];
 		put?: never;
 		post?: never;
+		delete: operations["delete_task_run"];
+		options?: never;
+		head?: never;
+		patch?: never;
+		trace?: never;
+	};
+	"/api/tasks/{run_id}/exclude": {
+		parameters: {
+			query?: never;
+			header?: never;
+			path?: never;
+			cookie?: never;
+		};
+		get?: never;
+		put?: never;
+		post: operations["exclude_task"];
+		delete?: never;
+		options?: never;
+		head?: never;
+		patch?: never;
+		trace?: never;
+	};
+	"/api/tasks/{run_id}/include": {
+		parameters: {
+			query?: never;
+			header?: never;
+			path?: never;
+			cookie?: never;
+		};
+		get?: never;
+		put?: never;
+		post: operations["include_task"];
+		delete?: never;
+		options?: never;
+		head?: never;
+		patch?: never;
+		trace?: never;
+	};
+	"/api/tasks/{run_id}/input": {
+		parameters: {
+			query?: never;
+			header?: never;
+			path?: never;
+			cookie?: never;
+		};
+		get?: never;
+		put?: never;
+		post: operations["send_task_run_input"];
 		delete?: never;
 		options?: never;
 		head?: never;
@@ -116,6 +196,38 @@ export interface paths {
 		patch?: never;
 		trace?: never;
 	};
+	"/api/tasks/{run_id}/resize": {
+		parameters: {
+			query?: never;
+			header?: never;
+			path?: never;
+			cookie?: never;
+		};
+		get?: never;
+		put?: never;
+		post: operations["resize_task_run"];
+		delete?: never;
+		options?: never;
+		head?: never;
+		patch?: never;
+		trace?: never;
+	};
+	"/api/tasks/{run_id}/signal": {
+		parameters: {
+			query?: never;
+			header?: never;
+			path?: never;
+			cookie?: never;
+		};
+		get?: never;
+		put?: never;
+		post: operations["signal_task_run"];
+		delete?: never;
+		options?: never;
+		head?: never;
+		patch?: never;
+		trace?: never;
+	};
 }
 export type webhooks = Record<string, never>;
 export interface components {
@@ -129,16 +241,87 @@ export interface components {
 		CancelTaskResponseBody: {
 			cancelledRunIds: string[];
 		};
+		ConfigDiagnostic: {
+			column: number;
+			line: number;
+			message: string;
+			/** @description Where in the document the problem is, such as `$.tasks.dev.command`. */
+			path: string;
+			severity: components["schemas"]["DiagnosticSeverity"];
+		};
+		DeleteTaskRunResponse:
+			| components["schemas"]["DeleteTaskRunResponseBody"]
+			| components["schemas"]["ErrorResponse"];
+		DeleteTaskRunResponseBody: {
+			/** @description The run and every run under it, which were deleted with their logs. */
+			deletedRunIds: string[];
+		};
+		Dependency: string | components["schemas"]["DetailedDependency"];
+		DetailedDependency: {
+			satisfiedBy?: components["schemas"]["SatisfactionPolicy"] | null;
+			/** @description The key of the task depended on. */
+			task: string;
+		};
+		/** @enum {string} */
+		DiagnosticSeverity: "error" | "warning";
+		EnvFile: string | string[];
 		ErrorResponse: {
 			message: string;
 		};
+		ExcludeTaskRequest: {
+			/** @description The full key of one of the run's optional subtasks, such as `dev:storybook`. */
+			task: string;
+		};
+		ExcludeTaskResponse:
+			| components["schemas"]["ExcludeTaskResponseBody"]
+			| components["schemas"]["ErrorResponse"];
+		ExcludeTaskResponseBody: {
+			cancelledRunIds: string[];
+		};
 		GetTaskRunLogsRequest: {
+			/**
+			 * Format: int64
+			 * @description Only lines with a greater sequence.
+			 */
+			afterSequence?: number | null;
+			/**
+			 * Format: int32
+			 * @description Which of the run's attempts to return. Defaults to the latest.
+			 */
+			attempt?: number | null;
+			/**
+			 * Format: int64
+			 * @description Only lines with a smaller sequence.
+			 */
+			beforeSequence?: number | null;
+			/**
+			 * Format: int64
+			 * @description Websocket only: the last line a reconnecting client already has.
+			 *     The lines after it are sent as log messages instead of a snapshot.
+			 */
+			fromSequence?: number | null;
 			includeChildren?: boolean | null;
+			/**
+			 * Format: int64
+			 * @description At most this many lines, starting from the oldest that match.
+			 */
+			limit?: number | null;
+			/**
+			 * Format: int64
+			 * @description At most this many lines, ending with the newest that match. Cannot
+			 *     be combined with `limit`.
+			 */
+			tail?: number | null;
 		};
 		GetTaskRunLogsResponse:
 			| components["schemas"]["GetTaskRunLogsResponseBody"]
 			| components["schemas"]["ErrorResponse"];
 		GetTaskRunLogsResponseBody: {
+			/**
+			 * @description Whether `limit` or `tail` left out lines that match: newer ones for
+			 *     `limit`, older ones for `tail`.
+			 */
+			hasMore?: boolean;
 			logs: components["schemas"]["TaskRunLogLine"][];
 			runId: string;
 		};
@@ -148,6 +331,17 @@ export interface components {
 		GetTaskRunResponseBody: {
 			taskRun: components["schemas"]["TaskRunTreeNode"];
 		};
+		IncludeTaskRequest: {
+			/** @description The full key of one of the run's optional subtasks, such as `dev:storybook`. */
+			task: string;
+		};
+		IncludeTaskResponse:
+			| components["schemas"]["IncludeTaskResponseBody"]
+			| components["schemas"]["ErrorResponse"];
+		IncludeTaskResponseBody: {
+			/** @description The subtask's run, if it was started or queued. Unset while the run has not reached its subtasks, which then start with it. */
+			runId?: string | null;
+		};
 		ListTaskRunsRequest: {
 			/** @example /Users/johndoe/documents/github/example-project */
 			cwd: string;
@@ -172,6 +366,82 @@ export interface components {
 				[key: string]: components["schemas"]["Task"];
 			};
 		};
+		LogSearchMatch: {
+			/** Format: int32 */
+			attempt: number;
+			isStderr: boolean;
+			runId: string;
+			/** Format: int64 */
+			sequence: number;
+			/** @description The line, cut down around the match, with each match wrapped in `**`. */
+			snippet: string;
+			task: string;
+			/** Format: int64 */
+			timestamp: number;
+		};
+		ReadyProbe: {
+			/** @description An `http://` URL that answers with a 2xx status once the task is up. */
+			http?: string | null;
+			/** @description A regular expression matched against each line the task prints. */
+			log?: string | null;
+			/**
+			 * Format: int32
+			 * @description A port on localhost that accepts TCP connections once the task is up.
+			 */
+			port?: number | null;
+			/**
+			 * Format: int64
+			 * @description How long to wait for the probes to pass, in milliseconds. Defaults to 60000.
+			 */
+			timeoutMs?: number | null;
+		};
+		ResizeTaskRunRequest: {
+			/**
+			 * Format: int32
+			 * @description The terminal's width, in columns.
+			 */
+			cols: number;
+			/**
+			 * Format: int32
+			 * @description The terminal's height, in rows.
+			 */
+			rows: number;
+		};
+		ResizeTaskRunResponse:
+			| components["schemas"]["ResizeTaskRunResponseBody"]
+			| components["schemas"]["ErrorResponse"];
+		ResizeTaskRunResponseBody: {
+			/**
+			 * Format: int32
+			 * @description The terminal's width, in columns.
+			 */
+			cols: number;
+			/**
+			 * Format: int32
+			 * @description The terminal's height, in rows.
+			 */
+			rows: number;
+		};
+		/** @enum {string} */
+		RestartOn: "failure" | "always";
+		RestartPolicy: {
+			/**
+			 * Format: int64
+			 * @description How long to wait before restarting, in milliseconds, doubled for each restart in a row up to a minute. Defaults to 1000.
+			 */
+			backoffMs?: number | null;
+			/**
+			 * Format: int32
+			 * @description How many times in a row to restart before giving up. Defaults to no limit.
+			 */
+			maxRestarts?: number | null;
+			on: components["schemas"]["RestartOn"];
+			/**
+			 * Format: int64
+			 * @description How long the command has to stay up for it to no longer count as restarting in a row, in milliseconds. Defaults to 60000.
+			 */
+			resetAfterMs?: number | null;
+		};
 		RestartTaskRequest: {
 			runId: string;
 		};
@@ -181,10 +451,79 @@ export interface components {
 		RestartTaskResponseBody: {
 			runId: string;
 		};
+		SatisfactionPolicy:
+			| "always"
+			| "sinceServerStart"
+			| {
+					/**
+					 * Format: int64
+					 * @description A run in the last this many seconds.
+					 */
+					withinSeconds: number;
+			  }
+			| "sameRootRun";
+		SearchLogsRequest: {
+			/** @example /Users/johndoe/documents/github/example-project */
+			cwd: string;
+			/**
+			 * Format: int64
+			 * @description At most this many matches, newest first. Defaults to 100.
+			 */
+			limit?: number | null;
+			/** @description Text to find anywhere in a line, ignoring case. At least three characters. */
+			q: string;
+			/**
+			 * Format: int64
+			 * @description Only lines written at or after this time, in milliseconds since the epoch.
+			 */
+			since?: number | null;
+			/** @description Only lines written by this task. */
+			task?: string | null;
+		};
+		SearchLogsResponse:
+			| components["schemas"]["SearchLogsResponseBody"]
+			| components["schemas"]["ErrorResponse"];
+		SearchLogsResponseBody: {
+			/** @description Whether `limit` left out older matches. */
+			hasMore: boolean;
+			/** @description Newest first. */
+			matches: components["schemas"]["LogSearchMatch"][];
+		};
+		SendTaskRunInputRequest: {
+			/** @description Written to the task's stdin, or typed at its terminal with `pty: true`, as is. End it with `\n` to submit a line, or `\r` for a terminal. */
+			data: string;
+		};
+		SendTaskRunInputResponse:
+			| components["schemas"]["SendTaskRunInputResponseBody"]
+			| components["schemas"]["ErrorResponse"];
+		SendTaskRunInputResponseBody: {
+			/** @description How many bytes were written. */
+			bytesWritten: number;
+		};
+		SignalTaskRunRequest: {
+			/** @description The signal to send the run's process group, such as `SIGHUP`. The `SIG` prefix and case are optional. */
+			signal: string;
+		};
+		SignalTaskRunResponse:
+			| components["schemas"]["SignalTaskRunResponseBody"]
+			| components["schemas"]["ErrorResponse"];
+		SignalTaskRunResponseBody: {
+			/** @description The full name of the signal that was sent, such as `SIGHUP`. */
+			signal: string;
+		};
 		StartTaskRequest: {
 			cwd: string;
+			/** @description Environment variables for this run only, applied on top of each task's own. */
+			env?: {
+				[key: string]: string;
+			} | null;
 			includeTasks?: string[] | null;
 			task: string;
+			/**
+			 * Format: int64
+			 * @description How long the run and its subtasks may take altogether, in milliseconds. Whatever is still queued or running then is stopped and fails. Ignored when the task is already running, as that run was started by someone else.
+			 */
+			timeoutMs?: number | null;
 		};
 		StartTaskResponse:
 			| components["schemas"]["StartTaskResponseBody"]
@@ -193,25 +532,57 @@ export interface components {
 			runId: string;
 		};
 		Task: {
+			/** @description Whether to start any task in `dependsOn` that has not succeeded and is not already running, rather than waiting for it. Defaults to true. Subtasks inherit it unless they set their own. */
+			autoStartDependencies?: boolean | null;
 			/** @description The color used for client-side log rendering for this task. */
 			color?: string | null;
 			/** @description The command that the task will run. */
 			command?: string | null;
+			/** @description The directory to run the task in, relative to `task.config.json`. Subtasks inherit it unless they set their own. */
+			cwd?: string | null;
 			/** @description Any other task names that this task depends on. */
-			dependsOn?: string[] | null;
+			dependsOn?: components["schemas"]["Dependency"][] | null;
 			dependsOnTasks?: {
 				[key: string]: components["schemas"]["Task"];
 			} | null;
+			/** @description Environment variables to set for the task. Values may reference other variables as `${VAR}`. Subtasks inherit them and can override individual keys. */
+			env?: {
+				[key: string]: string;
+			} | null;
+			envFile?: components["schemas"]["EnvFile"] | null;
 			/** @description Whether the task is optional. If true, the task will only run if started manually. */
 			optional?: boolean | null;
+			/** @description Whether to run the command under a pseudo-terminal, so that it sees an interactive terminal rather than pipes. Its stdout and stderr then arrive as one stream. Only supported on Linux. Defaults to false. */
+			pty?: boolean | null;
+			ready?: components["schemas"]["ReadyProbe"] | null;
+			restart?: components["schemas"]["RestartPolicy"] | null;
+			/** @description Whether clients can send input to the command's stdin while it runs. Otherwise stdin is empty, so a command that reads it sees end-of-file rather than waiting. A task with `pty: true` always takes input. Defaults to false. */
+			stdin?: boolean | null;
+			/** @description The signal sent to the task's process group to stop it, such as `SIGINT`. Defaults to `SIGTERM`. */
+			stopSignal?: string | null;
+			/**
+			 * Format: int64
+			 * @description How long the task has to exit after `stopSignal` before it is sent `SIGKILL`. Defaults to 5000.
+			 */
+			stopTimeoutMs?: number | null;
 			/** @description Subtasks of this task. Keys must be unique task names. */
 			tasks?: {
 				[key: string]: components["schemas"]["Task"];
 			} | null;
+			/**
+			 * Format: int64
+			 * @description How long the command may run, in milliseconds, before it is stopped the way cancelling it would and its run fails. Each restart gets the full time again. Defaults to no limit.
+			 */
+			timeoutMs?: number | null;
 			/** @description The title of the task. */
 			title?: string | null;
 		};
 		TaskRunLogLine: {
+			/**
+			 * Format: int32
+			 * @description The run's attempt the line was written in.
+			 */
+			attempt: number;
 			isStderr: boolean;
 			line: string;
 			runId: string;
@@ -227,17 +598,77 @@ export interface components {
 			| "Running"
 			| "Success"
 			| "Cancelled"
-			| "Failed";
+			| "Failed"
+			| "Blocked"
+			| "Interrupted";
 		TaskRunTreeNode: {
+			/**
+			 * Format: int32
+			 * @description Starts at 1 and goes up each time the run is restarted. Earlier attempts' logs are kept.
+			 */
+			attempt: number;
+			/** @description For a `Blocked` run, the task it was waiting on when that task's run failed, was cancelled or was blocked itself. */
+			blockedBy?: string | null;
 			children: components["schemas"]["TaskRunTreeNode"][];
 			cwd: string;
+			/**
+			 * Format: int32
+			 * @description The exit code of the run's process, once it has exited. One killed by a signal reports `128 + n`, like a shell.
+			 */
+			exitCode?: number | null;
+			/**
+			 * Format: int64
+			 * @description When the run's latest process exited, in milliseconds since the epoch.
+			 */
+			finishedAt?: number | null;
 			id: string;
+			/** @description The optional subtasks enabled for the invocation this run belongs to. */
+			includeTasks: string[];
 			parentRunId?: string | null;
+			/**
+			 * Format: int32
+			 * @description The process ID of the run's latest process, which is also its process group ID.
+			 */
+			pid?: number | null;
+			/** @description Whether the task's `ready` probe has passed since the run last started. */
+			ready: boolean;
+			/**
+			 * Format: int32
+			 * @description How many times the task's `restart` policy has restarted the run since it was last started by hand.
+			 */
+			restarts: number;
+			/** @description The signal that ended the run's process, such as `SIGKILL`. */
+			signal?: string | null;
+			/**
+			 * Format: int64
+			 * @description When the run's latest process started, in milliseconds since the epoch.
+			 */
+			startedAt?: number | null;
+			/** @description The run that started this one because it was waiting on this task. */
+			startedByRunId?: string | null;
 			status: components["schemas"]["TaskRunStatus"];
 			task: string;
+			/**
+			 * Format: int64
+			 * @description For a run stopped for taking too long, the limit it ran into in milliseconds: its task's `timeoutMs`, or the one its tree was started with.
+			 */
+			timedOutAfterMs?: number | null;
 			/** Format: int64 */
 			updatedAt: number;
 			waitingOn?: string | null;
+			/** @description The directory the task's command runs in, once its `cwd` is resolved. */
+			workingDir?: string | null;
+		};
+		ValidateConfigRequest: {
+			/** @example /Users/johndoe/documents/github/example-project */
+			cwd: string;
+		};
+		ValidateConfigResponse:
+			| components["schemas"]["ValidateConfigResponseBody"]
+			| components["schemas"]["ErrorResponse"];
+		ValidateConfigResponseBody: {
+			/** @description Every problem found, in document order. Empty when the config is valid. */
+			diagnostics: components["schemas"]["ConfigDiagnostic"][];
 		};
 	};
 	responses: never;
@@ -248,6 +679,96 @@ export interface components {
 }
 export type $defs = Record<string, never>;
 export interface operations {
+	validate_config: {
+		parameters: {
+			query: {
+				/** @description The current working directory to load the task config from */
+				cwd: string;
+			};
+			header?: never;
+			path?: never;
+			cookie?: never;
+		};
+		requestBody?: never;
+		responses: {
+			/** @description Success */
+			200: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ValidateConfigResponse"];
+				};
+			};
+			/** @description Not Found */
+			404: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+			/** @description Internal Server Error */
+			500: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+		};
+	};
+	search_logs: {
+		parameters: {
+			query: {
+				/** @description The project whose runs to search */
+				cwd: string;
+				/** @description Text to find anywhere in a line, ignoring case. At least three characters */
+				q: string;
+				/** @description Only search lines written by this task */
+				task?: string | null;
+				/** @description Only search lines written at or after this time, in milliseconds since the epoch */
+				since?: number | null;
+				/** @description Return at most this many matches, newest first. Defaults to 100, and at most 1000 */
+				limit?: number | null;
+			};
+			header?: never;
+			path?: never;
+			cookie?: never;
+		};
+		requestBody?: never;
+		responses: {
+			/** @description Success */
+			200: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["SearchLogsResponse"];
+				};
+			};
+			/** @description Bad Request */
+			400: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+			/** @description Internal Server Error */
+			500: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+		};
+	};
 	list_tasks: {
 		parameters: {
 			query: {
@@ -269,6 +790,15 @@ export interface operations {
 					"application/json": components["schemas"]["ListTasksResponse"];
 				};
 			};
+			/** @description Bad Request */
+			400: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
 			/** @description Not Found */
 			404: {
 				headers: {
@@ -353,6 +883,15 @@ export interface operations {
 					"application/json": components["schemas"]["RestartTaskResponse"];
 				};
 			};
+			/** @description Bad Request */
+			400: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
 			/** @description Not Found */
 			404: {
 				headers: {
@@ -497,12 +1036,9 @@ export interface operations {
 			};
 		};
 	};
-	get_task_run_logs: {
+	delete_task_run: {
 		parameters: {
-			query?: {
-				/** @description Whether to include logs from descendant task runs */
-				includeChildren?: boolean | null;
-			};
+			query?: never;
 			header?: never;
 			path: {
 				/** @description The task run id */
@@ -518,7 +1054,7 @@ export interface operations {
 					[name: string]: unknown;
 				};
 				content: {
-					"application/json": components["schemas"]["GetTaskRunLogsResponse"];
+					"application/json": components["schemas"]["DeleteTaskRunResponse"];
 				};
 			};
 			/** @description Not Found */
@@ -530,6 +1066,15 @@ export interface operations {
 					"application/json": components["schemas"]["ErrorResponse"];
 				};
 			};
+			/** @description Conflict */
+			409: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
 			/** @description Internal Server Error */
 			500: {
 				headers: {
@@ -541,4 +1086,366 @@ export interface operations {
 			};
 		};
 	};
+	exclude_task: {
+		parameters: {
+			query?: never;
+			header?: never;
+			path: {
+				/** @description The run of the optional subtask's parent */
+				run_id: string;
+			};
+			cookie?: never;
+		};
+		requestBody: {
+			content: {
+				"application/json": components["schemas"]["ExcludeTaskRequest"];
+			};
+		};
+		responses: {
+			/** @description Success */
+			200: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ExcludeTaskResponse"];
+				};
+			};
+			/** @description Bad Request */
+			400: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+			/** @description Not Found */
+			404: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+			/** @description Internal Server Error */
+			500: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+		};
+	};
+	include_task: {
+		parameters: {
+			query?: never;
+			header?: never;
+			path: {
+				/** @description The run of the optional subtask's parent */
+				run_id: string;
+			};
+			cookie?: never;
+		};
+		requestBody: {
+			content: {
+				"application/json": components["schemas"]["IncludeTaskRequest"];
+			};
+		};
+		responses: {
+			/** @description Success */
+			200: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["IncludeTaskResponse"];
+				};
+			};
+			/** @description Bad Request */
+			400: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+			/** @description Not Found */
+			404: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+			/** @description Internal Server Error */
+			500: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+		};
+	};
+	send_task_run_input: {
+		parameters: {
+			query?: never;
+			header?: never;
+			path: {
+				/** @description The running task run to write to */
+				run_id: string;
+			};
+			cookie?: never;
+		};
+		requestBody: {
+			content: {
+				"application/json": components["schemas"]["SendTaskRunInputRequest"];
+			};
+		};
+		responses: {
+			/** @description Success */
+			200: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["SendTaskRunInputResponse"];
+				};
+			};
+			/** @description Not Found */
+			404: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+			/** @description Conflict */
+			409: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+			/** @description Internal Server Error */
+			500: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+		};
+	};
+	get_task_run_logs: {
+		parameters: {
+			query?: {
+				/** @description Whether to include logs from descendant task runs */
+				includeChildren?: boolean | null;
+				/** @description Which attempt of the run to return logs from. Defaults to the latest */
+				attempt?: number | null;
+				/** @description Only return lines with a greater sequence */
+				afterSequence?: number | null;
+				/** @description Only return lines with a smaller sequence */
+				beforeSequence?: number | null;
+				/** @description Return at most this many lines, starting from the oldest that match */
+				limit?: number | null;
+				/** @description Return at most this many lines, ending with the newest that match. Cannot be combined with limit */
+				tail?: number | null;
+				/** @description Websocket only: the last line a reconnecting client already has. The lines after it are sent as log messages instead of a snapshot */
+				fromSequence?: number | null;
+			};
+			header?: never;
+			path: {
+				/** @description The task run id */
+				run_id: string;
+			};
+			cookie?: never;
+		};
+		requestBody?: never;
+		responses: {
+			/** @description Success */
+			200: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["GetTaskRunLogsResponse"];
+				};
+			};
+			/** @description Bad Request */
+			400: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+			/** @description Not Found */
+			404: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+			/** @description Internal Server Error */
+			500: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+		};
+	};
+	resize_task_run: {
+		parameters: {
+			query?: never;
+			header?: never;
+			path: {
+				/** @description A run of a task with `pty: true` */
+				run_id: string;
+			};
+			cookie?: never;
+		};
+		requestBody: {
+			content: {
+				"application/json": components["schemas"]["ResizeTaskRunRequest"];
+			};
+		};
+		responses: {
+			/** @description Success */
+			200: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ResizeTaskRunResponse"];
+				};
+			};
+			/** @description Bad Request */
+			400: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+			/** @description Not Found */
+			404: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+			/** @description Conflict */
+			409: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+			/** @description Internal Server Error */
+			500: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+		};
+	};
+	signal_task_run: {
+		parameters: {
+			query?: never;
+			header?: never;
+			path: {
+				/** @description The running task run to signal */
+				run_id: string;
+			};
+			cookie?: never;
+		};
+		requestBody: {
+			content: {
+				"application/json": components["schemas"]["SignalTaskRunRequest"];
+			};
+		};
+		responses: {
+			/** @description Success */
+			200: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["SignalTaskRunResponse"];
+				};
+			};
+			/** @description Bad Request */
+			400: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+			/** @description Not Found */
+			404: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+			/** @description Conflict */
+			409: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+			/** @description Internal Server Error */
+			500: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+			/** @description Not Implemented */
+			501: {
+				headers: {
+					[name: string]: unknown;
+				};
+				content: {
+					"application/json": components["schemas"]["ErrorResponse"];
+				};
+			};
+		};
+	};
 }